@@ -0,0 +1,225 @@
+use crate::{
+    Painted, RenderError, RenderOptions, Result, finish_pixels, font_context, load_document,
+    paint_document,
+};
+#[cfg(feature = "gif")]
+use image::{
+    Delay, Frame, ImageError, RgbaImage,
+    codecs::gif::{GifEncoder, Repeat},
+    error::{ParameterError, ParameterErrorKind},
+};
+
+/// Upper bound on frames per animation; each frame is a full layout and paint.
+pub const MAX_ANIMATION_FRAMES: usize = 120;
+
+/// `NeuQuant` sampling speed (1 = best quality, 30 = fastest); 10 is the `gif` crate's
+/// recommended balance.
+#[cfg(feature = "gif")]
+const GIF_QUANTIZER_SPEED: i32 = 10;
+
+/// Render HTML at each of `frame_times` (seconds) and assemble an animated PNG that loops
+/// forever at `fps` frames per second.
+///
+/// Every frame costs a full style/layout pass and a CPU paint of `width * height` pixels,
+/// so total time grows linearly with the frame count (capped at [`MAX_ANIMATION_FRAMES`]).
+///
+/// # Errors
+/// Returns an error if the frame count is out of range or the APNG encoding fails.
+pub fn render_html_to_apng_bytes(
+    html: &str,
+    width: u32,
+    height: u32,
+    scale: f64,
+    frame_times: &[f64],
+    fps: u16,
+) -> Result<Vec<u8>> {
+    let opts = RenderOptions::new(width, height).scale(scale);
+    render_html_apng(html, &opts, frame_times, fps)
+}
+
+/// [`render_html_to_apng_bytes`] driven by [`RenderOptions`]; `opts.animation_time` is
+/// replaced by each entry of `frame_times`.
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded, the frame count is out of range, or the
+/// APNG encoding fails.
+pub fn render_html_apng(
+    html: &str,
+    opts: &RenderOptions,
+    frame_times: &[f64],
+    fps: u16,
+) -> Result<Vec<u8>> {
+    let frames = render_frames(html, opts, frame_times)?;
+    let (Some(first), Ok(frame_count)) = (frames.first(), u32::try_from(frames.len())) else {
+        return Err(frame_count_error(frames.len()));
+    };
+
+    let mut buffer = Vec::new();
+    let mut encoder = png::Encoder::new(&mut buffer, first.width, first.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let apng_error = |source| RenderError::EncodeApng { source };
+    encoder.set_animated(frame_count, 0).map_err(apng_error)?;
+    encoder.set_frame_delay(1, fps.max(1)).map_err(apng_error)?;
+
+    let mut writer = encoder.write_header().map_err(apng_error)?;
+    for mut frame in frames {
+        finish_pixels(
+            &mut frame.rgba,
+            frame.width,
+            opts.premultiplied_alpha,
+            opts.dither,
+        );
+        writer.write_image_data(&frame.rgba).map_err(apng_error)?;
+    }
+    writer.finish().map_err(apng_error)?;
+
+    Ok(buffer)
+}
+
+/// Render HTML at each of `frame_times` (seconds) and assemble a looping animated GIF,
+/// showing each frame for `delay_centiseconds`.
+///
+/// GIF frames are limited to a 256-color palette, so each RGBA frame is quantized with
+/// `NeuQuant` and alpha becomes 1-bit transparency. Use [`render_html_to_apng_bytes`] when
+/// gradients or soft edges matter. Per-frame cost is the same as for APNG.
+///
+/// # Errors
+/// Returns an error if the frame count is out of range or the GIF encoding fails.
+#[cfg(feature = "gif")]
+pub fn render_html_to_gif_bytes(
+    html: &str,
+    width: u32,
+    height: u32,
+    scale: f64,
+    frame_times: &[f64],
+    delay_centiseconds: u16,
+) -> Result<Vec<u8>> {
+    let opts = RenderOptions::new(width, height).scale(scale);
+    render_html_gif(html, &opts, frame_times, delay_centiseconds)
+}
+
+/// [`render_html_to_gif_bytes`] driven by [`RenderOptions`]; `opts.animation_time` is
+/// replaced by each entry of `frame_times`.
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded, the frame count is out of range, or the GIF
+/// encoding fails.
+#[cfg(feature = "gif")]
+pub fn render_html_gif(
+    html: &str,
+    opts: &RenderOptions,
+    frame_times: &[f64],
+    delay_centiseconds: u16,
+) -> Result<Vec<u8>> {
+    let frames = render_frames(html, opts, frame_times)?;
+    let delay = Delay::from_numer_denom_ms(u32::from(delay_centiseconds) * 10, 1);
+    let gif_error = |source| RenderError::EncodeGif { source };
+
+    let mut buffer = Vec::new();
+    {
+        let mut encoder = GifEncoder::new_with_speed(&mut buffer, GIF_QUANTIZER_SPEED);
+        encoder.set_repeat(Repeat::Infinite).map_err(gif_error)?;
+        for mut frame in frames {
+            finish_pixels(&mut frame.rgba, frame.width, false, opts.dither);
+            let image =
+                RgbaImage::from_raw(frame.width, frame.height, frame.rgba).ok_or_else(|| {
+                    gif_error(ImageError::Parameter(ParameterError::from_kind(
+                        ParameterErrorKind::DimensionMismatch,
+                    )))
+                })?;
+            encoder
+                .encode_frame(Frame::from_parts(image, 0, 0, delay))
+                .map_err(gif_error)?;
+        }
+    }
+
+    Ok(buffer)
+}
+
+/// Paint one frame per entry of `frame_times`, parsing the document only once.
+pub(crate) fn render_frames(
+    html: &str,
+    opts: &RenderOptions,
+    frame_times: &[f64],
+) -> Result<Vec<Painted>> {
+    if frame_times.is_empty() || frame_times.len() > MAX_ANIMATION_FRAMES {
+        return Err(frame_count_error(frame_times.len()));
+    }
+
+    let font_ctx = font_context(opts)?;
+    let (mut doc, _warnings) = load_document(html, opts, font_ctx)?;
+
+    // A measured size is fixed by the first frame so every frame has the same size.
+    let mut canvas_size = None;
+    let mut frames = Vec::with_capacity(frame_times.len());
+    for &time in frame_times {
+        frames.push(paint_document(&mut doc, opts, time, &mut canvas_size)?);
+    }
+    Ok(frames)
+}
+
+fn frame_count_error(count: usize) -> RenderError {
+    RenderError::FrameCount {
+        count,
+        max: MAX_ANIMATION_FRAMES,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error as StdError, io::Cursor, result::Result as StdResult};
+
+    #[cfg(feature = "gif")]
+    use image::{AnimationDecoder as _, codecs::gif::GifDecoder};
+
+    use super::*;
+    type TestResult<T = ()> = StdResult<T, Box<dyn StdError>>;
+
+    const PULSE: &str = "<html><head><style>\
+        @keyframes pulse { from { opacity: 0 } to { opacity: 1 } }\
+        div { width: 32px; height: 32px; background: red; animation: pulse 1s linear }\
+        </style></head><body style=\"margin:0\"><div></div></body></html>";
+
+    #[test]
+    fn apng_contains_one_frame_per_time() -> TestResult {
+        let bytes = render_html_to_apng_bytes(PULSE, 32, 32, 1.0, &[0.0, 0.5, 1.0], 10)?;
+
+        let reader = png::Decoder::new(Cursor::new(bytes)).read_info()?;
+        let frames = reader
+            .info()
+            .animation_control()
+            .map(|control| control.num_frames);
+        if frames != Some(3) {
+            return Err(format!("expected 3 frames, got {frames:?}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "gif")]
+    fn gif_contains_one_frame_per_time() -> TestResult {
+        let bytes = render_html_to_gif_bytes(PULSE, 32, 32, 1.0, &[0.0, 0.5, 1.0], 10)?;
+
+        let decoder = GifDecoder::new(Cursor::new(bytes))?;
+        let frames = decoder.into_frames().collect_frames()?;
+        if frames.len() != 3 {
+            return Err(format!("expected 3 frames, got {}", frames.len()).into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn frame_count_is_capped() -> TestResult {
+        let times = vec![0.0; MAX_ANIMATION_FRAMES + 1];
+
+        match render_html_to_apng_bytes(PULSE, 8, 8, 1.0, &times, 10) {
+            Err(RenderError::FrameCount { count, .. }) if count == times.len() => Ok(()),
+            other => Err(format!(
+                "expected FrameCount error, got {:?}",
+                other.map(|bytes| bytes.len())
+            )
+            .into()),
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use std::{
+    fs, mem,
+    path::{Path, PathBuf},
+    result::Result as StdResult,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use blitz::{
+    dom::{BaseDocument, net::Resource},
+    traits::net::{BoxedHandler, Bytes, NetCallback, NetProvider, Request, Url},
+};
+
+#[cfg(feature = "fetch")]
+use crate::fetch::Fetcher;
+use crate::{RenderError, Result};
+
+/// Serves `file:` URLs from a single directory so templates can reference local images
+/// and stylesheets by relative path, or, with the `fetch` feature, same-origin URLs of a
+/// page fetched over HTTP.
+///
+/// Only `file:` URLs that resolve (after following symlinks) inside the base directory are
+/// read; every other scheme and any path outside it is left unloaded and recorded as
+/// skipped. Reads happen synchronously inside `fetch`, so the decoded resources are queued
+/// by the time the document finishes parsing and can be applied before layout.
+pub(crate) struct Assets {
+    root: AssetRoot,
+    loaded: Arc<LoadedResources>,
+    skipped: Mutex<Vec<String>>,
+}
+
+/// Where [`Assets`] reads resources from.
+enum AssetRoot {
+    /// Load nothing and only record what the document asked for.
+    None,
+    /// `file:` URLs inside this canonical directory.
+    Dir(PathBuf),
+    /// `http`/`https` URLs with the same origin as this page.
+    #[cfg(feature = "fetch")]
+    Page(Url, Fetcher),
+}
+
+#[derive(Default)]
+struct LoadedResources(Mutex<Vec<Resource>>);
+
+impl Assets {
+    /// # Errors
+    /// Returns [`RenderError::AssetDir`] if `base_dir` cannot be resolved.
+    pub(crate) fn new(base_dir: &Path) -> Result<Self> {
+        let base_dir = base_dir
+            .canonicalize()
+            .map_err(|source| RenderError::AssetDir {
+                source,
+                path: base_dir.to_path_buf(),
+            })?;
+        Ok(Self::with_root(AssetRoot::Dir(base_dir)))
+    }
+
+    /// A provider that loads nothing, to find out which resources a document references.
+    pub(crate) fn none() -> Self {
+        Self::with_root(AssetRoot::None)
+    }
+
+    /// Serve the same-origin resources of the fetched `page` through `fetcher`.
+    #[cfg(feature = "fetch")]
+    pub(crate) fn page(page: Url, fetcher: Fetcher) -> Self {
+        Self::with_root(AssetRoot::Page(page, fetcher))
+    }
+
+    fn with_root(root: AssetRoot) -> Self {
+        Self {
+            root,
+            loaded: Arc::default(),
+            skipped: Mutex::default(),
+        }
+    }
+
+    /// The URL relative references in the document are resolved against.
+    pub(crate) fn base_url(&self) -> Option<String> {
+        match &self.root {
+            AssetRoot::None => None,
+            AssetRoot::Dir(base_dir) => Url::from_directory_path(base_dir).ok().map(String::from),
+            #[cfg(feature = "fetch")]
+            AssetRoot::Page(page, _fetcher) => Some(page.to_string()),
+        }
+    }
+
+    /// One warning per requested resource that was not loaded, in request order.
+    pub(crate) fn warnings(&self) -> Vec<String> {
+        self.skipped
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn skip(&self, url: &Url, reason: &str) {
+        self.skipped
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(format!("resource {url} was not loaded: {reason}"));
+    }
+
+    /// Apply every queued resource to `doc`, including ones requested while applying
+    /// earlier resources (e.g. images referenced from a stylesheet).
+    pub(crate) fn load_into(&self, doc: &mut BaseDocument) {
+        loop {
+            let pending =
+                mem::take(&mut *self.loaded.0.lock().unwrap_or_else(PoisonError::into_inner));
+            if pending.is_empty() {
+                break;
+            }
+            for resource in pending {
+                doc.load_resource(resource);
+            }
+        }
+    }
+
+    /// Read the resource at `url`, or say why it is not served.
+    fn read(&self, url: &Url) -> StdResult<Vec<u8>, String> {
+        #[cfg(feature = "fetch")]
+        if let AssetRoot::Page(page, fetcher) = &self.root {
+            if url.origin() != page.origin() {
+                return Err("only same-origin resources of the page are loaded".to_owned());
+            }
+            return fetcher.get(url);
+        }
+        let path = self.resolve(url)?;
+        fs::read(path).map_err(|_read_error| "file could not be read".to_owned())
+    }
+
+    /// Map a `file:` URL to a path inside the base directory, or say why it is not served.
+    fn resolve(&self, url: &Url) -> StdResult<PathBuf, &'static str> {
+        let AssetRoot::Dir(base_dir) = &self.root else {
+            return Err("no base_dir is set");
+        };
+        if url.scheme() != "file" {
+            return Err("only file: URLs inside base_dir are loaded");
+        }
+        let path = url
+            .to_file_path()
+            .ok()
+            .and_then(|path| path.canonicalize().ok())
+            .ok_or("file not found")?;
+        if !path.starts_with(base_dir) {
+            return Err("outside base_dir");
+        }
+        Ok(path)
+    }
+}
+
+impl NetProvider<Resource> for Assets {
+    fn fetch(&self, doc_id: usize, request: Request, handler: BoxedHandler<Resource>) {
+        match self.read(&request.url) {
+            Ok(bytes) => {
+                let callback: Arc<dyn NetCallback<Resource>> =
+                    Arc::<LoadedResources>::clone(&self.loaded);
+                handler.bytes(doc_id, Bytes::from(bytes), callback);
+            }
+            Err(reason) => self.skip(&request.url, &reason),
+        }
+    }
+}
+
+impl NetCallback<Resource> for LoadedResources {
+    fn call(&self, _doc_id: usize, result: StdResult<Resource, Option<String>>) {
+        if let Ok(resource) = result {
+            self.0
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(resource);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error as StdError, result::Result as StdResult};
+
+    use tempfile::tempdir;
+
+    use super::*;
+    type TestResult<T = ()> = StdResult<T, Box<dyn StdError>>;
+
+    #[test]
+    fn resolve_only_serves_files_inside_base_dir() -> TestResult {
+        let root = tempdir()?;
+        let assets_dir = root.path().join("assets");
+        fs::create_dir_all(&assets_dir)?;
+        fs::write(assets_dir.join("logo.png"), b"png")?;
+        fs::write(root.path().join("secret.txt"), b"secret")?;
+
+        let assets = Assets::new(&assets_dir)?;
+        let base = Url::parse(&assets.base_url().ok_or("no base url")?)?;
+
+        if assets.resolve(&base.join("logo.png")?).is_err() {
+            return Err("file inside the base dir should resolve".into());
+        }
+        for escape in [
+            "../secret.txt",
+            "/etc/passwd",
+            "https://example.com/logo.png",
+        ] {
+            if assets.resolve(&base.join(escape)?).is_ok() {
+                return Err(format!("{escape} should not resolve").into());
+            }
+        }
+        Ok(())
+    }
+    #[test]
+    fn assets_without_base_dir_serve_nothing() -> TestResult {
+        let assets = Assets::none();
+
+        if assets.base_url().is_some() {
+            return Err("no base dir means no base url".into());
+        }
+        let url = Url::parse("file:///etc/hostname")?;
+        match assets.resolve(&url) {
+            Err("no base_dir is set") => Ok(()),
+            other => Err(format!("unexpected resolution: {other:?}").into()),
+        }
+    }
+}
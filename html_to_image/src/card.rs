@@ -0,0 +1,76 @@
+use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+use serde::Serialize;
+
+/// The bundled demo card template (`templates/card.html`).
+pub const CARD_TEMPLATE: &str = include_str!("../../templates/card.html");
+pub const DEFAULT_CARD_WIDTH: u32 = 420;
+pub const DEFAULT_CARD_HEIGHT: u32 = 155;
+
+const ICONS: &[&str] = &[
+    "★", "✨", "🚀", "🎉", "✅", "💎", "🌙", "☕", "⚡", "🔔", "🧠",
+];
+
+const MESSAGES: &[&str] = &[
+    "Your shiny Discord-sized card is ready. Crisp, compact, and screenshot-friendly.",
+    "New render dropped: clean edges, smooth gradients, zero browser drama.",
+    "Everything compiled. Nothing exploded. This is your sign to ship it. ✅",
+    "A small card with big energy. Have a great one. ✨",
+    "Pixels are aligned and vibes are immaculate.",
+];
+
+/// Template data for [`CARD_TEMPLATE`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CardData {
+    pub user: String,
+    pub icon: String,
+    pub message: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Build card data for `name` with a randomly picked icon and message.
+///
+/// The same `seed` always yields the same icon/message; `None` seeds from the OS.
+#[must_use]
+pub fn generate_card(seed: Option<u64>, name: &str) -> CardData {
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    };
+
+    let icon = ICONS.choose(&mut rng).copied().unwrap_or("★");
+    let message = MESSAGES
+        .choose(&mut rng)
+        .copied()
+        .unwrap_or("Your shiny Discord-sized card is ready.");
+
+    CardData {
+        user: name.to_owned(),
+        icon: icon.to_owned(),
+        message: message.to_owned(),
+        width: DEFAULT_CARD_WIDTH,
+        height: DEFAULT_CARD_HEIGHT,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error as StdError, result::Result as StdResult};
+
+    use super::*;
+    type TestResult<T = ()> = StdResult<T, Box<dyn StdError>>;
+
+    #[test]
+    fn generate_card_is_deterministic_for_seed() -> TestResult {
+        let first = generate_card(Some(42), "User");
+        let second = generate_card(Some(42), "User");
+
+        if first != second {
+            return Err(format!("seeded cards differ: {first:?} vs {second:?}").into());
+        }
+        if first.user != "User" {
+            return Err(format!("unexpected user: {}", first.user).into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+/// How two RGBA8 buffers differ, from [`compare_rgba`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiffReport {
+    /// Largest difference of any single channel, `0..=255`.
+    pub max_delta: u8,
+    /// Mean absolute difference over every channel of every pixel.
+    pub mean_delta: f64,
+    /// Pixels with at least one channel differing by more than the tolerance.
+    pub differing_pixels: usize,
+}
+
+/// Compare two RGBA8 buffers (e.g. a decoded golden PNG and a fresh render) channel by
+/// channel, returning `None` when no channel differs by more than `tolerance`.
+///
+/// Both buffers should use the same alpha convention. Buffers of different lengths never
+/// match: each pixel present in only one of them, including a trailing partial pixel,
+/// counts as differing by 255.
+#[must_use]
+#[allow(
+    clippy::cast_precision_loss,
+    reason = "The mean is a summary; buffers large enough to round are far beyond MAX_DIMENSION"
+)]
+pub fn compare_rgba(expected: &[u8], actual: &[u8], tolerance: u8) -> Option<DiffReport> {
+    let shared = expected.chunks_exact(4).zip(actual.chunks_exact(4));
+    let longer = expected.len().max(actual.len());
+    let extra_pixels = if expected.len() == actual.len() {
+        0
+    } else {
+        longer.div_ceil(4) - expected.len().min(actual.len()) / 4
+    };
+
+    let mut max_delta = if extra_pixels > 0 { u8::MAX } else { 0 };
+    let mut total_delta = extra_pixels as u64 * 4 * u64::from(u8::MAX);
+    let mut differing_pixels = extra_pixels;
+    for (want, got) in shared {
+        let pixel_delta = want
+            .iter()
+            .zip(got)
+            .map(|(want, got)| want.abs_diff(*got))
+            .inspect(|delta| total_delta += u64::from(*delta))
+            .max()
+            .unwrap_or(0);
+        max_delta = max_delta.max(pixel_delta);
+        if pixel_delta > tolerance {
+            differing_pixels += 1;
+        }
+    }
+
+    if differing_pixels == 0 {
+        return None;
+    }
+    let channels = longer.div_ceil(4) * 4;
+    Some(DiffReport {
+        max_delta,
+        mean_delta: total_delta as f64 / channels as f64,
+        differing_pixels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error as StdError, result::Result as StdResult};
+
+    use super::*;
+    type TestResult<T = ()> = StdResult<T, Box<dyn StdError>>;
+
+    #[test]
+    fn differences_within_tolerance_match() -> TestResult {
+        let golden = [10, 20, 30, 255, 0, 0, 0, 0];
+        let render = [12, 19, 30, 255, 0, 0, 0, 0];
+
+        for (other, tolerance) in [(golden, 0), (render, 2)] {
+            if let Some(report) = compare_rgba(&golden, &other, tolerance) {
+                return Err(format!("expected a match, got {report:?}").into());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn differences_beyond_tolerance_are_reported() -> TestResult {
+        let golden = [10, 20, 30, 255, 0, 0, 0, 0];
+        let render = [12, 20, 30, 255, 0, 0, 0, 8];
+
+        let report = compare_rgba(&golden, &render, 1).ok_or("expected a difference")?;
+
+        let expected = DiffReport {
+            max_delta: 8,
+            mean_delta: 10.0 / 8.0,
+            differing_pixels: 2,
+        };
+        if report != expected {
+            return Err(format!("unexpected report: {report:?}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn buffers_of_different_sizes_never_match() -> TestResult {
+        let report = compare_rgba(&[0; 8], &[0; 4], u8::MAX).ok_or("expected a difference")?;
+
+        if (report.max_delta, report.differing_pixels) != (u8::MAX, 1) {
+            return Err(format!("unexpected report: {report:?}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn a_trailing_partial_pixel_never_matches() -> TestResult {
+        let report = compare_rgba(&[0; 5], &[0; 4], 0).ok_or("expected a difference")?;
+
+        if (report.max_delta, report.differing_pixels) != (u8::MAX, 1) {
+            return Err(format!("unexpected report: {report:?}").into());
+        }
+        Ok(())
+    }
+}
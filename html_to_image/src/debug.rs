@@ -0,0 +1,252 @@
+use blitz::dom::BaseDocument;
+
+/// Fill opacity of each box, out of 255.
+const BOX_FILL_ALPHA: u8 = 40;
+const LABEL_BACKGROUND: [u8; 4] = [0, 0, 0, 180];
+const LABEL_FOREGROUND: [u8; 4] = [255, 255, 255, 255];
+const GLYPH_WIDTH: u32 = 3;
+const GLYPH_HEIGHT: u32 = 5;
+
+/// Box colors cycled by tree depth so nested elements are easy to tell apart.
+const BOX_COLORS: &[[u8; 3]] = &[
+    [239, 68, 68],
+    [34, 197, 94],
+    [59, 130, 246],
+    [234, 179, 8],
+    [168, 85, 247],
+    [6, 182, 212],
+];
+
+/// An element's border box in CSS pixels, relative to the document origin.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LayoutBox {
+    pub(crate) tag: String,
+    pub(crate) depth: usize,
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+}
+
+/// Walk the resolved layout tree and collect every element's box.
+pub(crate) fn layout_boxes(doc: &BaseDocument) -> Vec<LayoutBox> {
+    let mut boxes = Vec::new();
+    let mut stack = vec![(doc.root_node(), 0_usize, 0.0_f32, 0.0_f32)];
+
+    while let Some((node, depth, parent_x, parent_y)) = stack.pop() {
+        let layout = &node.final_layout;
+        let x = parent_x + layout.location.x;
+        let y = parent_y + layout.location.y;
+
+        let child_depth = if let Some(element) = node.element_data() {
+            if layout.size.width > 0.0 && layout.size.height > 0.0 {
+                boxes.push(LayoutBox {
+                    tag: element.name.local.to_string(),
+                    depth,
+                    x,
+                    y,
+                    width: layout.size.width,
+                    height: layout.size.height,
+                });
+            }
+            depth + 1
+        } else {
+            depth
+        };
+
+        for child_id in node.children.iter().rev() {
+            if let Some(child) = doc.get_node(*child_id) {
+                stack.push((child, child_depth, x, y));
+            }
+        }
+    }
+
+    boxes
+}
+
+/// Overlay translucent boxes, outlines, and tag labels onto a premultiplied RGBA8 buffer.
+pub(crate) fn draw_layout_boxes(
+    rgba: &mut [u8],
+    width: u32,
+    height: u32,
+    boxes: &[LayoutBox],
+    scale: f64,
+) {
+    let mut canvas = Canvas {
+        rgba,
+        width,
+        height,
+    };
+
+    for layout_box in boxes {
+        let [red, green, blue] = BOX_COLORS
+            .get(layout_box.depth % BOX_COLORS.len())
+            .copied()
+            .unwrap_or([255, 0, 0]);
+        let left = to_pixel(layout_box.x, scale);
+        let top = to_pixel(layout_box.y, scale);
+        let right = to_pixel(layout_box.x + layout_box.width, scale);
+        let bottom = to_pixel(layout_box.y + layout_box.height, scale);
+
+        canvas.fill(left, top, right, bottom, [red, green, blue, BOX_FILL_ALPHA]);
+        let outline = [red, green, blue, u8::MAX];
+        canvas.fill(left, top, right, top + 1, outline);
+        canvas.fill(left, bottom - 1, right, bottom, outline);
+        canvas.fill(left, top, left + 1, bottom, outline);
+        canvas.fill(right - 1, top, right, bottom, outline);
+
+        canvas.label(left + 1, top + 1, &layout_box.tag);
+    }
+}
+
+fn to_pixel(css: f32, scale: f64) -> i64 {
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "Coordinates are bounded by the canvas size before indexing"
+    )]
+    let pixel = (f64::from(css) * scale).round() as i64;
+    pixel
+}
+
+struct Canvas<'buffer> {
+    rgba: &'buffer mut [u8],
+    width: u32,
+    height: u32,
+}
+
+impl Canvas<'_> {
+    /// Source-over blend a straight-alpha color into `[left, right) x [top, bottom)`.
+    fn fill(&mut self, left: i64, top: i64, right: i64, bottom: i64, color: [u8; 4]) {
+        let left = left.clamp(0, i64::from(self.width));
+        let right = right.clamp(0, i64::from(self.width));
+        let top = top.clamp(0, i64::from(self.height));
+        let bottom = bottom.clamp(0, i64::from(self.height));
+
+        for y in top..bottom {
+            for x in left..right {
+                self.blend(x, y, color);
+            }
+        }
+    }
+
+    fn blend(&mut self, x: i64, y: i64, [red, green, blue, alpha]: [u8; 4]) {
+        let Ok(offset) = usize::try_from((y * i64::from(self.width) + x) * 4) else {
+            return;
+        };
+        let Some(pixel) = self.rgba.get_mut(offset..offset + 4) else {
+            return;
+        };
+
+        let src_alpha = u16::from(alpha);
+        let inverse = 255 - src_alpha;
+        for (channel, source) in pixel.iter_mut().zip([red, green, blue, u8::MAX]) {
+            let source = u16::from(source) * src_alpha / 255;
+            let blended = source + u16::from(*channel) * inverse / 255;
+            *channel = u8::try_from(blended).unwrap_or(u8::MAX);
+        }
+    }
+
+    fn label(&mut self, left: i64, top: i64, text: &str) {
+        let glyph_count = i64::try_from(text.chars().count()).unwrap_or(0);
+        let advance = i64::from(GLYPH_WIDTH + 1);
+        self.fill(
+            left,
+            top,
+            left + glyph_count * advance + 1,
+            top + i64::from(GLYPH_HEIGHT) + 2,
+            LABEL_BACKGROUND,
+        );
+
+        let mut x = left + 1;
+        for ch in text.chars() {
+            if let Some(rows) = glyph(ch) {
+                for (row, bits) in (0_i64..).zip(rows) {
+                    for column in 0..i64::from(GLYPH_WIDTH) {
+                        if bits & (0b100 >> column) != 0 {
+                            self.blend(x + column, top + 1 + row, LABEL_FOREGROUND);
+                        }
+                    }
+                }
+            }
+            x += advance;
+        }
+    }
+}
+
+/// A 3x5 bitmap glyph (one byte per row, high bit on the left) for tag-name characters.
+fn glyph(ch: char) -> Option<[u8; 5]> {
+    let rows = match ch.to_ascii_lowercase() {
+        'a' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'b' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'c' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'd' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'e' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'f' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'g' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'h' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'i' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'j' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'k' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'l' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'm' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'n' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'o' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'p' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'r' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        's' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        't' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'u' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'v' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'w' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'x' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        _ => return None,
+    };
+    Some(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error as StdError, result::Result as StdResult};
+
+    use super::*;
+    type TestResult<T = ()> = StdResult<T, Box<dyn StdError>>;
+
+    #[test]
+    fn draw_layout_boxes_marks_box_pixels() -> TestResult {
+        let mut rgba = vec![0_u8; 32 * 16 * 4];
+        let boxes = [LayoutBox {
+            tag: "div".to_owned(),
+            depth: 0,
+            x: 2.0,
+            y: 2.0,
+            width: 20.0,
+            height: 10.0,
+        }];
+
+        draw_layout_boxes(&mut rgba, 32, 16, &boxes, 1.0);
+
+        let outline = rgba.get((2 * 32 + 10) * 4..(2 * 32 + 10) * 4 + 4);
+        if outline != Some(&[239, 68, 68, 255][..]) {
+            return Err(format!("outline pixel not drawn: {outline:?}").into());
+        }
+        let outside = rgba.get((15 * 32 + 30) * 4..(15 * 32 + 30) * 4 + 4);
+        if outside != Some(&[0, 0, 0, 0][..]) {
+            return Err(format!("pixel outside the box changed: {outside:?}").into());
+        }
+        Ok(())
+    }
+}
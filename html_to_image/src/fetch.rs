@@ -0,0 +1,175 @@
+use std::{io::Read as _, result::Result as StdResult, time::Duration};
+
+use blitz::traits::net::Url;
+use reqwest::blocking::{Client, Response};
+
+use crate::{
+    RenderError, RenderOptions, RenderOutput, Result, assets::Assets, render_output_with_assets,
+};
+
+/// How long [`render_url`] waits for each request by default.
+pub const DEFAULT_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Largest page or asset [`render_url`] downloads by default.
+pub const DEFAULT_MAX_FETCH_BYTES: usize = 0x0080_0000; // 8 MiB
+
+/// Bounds on every HTTP request [`render_url`] makes, for the page and its assets alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FetchLimits {
+    /// Give up on a request after this long, including connecting and reading the body.
+    pub timeout: Duration,
+    /// Reject responses larger than this many bytes.
+    pub max_bytes: usize,
+}
+
+impl Default for FetchLimits {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_FETCH_TIMEOUT,
+            max_bytes: DEFAULT_MAX_FETCH_BYTES,
+        }
+    }
+}
+
+/// Fetch the HTML page at `url` over `http`/`https` and render it as
+/// [`crate::render_html_output`] would.
+///
+/// Relative references in the page resolve against `url`. Same-origin images and
+/// stylesheets are fetched under the same `limits`; others are skipped (and reported with
+/// `opts.collect_warnings`). `opts.base_dir` is ignored. The page is decoded as UTF-8.
+///
+/// This blocks on the network; from async code, call it on a blocking thread.
+///
+/// # Errors
+/// Returns [`RenderError::Fetch`] if `url` is not `http`/`https`, the request fails or
+/// times out, the server answers with an error status, or the page exceeds
+/// `limits.max_bytes`. Otherwise fails like [`crate::render_html_output`].
+pub fn render_url(url: &str, opts: &RenderOptions, limits: FetchLimits) -> Result<RenderOutput> {
+    let fetch_error = |reason: String| RenderError::Fetch {
+        url: url.to_owned(),
+        reason,
+    };
+    let page = Url::parse(url).map_err(|err| fetch_error(err.to_string()))?;
+    let fetcher = Fetcher::new(limits).map_err(fetch_error)?;
+    let html = fetcher.get(&page).map_err(fetch_error)?;
+    let html = String::from_utf8_lossy(&html);
+
+    render_output_with_assets(&html, opts, Some(Assets::page(page, fetcher)))
+}
+
+/// Blocking HTTP client restricted to `http`/`https` with a response size cap.
+pub(crate) struct Fetcher {
+    client: Client,
+    max_bytes: usize,
+}
+
+impl Fetcher {
+    fn new(limits: FetchLimits) -> StdResult<Self, String> {
+        let client = Client::builder()
+            .timeout(limits.timeout)
+            .build()
+            .map_err(|err| err.to_string())?;
+        Ok(Self {
+            client,
+            max_bytes: limits.max_bytes,
+        })
+    }
+
+    /// Download `url`, or say why it could not be.
+    pub(crate) fn get(&self, url: &Url) -> StdResult<Vec<u8>, String> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("only http and https URLs are fetched".to_owned());
+        }
+        let too_large = || format!("response is larger than {} bytes", self.max_bytes);
+        let response = self
+            .client
+            .get(url.as_str())
+            .send()
+            .and_then(Response::error_for_status)
+            .map_err(|err| err.to_string())?;
+        let cap = u64::try_from(self.max_bytes).unwrap_or(u64::MAX);
+        if response.content_length().is_some_and(|len| len > cap) {
+            return Err(too_large());
+        }
+
+        let mut body = Vec::new();
+        response
+            .take(cap.saturating_add(1))
+            .read_to_end(&mut body)
+            .map_err(|err| err.to_string())?;
+        if body.len() > self.max_bytes {
+            return Err(too_large());
+        }
+        Ok(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        error::Error as StdError,
+        io::{BufRead as _, BufReader, Write as _},
+        net::TcpListener,
+        result::Result as StdResult,
+        thread::{self, JoinHandle},
+    };
+
+    use super::*;
+    type TestResult<T = ()> = StdResult<T, Box<dyn StdError>>;
+
+    /// Answer one HTTP request on a free local port with `body`, returning the page URL.
+    fn serve_once(body: &'static str) -> TestResult<(String, JoinHandle<()>)> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}/card.html", listener.local_addr()?);
+        let server = thread::spawn(move || {
+            if let Ok((mut stream, _peer)) = listener.accept() {
+                // Consume the request headers up to the blank line that ends them.
+                let mut request = BufReader::new(&stream);
+                let mut line = String::new();
+                while request.read_line(&mut line).is_ok_and(|len| len > 2) {
+                    line.clear();
+                }
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\ncontent-type: text/html\r\ncontent-length: {}\r\n\
+                     connection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .ok();
+            }
+        });
+        Ok((url, server))
+    }
+
+    #[test]
+    fn render_url_renders_the_fetched_page() -> TestResult {
+        let (url, server) = serve_once("<html><body><div>remote</div></body></html>")?;
+
+        let output = render_url(&url, &RenderOptions::new(32, 16), FetchLimits::default())?;
+        server.join().map_err(|_panic| "server thread panicked")?;
+
+        if (output.width, output.height) != (32, 16) {
+            return Err(format!("unexpected size {}x{}", output.width, output.height).into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn render_url_rejects_oversized_pages_and_other_schemes() -> TestResult {
+        let (url, server) = serve_once("<html><body>far too long for the cap</body></html>")?;
+        let limits = FetchLimits {
+            max_bytes: 8,
+            ..FetchLimits::default()
+        };
+
+        let oversized = render_url(&url, &RenderOptions::new(32, 16), limits);
+        server.join().map_err(|_panic| "server thread panicked")?;
+        let local = render_url("file:///etc/hostname", &RenderOptions::new(32, 16), limits);
+
+        for result in [oversized, local] {
+            if !matches!(result, Err(RenderError::Fetch { .. })) {
+                return Err(format!("expected Fetch, got {:?}", result.map(|_| ())).into());
+            }
+        }
+        Ok(())
+    }
+}
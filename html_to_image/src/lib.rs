@@ -1,41 +1,116 @@
 use std::{
-    fs, io,
+    borrow::Cow,
+    fs,
+    io::{self, Write},
+    panic,
     path::{Path, PathBuf},
     result::Result as StdResult,
-    sync::Arc,
+    slice,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError},
+    },
+    thread,
+    time::Duration,
 };
 
 use anyrender::ImageRenderer;
 use anyrender_vello_cpu::VelloCpuImageRenderer;
-use blitz::{dom::DocumentConfig, html::HtmlDocument, paint};
-use image::{ImageEncoder, codecs::png::PngEncoder};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use blitz::{
+    dom::{BaseDocument, DocumentConfig, net::Resource},
+    html::HtmlDocument,
+    paint,
+    traits::{
+        net::NetProvider,
+        shell::{ColorScheme, Viewport},
+    },
+};
+#[cfg(feature = "jpeg")]
+use image::codecs::jpeg::JpegEncoder;
+#[cfg(feature = "webp")]
+use image::codecs::webp::WebPEncoder;
+use image::{
+    ImageBuffer, ImageEncoder, Rgba,
+    codecs::{bmp::BmpEncoder, tiff::TiffEncoder},
+    imageops::{self, FilterType},
+};
 use linebender_resource_handle::Blob;
-use parley::FontContext;
+use parley::{
+    FontContext,
+    fontique::{FamilyId, GenericFamily},
+};
 use serde::Serialize;
 use thiserror::Error;
 
+use crate::assets::Assets;
+
+mod animation;
+mod assets;
+mod card;
+mod compare;
+mod debug;
+#[cfg(feature = "fetch")]
+mod fetch;
+mod options;
+mod renderer;
+mod sanitize;
+mod svg;
+
+pub use animation::{MAX_ANIMATION_FRAMES, render_html_apng, render_html_to_apng_bytes};
+#[cfg(feature = "gif")]
+pub use animation::{render_html_gif, render_html_to_gif_bytes};
+pub use card::{CARD_TEMPLATE, CardData, DEFAULT_CARD_HEIGHT, DEFAULT_CARD_WIDTH, generate_card};
+pub use compare::{DiffReport, compare_rgba};
+#[cfg(feature = "fetch")]
+pub use fetch::{DEFAULT_FETCH_TIMEOUT, DEFAULT_MAX_FETCH_BYTES, FetchLimits, render_url};
+pub use options::{AutoEscapeMode, CompressionLevel, OutputFormat, RenderOptions, ResizeFilter};
+pub use renderer::Renderer;
+pub use sanitize::sanitize_html;
+pub use svg::render_html_to_svg_string;
+
 pub const DEFAULT_SCALE: f64 = 1.0;
+pub const DEFAULT_DEVICE_PIXEL_RATIO: f64 = 1.0;
 pub const DEFAULT_ANIMATION_TIME: f64 = 5.0;
+#[cfg(feature = "webp")]
+pub const DEFAULT_WEBP_QUALITY: f32 = 80.0;
+/// Upper bound for a measured (`height == 0`) output height, in pixels.
+pub const MAX_DIMENSION: u32 = 4096;
+/// Largest [`RenderOptions::supersample`] factor; higher values are clamped to it.
+pub const MAX_SUPERSAMPLE: u8 = 4;
+
+/// Appended to documents rendered with `width == 0`: an absolutely positioned body is
+/// sized to fit its content, so its laid-out width is the content's natural width.
+const SHRINK_TO_FIT_STYLE: &str = "<style>body { position: absolute }</style>";
+/// How often [`render_html_bytes_cancellable`] checks its cancel flag.
+pub const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 #[derive(Debug, Error)]
 pub enum RenderError {
     #[error("failed to read template file: {path}")]
     ReadTemplate { source: io::Error, path: PathBuf },
-    #[error("failed to register template")]
+    #[error("failed to register template{}", template_location(.template_name.as_deref(), *.line))]
     RegisterTemplate {
         #[source]
         source: minijinja::Error,
+        line: Option<usize>,
+        template_name: Option<String>,
     },
     #[error("failed to load template from environment")]
     LoadTemplate {
         #[source]
         source: minijinja::Error,
     },
-    #[error("failed to render template")]
+    #[error("failed to render template{}", template_location(.template_name.as_deref(), *.line))]
     RenderTemplate {
         #[source]
         source: minijinja::Error,
+        line: Option<usize>,
+        template_name: Option<String>,
     },
+    #[error("template output of {size} bytes exceeds the limit of {limit} bytes")]
+    TemplateTooLarge { size: usize, limit: usize },
     #[error("failed to create output directory: {path}")]
     CreateOutputDir { source: io::Error, path: PathBuf },
     #[error("failed to write png: {path}")]
@@ -43,14 +118,120 @@ pub enum RenderError {
         source: image::ImageError,
         path: PathBuf,
     },
+    #[error("failed to write png to the output stream")]
+    StreamPng {
+        #[source]
+        source: io::Error,
+    },
     #[error("failed to read font at {path}")]
     ReadFont { source: io::Error, path: PathBuf },
     #[error("no loadable fonts found at {path}")]
     RegisterFont { path: PathBuf },
+    #[error("no loadable fonts found in font data #{index}")]
+    RegisterFontBytes { index: usize },
+    #[error("html is empty")]
+    EmptyHtml,
+    #[error("failed to encode jpeg")]
+    EncodeJpeg {
+        #[source]
+        source: image::ImageError,
+    },
+    #[error("failed to encode bmp")]
+    EncodeBmp {
+        #[source]
+        source: image::ImageError,
+    },
+    #[error("failed to encode tiff")]
+    EncodeTiff {
+        #[source]
+        source: image::ImageError,
+    },
+    #[error("failed to encode webp: {reason}")]
+    EncodeWebp { reason: String },
+    #[error("failed to read asset directory: {path}")]
+    AssetDir { source: io::Error, path: PathBuf },
+    #[error("frame count {count} must be between 1 and {max}")]
+    FrameCount { count: usize, max: usize },
+    #[error("failed to encode apng")]
+    EncodeApng {
+        #[source]
+        source: png::EncodingError,
+    },
+    #[error("failed to encode gif")]
+    EncodeGif {
+        #[source]
+        source: image::ImageError,
+    },
+    #[error("measured content height {height}px exceeds the maximum of {max}px")]
+    ContentTooTall { height: f64, max: u32 },
+    #[error("{width}x{height} is too large to allocate a pixel buffer for")]
+    DimensionsTooLarge { width: u32, height: u32 },
+    #[error("invalid CSS selector: {selector:?}")]
+    InvalidSelector { selector: String },
+    #[error("selector {selector:?} matched no rendered element")]
+    SelectorNotFound { selector: String },
+    #[error(
+        "crop {width}x{height} at ({x}, {y}) does not fit the {image_width}x{image_height} render"
+    )]
+    CropOutOfBounds {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        image_width: u32,
+        image_height: u32,
+    },
+    #[error("device pixel ratio must be a positive number, got {ratio}")]
+    InvalidDevicePixelRatio { ratio: f64 },
+    #[error("rendering did not finish within {timeout:?}")]
+    Timeout { timeout: Duration },
+    #[error("rendering was cancelled")]
+    Cancelled,
+    #[error("failed to fetch {url}: {reason}")]
+    Fetch { url: String, reason: String },
+    #[error("html cannot be emitted as svg: {reason}")]
+    NotVectorizable { reason: String },
+    #[error("emoji rendering needs html_to_image built with the `emoji` feature")]
+    EmojiNotBundled,
+    #[error("failed to start render worker thread")]
+    SpawnWorker {
+        #[source]
+        source: io::Error,
+    },
+    #[error("render worker exited without sending a result")]
+    WorkerExited,
 }
 
 pub type Result<T> = StdResult<T, RenderError>;
 
+impl RenderError {
+    fn register_template(source: minijinja::Error) -> Self {
+        Self::RegisterTemplate {
+            line: source.line(),
+            template_name: source.name().map(str::to_owned),
+            source,
+        }
+    }
+
+    fn render_template(source: minijinja::Error) -> Self {
+        Self::RenderTemplate {
+            line: source.line(),
+            template_name: source.name().map(str::to_owned),
+            source,
+        }
+    }
+}
+
+/// ` (in name:line)` suffix for template errors, or empty when `MiniJinja` gave no location.
+fn template_location(name: Option<&str>, line: Option<usize>) -> String {
+    match (name, line) {
+        (Some(name), Some(line)) => format!(" (in {name}:{line})"),
+        (Some(name), None) => format!(" (in {name})"),
+        (None, Some(line)) => format!(" (at line {line})"),
+        (None, None) => String::new(),
+    }
+}
+
 /// Load an HTML template from disk.
 ///
 /// # Errors
@@ -64,26 +245,260 @@ pub fn load_template(path: &Path) -> Result<String> {
 
 /// Render the `MiniJinja` template into HTML using arbitrary serializable data.
 ///
+/// Values are HTML-escaped; see [`render_template_with_escape`] for other output. To insert
+/// a trusted, already-sanitized HTML fragment as markup, mark it with `MiniJinja`'s built-in
+/// `safe` filter: `{{ snippet | safe }}`.
+///
+/// `safe` turns off escaping for that value entirely, so never apply it to user input: a
+/// `<script>`, `<style>`, or `<link>` in the value is rendered (and its URLs resolved) like
+/// the template's own markup.
+///
 /// # Errors
 /// Returns an error if the template cannot be registered or rendered.
 pub fn render_template<T: Serialize>(template: &str, data: &T) -> Result<String> {
+    render_template_with(template, data, |_| {})
+}
+
+/// Expand `template` with `data` exactly as [`render_to_png`] does before painting, and
+/// return that HTML instead of rendering it.
+///
+/// Handy when a template renders wrong: this is the markup Blitz receives, the same the
+/// CLI prints for `--dump-html`.
+///
+/// # Errors
+/// Returns an error if the template cannot be registered or rendered.
+pub fn render_template_only<T: Serialize>(template: &str, data: &T) -> Result<String> {
+    render_template(template, data)
+}
+
+/// Render a `MiniJinja` template with the given auto-escaping instead of HTML's.
+///
+/// Use [`AutoEscapeMode::None`] for plain text, where HTML entities would corrupt the output.
+///
+/// # Errors
+/// Returns an error if the template cannot be registered or rendered.
+pub fn render_template_with_escape<T: Serialize>(
+    template: &str,
+    data: &T,
+    escape: AutoEscapeMode,
+) -> Result<String> {
+    render_template_in(template, data, escape, usize::MAX, |_| {})
+}
+
+/// Render a `MiniJinja` template, failing on undefined variables instead of printing
+/// nothing.
+///
+/// A misspelled `{{ nmae }}` becomes a [`RenderError::RenderTemplate`] naming the line, rather
+/// than a silent blank. Optional values can still be tested with `{% if name is defined %}`
+/// or given a fallback with `{{ name | default("") }}`.
+///
+/// # Errors
+/// Returns an error if the template cannot be registered or rendered, including when it
+/// uses a variable missing from `data`.
+pub fn render_template_strict<T: Serialize>(template: &str, data: &T) -> Result<String> {
+    render_template_with(template, data, |env| {
+        env.set_undefined_behavior(minijinja::UndefinedBehavior::Strict);
+    })
+}
+
+/// Render a `MiniJinja` template after letting `setup` customize the environment.
+///
+/// `setup` runs after HTML auto-escaping is enabled and before the template is parsed, so
+/// it can register filters (`env.add_filter(...)`), functions, or globals, or change the
+/// undefined behavior as [`render_template_strict`] does.
+///
+/// # Errors
+/// Returns an error if the template cannot be registered or rendered.
+pub fn render_template_with<T, F>(template: &str, data: &T, setup: F) -> Result<String>
+where
+    T: Serialize,
+    F: FnOnce(&mut minijinja::Environment<'_>),
+{
+    render_template_in(template, data, AutoEscapeMode::Html, usize::MAX, setup)
+}
+
+/// [`render_template_with`], giving up once the HTML grows past `max_len` bytes.
+///
+/// Rendering stops as soon as the limit is crossed, so a template that loops millions of
+/// times fails fast instead of expanding to gigabytes of HTML first.
+///
+/// # Errors
+/// Returns [`RenderError::TemplateTooLarge`] when the output exceeds `max_len`; its `size`
+/// is how much had been produced when rendering stopped. Otherwise fails like
+/// [`render_template_with`].
+pub fn render_template_with_limit<T, F>(
+    template: &str,
+    data: &T,
+    max_len: usize,
+    setup: F,
+) -> Result<String>
+where
+    T: Serialize,
+    F: FnOnce(&mut minijinja::Environment<'_>),
+{
+    render_template_in(template, data, AutoEscapeMode::Html, max_len, setup)
+}
+
+/// Shared body of the `render_template*` functions.
+fn render_template_in<T, F>(
+    template: &str,
+    data: &T,
+    escape: AutoEscapeMode,
+    max_len: usize,
+    setup: F,
+) -> Result<String>
+where
+    T: Serialize,
+    F: FnOnce(&mut minijinja::Environment<'_>),
+{
     let mut env = minijinja::Environment::new();
 
-    // Treat this as HTML and escape user-provided values safely.
-    env.set_auto_escape_callback(|_| minijinja::AutoEscape::Html);
+    escape.apply(&mut env);
+    setup(&mut env);
 
     env.add_template("card.html", template)
-        .map_err(|source| RenderError::RegisterTemplate { source })?;
+        .map_err(RenderError::register_template)?;
 
-    let html = env
+    let mut output = BoundedOutput::new(max_len);
+    let rendered = env
         .get_template("card.html")
         .map_err(|source| RenderError::LoadTemplate { source })?
+        .render_to_write(data, &mut output)
+        .map(drop);
+    if output.size > max_len {
+        return Err(RenderError::TemplateTooLarge {
+            size: output.size,
+            limit: max_len,
+        });
+    }
+    rendered.map_err(RenderError::render_template)?;
+
+    Ok(String::from_utf8_lossy(&output.html).into_owned())
+}
+
+/// Template output buffer that refuses writes once `limit` bytes would be exceeded.
+struct BoundedOutput {
+    html: Vec<u8>,
+    /// Bytes offered so far, including the write that crossed the limit.
+    size: usize,
+    limit: usize,
+}
+
+impl BoundedOutput {
+    const fn new(limit: usize) -> Self {
+        Self {
+            html: Vec::new(),
+            size: 0,
+            limit,
+        }
+    }
+}
+
+impl Write for BoundedOutput {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.size = self.size.saturating_add(bytes.len());
+        if self.size > self.limit {
+            return Err(io::Error::other("template output limit exceeded"));
+        }
+        self.html.extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Render the template `entry` from `template_dir`, resolving `{% include %}`,
+/// `{% extends %}`, and `{% import %}` against the same directory.
+///
+/// Template names are `/`-separated paths relative to `template_dir`. Names with `.`-prefixed
+/// segments (including `..`) are rejected, as is anything that resolves outside the
+/// directory through a symlink.
+///
+/// # Errors
+/// Returns an error if `template_dir` cannot be read, a template is missing or outside the
+/// directory, or rendering fails.
+pub fn render_template_dir<T: Serialize>(
+    template_dir: &Path,
+    entry: &str,
+    data: &T,
+) -> Result<String> {
+    let base = template_dir
+        .canonicalize()
+        .map_err(|source| RenderError::ReadTemplate {
+            source,
+            path: template_dir.to_path_buf(),
+        })?;
+
+    let mut env = minijinja::Environment::new();
+    env.set_auto_escape_callback(|_| minijinja::AutoEscape::Html);
+    env.set_loader(move |name| load_template_within(&base, name));
+
+    let html = env
+        .get_template(entry)
+        .map_err(|source| RenderError::LoadTemplate { source })?
         .render(data)
-        .map_err(|source| RenderError::RenderTemplate { source })?;
+        .map_err(RenderError::render_template)?;
 
     Ok(html)
 }
 
+/// `MiniJinja` loader that only reads templates located under `base` (already canonical).
+fn load_template_within(base: &Path, name: &str) -> StdResult<Option<String>, minijinja::Error> {
+    let outside = || {
+        minijinja::Error::new(
+            minijinja::ErrorKind::InvalidOperation,
+            format!("template {name:?} is outside the template directory"),
+        )
+    };
+
+    let mut candidate = base.to_path_buf();
+    for segment in name.split('/') {
+        if segment.is_empty() || segment.starts_with('.') || segment.contains(['\\', ':']) {
+            return Err(outside());
+        }
+        candidate.push(segment);
+    }
+
+    let resolved = match candidate.canonicalize() {
+        Ok(resolved) => resolved,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(minijinja::Error::new(
+                minijinja::ErrorKind::InvalidOperation,
+                "could not read template",
+            )
+            .with_source(err));
+        }
+    };
+    if !resolved.starts_with(base) {
+        return Err(outside());
+    }
+
+    fs::read_to_string(resolved).map(Some).map_err(|err| {
+        minijinja::Error::new(
+            minijinja::ErrorKind::InvalidOperation,
+            "could not read template",
+        )
+        .with_source(err)
+    })
+}
+
+/// Reject HTML that is empty or whitespace-only.
+///
+/// Rendering such input silently yields a blank canvas. Call this after [`render_template`]
+/// when a blank result means a missing body rather than an intentionally empty image.
+///
+/// # Errors
+/// Returns [`RenderError::EmptyHtml`] if `html` contains only whitespace.
+pub fn ensure_html_not_empty(html: &str) -> Result<()> {
+    if html.trim().is_empty() {
+        return Err(RenderError::EmptyHtml);
+    }
+    Ok(())
+}
+
 /// Render raw HTML to a PNG file.
 ///
 /// # Errors
@@ -97,14 +512,12 @@ pub fn render_html_to_png(
     current_time_for_animations: f64,
     font_paths: &[PathBuf],
 ) -> Result<()> {
-    let rgba = render_html_to_rgba(
-        html,
-        width,
-        height,
-        scale,
-        current_time_for_animations,
-        font_paths,
-    )?;
+    let opts = RenderOptions::new(width, height)
+        .scale(scale)
+        .animation_time(current_time_for_animations)
+        .font_paths(font_paths.to_vec());
+    let mut painted = render_premultiplied(html, &opts)?;
+    unpremultiply_alpha(&mut painted.rgba);
 
     if let Some(parent) = out_path.parent()
         && !parent.as_os_str().is_empty()
@@ -115,192 +528,2653 @@ pub fn render_html_to_png(
         })?;
     }
 
-    image::save_buffer(out_path, &rgba, width, height, image::ColorType::Rgba8).map_err(
-        |source| RenderError::WritePng {
-            source,
-            path: out_path.to_path_buf(),
-        },
-    )?;
+    image::save_buffer(
+        out_path,
+        &painted.rgba,
+        painted.width,
+        painted.height,
+        image::ColorType::Rgba8,
+    )
+    .map_err(|source| RenderError::WritePng {
+        source,
+        path: out_path.to_path_buf(),
+    })?;
 
     Ok(())
 }
 
-/// Render raw HTML to PNG bytes (in-memory).
+/// Render raw HTML to PNG bytes as described by `opts`.
 ///
-/// This avoids filesystem I/O and is useful for HTTP responses.
+/// This is the entry point the positional `render_html_to_png_*` helpers wrap.
 ///
 /// # Errors
 /// Returns an error if fonts cannot be loaded or the PNG encoding fails.
-pub fn render_html_to_png_bytes(
-    html: &str,
-    width: u32,
-    height: u32,
-    scale: f64,
-    current_time_for_animations: f64,
-    font_paths: &[PathBuf],
-) -> Result<Vec<u8>> {
-    let rgba = render_html_to_rgba(
-        html,
-        width,
-        height,
-        scale,
-        current_time_for_animations,
-        font_paths,
-    )?;
-    encode_png(&rgba, width, height)
+pub fn render_html_bytes(html: &str, opts: &RenderOptions) -> Result<Vec<u8>> {
+    render_html_output(html, opts).map(|output| output.bytes)
 }
 
-fn render_html_to_rgba(
-    html: &str,
-    width: u32,
-    height: u32,
-    scale: f64,
-    current_time_for_animations: f64,
-    font_paths: &[PathBuf],
-) -> Result<Vec<u8>> {
-    let mut font_ctx = FontContext::new();
-    register_fonts(&mut font_ctx, font_paths)?;
+/// Render raw HTML as described by `opts` and encode it as `format`.
+///
+/// This is the single entry point behind the per-format `render_html_to_*_bytes` helpers.
+/// For [`OutputFormat::Png`] the format's `compression` wins over `opts.compression`.
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded, rendering fails, or encoding fails.
+pub fn render_html(html: &str, opts: &RenderOptions, format: OutputFormat) -> Result<Vec<u8>> {
+    let painted = render_premultiplied(html, opts)?;
+    encode_painted(painted, opts, format)
+}
 
-    let cfg = DocumentConfig {
-        font_ctx: Some(font_ctx),
-        ..Default::default()
+/// Encode a premultiplied render as `format`.
+fn encode_painted(
+    mut painted: Painted,
+    opts: &RenderOptions,
+    format: OutputFormat,
+) -> Result<Vec<u8>> {
+    let Painted { width, height, .. } = painted;
+    // JPEG drops alpha, so it keeps the premultiplied pixels (transparent becomes black).
+    let keep_premultiplied = match format {
+        OutputFormat::Png { .. } => opts.premultiplied_alpha,
+        #[cfg(feature = "jpeg")]
+        OutputFormat::Jpeg { .. } => true,
+        #[cfg(feature = "webp")]
+        OutputFormat::Webp { .. } => false,
+        OutputFormat::Bmp | OutputFormat::Tiff => false,
     };
+    finish_pixels(&mut painted.rgba, width, keep_premultiplied, opts.dither);
+    let rgba = &painted.rgba;
 
-    let mut doc = HtmlDocument::from_html(html, cfg);
-    doc.resolve(current_time_for_animations);
-    doc.resolve_layout();
+    match format {
+        OutputFormat::Png { compression } => {
+            let opts = RenderOptions {
+                compression,
+                ..opts.clone()
+            };
+            encode_png(rgba, width, height, &opts)
+        }
+        #[cfg(feature = "jpeg")]
+        OutputFormat::Jpeg { quality } => encode_jpeg(rgba, width, height, quality),
+        #[cfg(feature = "webp")]
+        OutputFormat::Webp { lossless, quality } => {
+            encode_webp(rgba, width, height, lossless, quality)
+        }
+        OutputFormat::Bmp => {
+            let mut buffer = Vec::new();
+            BmpEncoder::new(&mut buffer)
+                .write_image(rgba, width, height, image::ExtendedColorType::Rgba8)
+                .map_err(|source| RenderError::EncodeBmp { source })?;
+            Ok(buffer)
+        }
+        OutputFormat::Tiff => {
+            // The TIFF encoder seeks back to patch offsets, so it needs a cursor.
+            let mut buffer = io::Cursor::new(Vec::new());
+            TiffEncoder::new(&mut buffer)
+                .write_image(rgba, width, height, image::ExtendedColorType::Rgba8)
+                .map_err(|source| RenderError::EncodeTiff { source })?;
+            Ok(buffer.into_inner())
+        }
+    }
+}
 
-    let mut renderer = VelloCpuImageRenderer::new(width, height);
-    let mut rgba = vec![0_u8; (width * height * 4) as usize];
+/// Render raw HTML to PNG bytes as described by `opts`, reporting the rendered size.
+///
+/// With `opts.height == 0` the page is laid out at `opts.width` and the height is measured
+/// from its content (capped at `opts.max_height`); the result carries the chosen height.
+///
+/// With `opts.width == 0` the `<body>` is laid out shrink-to-fit, as if absolutely
+/// positioned, and the output is as wide as its margin box (capped at [`MAX_DIMENSION`]),
+/// e.g. for tightly cropped badges. Percentage widths inside the body then resolve against
+/// that shrunk width. The result carries the chosen width.
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded, the measured height exceeds
+/// `opts.max_height` ([`RenderError::ContentTooTall`]), or the PNG encoding fails.
+pub fn render_html_output(html: &str, opts: &RenderOptions) -> Result<RenderOutput> {
+    render_output_with_assets(html, opts, local_assets(opts)?)
+}
 
-    renderer.render(
-        |scene| {
-            paint::paint_scene(scene, &doc, scale, width, height);
-        },
-        &mut rgba,
+/// [`render_html_output`] with the document's resources served by `assets`.
+pub(crate) fn render_output_with_assets(
+    html: &str,
+    opts: &RenderOptions,
+    assets: Option<Assets>,
+) -> Result<RenderOutput> {
+    let font_ctx = font_context(opts)?;
+    let (mut doc, warnings) = load_document_with(html, opts, font_ctx, assets)?;
+    let mut painted = paint_document(&mut doc, opts, opts.animation_time, &mut None)?;
+    finish_pixels(
+        &mut painted.rgba,
+        painted.width,
+        opts.premultiplied_alpha,
+        opts.dither,
     );
+    Ok(RenderOutput {
+        bytes: encode_png(&painted.rgba, painted.width, painted.height, opts)?,
+        width: painted.width,
+        height: painted.height,
+        warnings,
+    })
+}
 
-    Ok(rgba)
+/// Raw RGBA8 pixels of a render, row-major with no padding between rows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaPixels {
+    pub rgba: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    /// Whether the color channels are premultiplied by alpha (copied from
+    /// `RenderOptions::premultiplied_alpha`); straight alpha otherwise.
+    pub premultiplied: bool,
 }
 
-fn encode_png(rgba: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
-    let mut buffer = Vec::new();
-    let encoder = PngEncoder::new(&mut buffer);
-    encoder
-        .write_image(rgba, width, height, image::ExtendedColorType::Rgba8)
-        .map_err(|source| RenderError::WritePng {
-            source,
-            path: PathBuf::from("in-memory"),
-        })?;
-    Ok(buffer)
+/// Render raw HTML to unencoded RGBA8 pixels, e.g. to composite or upload them elsewhere.
+///
+/// The Vello CPU renderer paints premultiplied alpha. Unless `opts.premultiplied_alpha`
+/// is set, the pixels are converted to straight alpha, matching the encoded PNG output;
+/// [`RgbaPixels::premultiplied`] records which convention was used.
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded or the measured height exceeds
+/// [`MAX_DIMENSION`].
+pub fn render_html_to_rgba_pixels(html: &str, opts: &RenderOptions) -> Result<RgbaPixels> {
+    let mut painted = render_premultiplied(html, opts)?;
+    finish_pixels(
+        &mut painted.rgba,
+        painted.width,
+        opts.premultiplied_alpha,
+        opts.dither,
+    );
+    Ok(RgbaPixels {
+        rgba: painted.rgba,
+        width: painted.width,
+        height: painted.height,
+        premultiplied: opts.premultiplied_alpha,
+    })
 }
 
-/// Render any `MiniJinja` template with arbitrary serializable data.
+/// Render raw HTML to an unencoded RGBA8 buffer and its `(width, height)`.
+///
+/// The buffer is the one every encoder starts from, so it can be post-processed (blurred,
+/// overlaid, ...) before encoding it yourself. Alpha is straight unless
+/// `opts.premultiplied_alpha` is set; see [`render_html_to_rgba_pixels`].
 ///
 /// # Errors
-/// Returns an error if reading the template, rendering HTML, or writing the PNG fails.
-#[allow(
-    clippy::too_many_arguments,
-    reason = "Render configuration is explicit and stable for callers"
-)]
-pub fn render_to_png<T: Serialize>(
-    template_path: &Path,
-    data: &T,
-    out_path: &Path,
-    width: u32,
-    height: u32,
-    scale: f64,
-    animation_time: f64,
-    font_paths: &[PathBuf],
-) -> Result<()> {
-    let template = load_template(template_path)?;
-    let html = render_template(&template, data)?;
-    render_html_to_png(
-        &html,
-        out_path,
-        width,
-        height,
-        scale,
-        animation_time,
-        font_paths,
-    )
+/// Returns an error if fonts cannot be loaded or the measured height exceeds
+/// [`MAX_DIMENSION`].
+pub fn render_html_to_rgba(html: &str, opts: &RenderOptions) -> Result<(Vec<u8>, u32, u32)> {
+    let pixels = render_html_to_rgba_pixels(html, opts)?;
+    Ok((pixels.rgba, pixels.width, pixels.height))
 }
 
-fn register_fonts(font_ctx: &mut FontContext, font_paths: &[PathBuf]) -> Result<()> {
-    if font_paths.is_empty() {
-        return Ok(());
+/// Parse and lay out `html` as [`render_html_output`] would, stopping before the paint.
+///
+/// A cheap dry run for editors: fonts are loaded, the document's resources are requested,
+/// and a measured (`opts.height == 0`) height is checked against `opts.max_height`. Returns
+/// the warnings collected with `opts.collect_warnings`; empty otherwise.
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded or the measured height exceeds
+/// `opts.max_height`.
+pub fn layout_html(html: &str, opts: &RenderOptions) -> Result<Vec<String>> {
+    let font_ctx = font_context(opts)?;
+    let (mut doc, warnings) = load_document(html, opts, font_ctx)?;
+    doc.resolve(opts.animation_time);
+    doc.resolve_layout();
+    if opts.height == 0 {
+        measure_content_height(&doc, paint_scale(opts), opts.max_height)?;
     }
+    Ok(warnings)
+}
 
-    for path in font_paths {
-        let data = fs::read(path).map_err(|source| RenderError::ReadFont {
-            source,
-            path: path.clone(),
-        })?;
-        let added = font_ctx
-            .collection
-            .register_fonts(Blob::new(Arc::new(data)), None);
-        if added.is_empty() {
-            return Err(RenderError::RegisterFont { path: path.clone() });
-        }
-    }
+/// Render raw HTML to PNG bytes with in-memory font files registered alongside
+/// `opts.font_paths`, e.g. fonts uploaded with the request.
+///
+/// # Errors
+/// Returns [`RenderError::RegisterFontBytes`] if an entry of `fonts` holds no usable font
+/// faces, or any error of [`render_html_bytes`].
+pub fn render_html_bytes_with_fonts(
+    html: &str,
+    opts: &RenderOptions,
+    fonts: &[Arc<Vec<u8>>],
+) -> Result<Vec<u8>> {
+    let mut font_ctx = font_context(opts)?;
+    register_fonts_from_bytes(&mut font_ctx, fonts)?;
 
-    Ok(())
+    let mut painted = paint_html_to_rgba(html, opts, font_ctx)?;
+    finish_pixels(
+        &mut painted.rgba,
+        painted.width,
+        opts.premultiplied_alpha,
+        opts.dither,
+    );
+    encode_png(&painted.rgba, painted.width, painted.height, opts)
 }
 
-#[cfg(test)]
-mod tests {
-    use std::fs;
-    use std::{error::Error as StdError, result::Result as StdResult};
+/// Render raw HTML as PNG straight into `writer` (a file, socket, or HTTP body).
+///
+/// Encoded bytes are written as they are compressed, so peak memory is the pixel buffer
+/// rather than the pixel buffer plus the whole PNG file. Wrap unbuffered writers such as
+/// [`fs::File`] in an [`io::BufWriter`].
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded, rendering fails, or writing to `writer`
+/// fails ([`RenderError::StreamPng`]).
+pub fn render_html_to_png_writer<W: Write>(
+    html: &str,
+    opts: &RenderOptions,
+    writer: W,
+) -> Result<()> {
+    let mut painted = render_premultiplied(html, opts)?;
+    finish_pixels(
+        &mut painted.rgba,
+        painted.width,
+        opts.premultiplied_alpha,
+        opts.dither,
+    );
+    write_png(&painted.rgba, painted.width, painted.height, opts, writer)
+        .map_err(|source| RenderError::StreamPng { source })
+}
+
+/// Render raw HTML to PNG bytes (in-memory).
+///
+/// This avoids filesystem I/O and is useful for HTTP responses. Pixels use straight
+/// (non-premultiplied) alpha, which is what the PNG format specifies.
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded or the PNG encoding fails.
+pub fn render_html_to_png_bytes(
+    html: &str,
+    width: u32,
+    height: u32,
+    scale: f64,
+    current_time_for_animations: f64,
+    font_paths: &[PathBuf],
+) -> Result<Vec<u8>> {
+    let opts = RenderOptions::new(width, height)
+        .scale(scale)
+        .animation_time(current_time_for_animations)
+        .font_paths(font_paths.to_vec());
+    render_html_bytes(html, &opts)
+}
+
+/// Render raw HTML to PNG bytes as described by `opts`, giving up after `timeout`.
+///
+/// The render runs on a dedicated worker thread. Blitz layout cannot be interrupted, so
+/// cancellation is best-effort: on timeout this returns [`RenderError::Timeout`]
+/// immediately, but the worker keeps running until the render finishes on its own and
+/// its result is then discarded. Callers bounding concurrency should account for
+/// abandoned workers still holding a CPU.
+///
+/// # Errors
+/// Returns [`RenderError::Timeout`] if the render exceeds `timeout`,
+/// [`RenderError::SpawnWorker`] if the worker thread cannot be started, or any error
+/// [`render_html_bytes`] returns.
+pub fn render_html_bytes_timeout(
+    html: &str,
+    opts: &RenderOptions,
+    timeout: Duration,
+) -> Result<Vec<u8>> {
+    let worker = RenderWorker::spawn(html, opts)?;
+    match worker.receiver.recv_timeout(timeout) {
+        Ok(result) => result.map(|output| output.bytes),
+        Err(RecvTimeoutError::Timeout) => Err(RenderError::Timeout { timeout }),
+        Err(RecvTimeoutError::Disconnected) => Err(worker.resume_panic()),
+    }
+}
+
+/// Render raw HTML to PNG bytes as described by `opts`, giving up once `cancel` is set.
+///
+/// Cancellation has the same best-effort semantics as [`render_html_bytes_timeout`]: this
+/// returns [`RenderError::Cancelled`] within [`CANCEL_POLL_INTERVAL`] of `cancel` being
+/// set, while the abandoned worker runs to completion in the background.
+///
+/// # Errors
+/// Returns [`RenderError::Cancelled`] if `cancel` is set before the render finishes,
+/// [`RenderError::SpawnWorker`] if the worker thread cannot be started, or any error
+/// [`render_html_bytes`] returns.
+pub fn render_html_bytes_cancellable(
+    html: &str,
+    opts: &RenderOptions,
+    cancel: &AtomicBool,
+) -> Result<Vec<u8>> {
+    render_html_output_cancellable(html, opts, cancel).map(|output| output.bytes)
+}
+
+/// [`render_html_bytes_cancellable`], also reporting the rendered dimensions.
+///
+/// # Errors
+/// Same as [`render_html_bytes_cancellable`].
+pub fn render_html_output_cancellable(
+    html: &str,
+    opts: &RenderOptions,
+    cancel: &AtomicBool,
+) -> Result<RenderOutput> {
+    let worker = RenderWorker::spawn(html, opts)?;
+    loop {
+        if cancel.load(Ordering::Acquire) {
+            return Err(RenderError::Cancelled);
+        }
+        match worker.receiver.recv_timeout(CANCEL_POLL_INTERVAL) {
+            Ok(result) => return result,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Err(worker.resume_panic()),
+        }
+    }
+}
+
+/// A render running on its own thread, so callers can stop waiting for it.
+struct RenderWorker {
+    receiver: Receiver<Result<RenderOutput>>,
+    handle: thread::JoinHandle<()>,
+}
+
+impl RenderWorker {
+    fn spawn(html: &str, opts: &RenderOptions) -> Result<Self> {
+        let (sender, receiver) = mpsc::sync_channel(1);
+        let html = html.to_owned();
+        let opts = opts.clone();
+        let handle = thread::Builder::new()
+            .name("html-to-image-render".to_owned())
+            .spawn(move || {
+                // A send error means the caller gave up and nobody wants the result.
+                sender.send(render_html_output(&html, &opts)).ok();
+            })
+            .map_err(|source| RenderError::SpawnWorker { source })?;
+        Ok(Self { receiver, handle })
+    }
+
+    /// The sender only drops without sending if the render panicked; re-raise that panic
+    /// on the caller's thread as a synchronous render would. A worker that somehow exits
+    /// cleanly without a result is reported as [`RenderError::WorkerExited`].
+    fn resume_panic(self) -> RenderError {
+        match self.handle.join() {
+            Err(payload) => panic::resume_unwind(payload),
+            Ok(()) => RenderError::WorkerExited,
+        }
+    }
+}
+
+/// Render raw HTML to PNG bytes, giving up after `timeout`.
+///
+/// See [`render_html_bytes_timeout`] for the (best-effort) cancellation semantics.
+///
+/// # Errors
+/// Returns [`RenderError::Timeout`] if the render exceeds `timeout`, or an error if fonts
+/// cannot be loaded or the PNG encoding fails.
+pub fn render_html_to_png_bytes_timeout(
+    html: &str,
+    width: u32,
+    height: u32,
+    scale: f64,
+    current_time_for_animations: f64,
+    font_paths: &[PathBuf],
+    timeout: Duration,
+) -> Result<Vec<u8>> {
+    let opts = RenderOptions::new(width, height)
+        .scale(scale)
+        .animation_time(current_time_for_animations)
+        .font_paths(font_paths.to_vec());
+    render_html_bytes_timeout(html, &opts, timeout)
+}
+
+/// Render raw HTML to PNG bytes with an explicit compression level.
+///
+/// [`CompressionLevel::Fast`] matches [`render_html_to_png_bytes`]; `Best` spends more CPU
+/// for smaller files.
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded or the PNG encoding fails.
+pub fn render_html_to_png_bytes_with(
+    html: &str,
+    width: u32,
+    height: u32,
+    scale: f64,
+    current_time_for_animations: f64,
+    font_paths: &[PathBuf],
+    compression: CompressionLevel,
+) -> Result<Vec<u8>> {
+    let opts = RenderOptions::new(width, height)
+        .scale(scale)
+        .animation_time(current_time_for_animations)
+        .font_paths(font_paths.to_vec())
+        .compression(compression);
+    render_html_bytes(html, &opts)
+}
+
+/// Render raw HTML to a `data:image/png;base64,...` URI for inline embedding (e.g. emails).
+///
+/// For other formats, use [`render_html_data_uri`].
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded or the PNG encoding fails.
+pub fn render_html_to_png_data_uri(
+    html: &str,
+    width: u32,
+    height: u32,
+    scale: f64,
+    current_time_for_animations: f64,
+    font_paths: &[PathBuf],
+) -> Result<String> {
+    let bytes = render_html_to_png_bytes(
+        html,
+        width,
+        height,
+        scale,
+        current_time_for_animations,
+        font_paths,
+    )?;
+    Ok(encode_data_uri(&bytes, "image/png"))
+}
+
+/// Render raw HTML as described by `opts` to a base64 `data:` URI encoded as `format`, e.g.
+/// `data:image/jpeg;base64,...` for [`OutputFormat::Jpeg`].
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded, rendering fails, or encoding fails.
+pub fn render_html_data_uri(
+    html: &str,
+    opts: &RenderOptions,
+    format: OutputFormat,
+) -> Result<String> {
+    let bytes = render_html(html, opts, format)?;
+    Ok(encode_data_uri(&bytes, format.mime_type()))
+}
+
+/// Wrap encoded image bytes in a base64 `data:` URI with the given MIME type
+/// (`image/png`, `image/jpeg`, `image/webp`, ...).
+#[must_use]
+pub fn encode_data_uri(bytes: &[u8], mime_type: &str) -> String {
+    format!("data:{mime_type};base64,{}", BASE64.encode(bytes))
+}
+
+/// Encoded image bytes together with the pixel dimensions actually rendered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderOutput {
+    pub bytes: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    /// Diagnostics gathered with `RenderOptions::collect_warnings`; empty otherwise.
+    pub warnings: Vec<String>,
+}
+
+/// Render raw HTML to PNG bytes and report the rendered dimensions.
+///
+/// Pass `height == 0` to measure the height from the content (see [`render_html_output`]).
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded, the measured height is too large, or the
+/// PNG encoding fails.
+pub fn render_html_to_png_output(
+    html: &str,
+    width: u32,
+    height: u32,
+    scale: f64,
+    current_time_for_animations: f64,
+    font_paths: &[PathBuf],
+) -> Result<RenderOutput> {
+    let opts = RenderOptions::new(width, height)
+        .scale(scale)
+        .animation_time(current_time_for_animations)
+        .font_paths(font_paths.to_vec());
+    render_html_output(html, &opts)
+}
+
+/// Render raw HTML to PNG bytes on top of an optional background color.
+///
+/// `background` is straight-alpha RGBA; `None` keeps the transparent canvas of
+/// [`render_html_to_png_bytes`]. The color shows through wherever the page is transparent
+/// or translucent, e.g. `Some([255, 255, 255, 255])` for an opaque white canvas.
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded or the PNG encoding fails.
+pub fn render_html_to_png_bytes_with_background(
+    html: &str,
+    width: u32,
+    height: u32,
+    scale: f64,
+    current_time_for_animations: f64,
+    font_paths: &[PathBuf],
+    background: Option<[u8; 4]>,
+) -> Result<Vec<u8>> {
+    let opts = RenderOptions::new(width, height)
+        .scale(scale)
+        .animation_time(current_time_for_animations)
+        .font_paths(font_paths.to_vec())
+        .background(background);
+    render_html_bytes(html, &opts)
+}
+
+/// Render raw HTML to PNG bytes, choosing the alpha convention of the encoded pixels.
+///
+/// The Vello CPU renderer produces premultiplied RGBA. With `premultiplied_alpha == false`
+/// the buffer is converted to straight alpha before encoding (the PNG convention); with
+/// `true` the premultiplied values are written as-is for compositors that expect them.
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded or the PNG encoding fails.
+pub fn render_html_to_png_bytes_with_alpha(
+    html: &str,
+    width: u32,
+    height: u32,
+    scale: f64,
+    current_time_for_animations: f64,
+    font_paths: &[PathBuf],
+    premultiplied_alpha: bool,
+) -> Result<Vec<u8>> {
+    let opts = RenderOptions::new(width, height)
+        .scale(scale)
+        .animation_time(current_time_for_animations)
+        .font_paths(font_paths.to_vec())
+        .premultiplied_alpha(premultiplied_alpha);
+    render_html_bytes(html, &opts)
+}
+
+/// Render raw HTML to PNG bytes with every element's layout box drawn on top.
+///
+/// Each box gets a translucent fill, a solid outline (colored by nesting depth), and its
+/// tag name as a label, which makes margins, padding, and overflow visible at a glance.
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded or the PNG encoding fails.
+pub fn render_html_to_debug_png_bytes(
+    html: &str,
+    width: u32,
+    height: u32,
+    scale: f64,
+    current_time_for_animations: f64,
+    font_paths: &[PathBuf],
+) -> Result<Vec<u8>> {
+    let opts = RenderOptions::new(width, height)
+        .scale(scale)
+        .animation_time(current_time_for_animations)
+        .font_paths(font_paths.to_vec())
+        .debug_boxes(true);
+    render_html_bytes(html, &opts)
+}
+
+/// Render raw HTML to JPEG bytes (in-memory).
+///
+/// JPEG has no alpha channel, so the alpha is dropped after painting: transparent areas come
+/// out black. Give the page an opaque background for predictable results. `quality` is
+/// clamped to `1..=100`.
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded or the JPEG encoding fails.
+#[cfg(feature = "jpeg")]
+pub fn render_html_to_jpeg_bytes(
+    html: &str,
+    width: u32,
+    height: u32,
+    scale: f64,
+    current_time_for_animations: f64,
+    font_paths: &[PathBuf],
+    quality: u8,
+) -> Result<Vec<u8>> {
+    let opts = RenderOptions::new(width, height)
+        .scale(scale)
+        .animation_time(current_time_for_animations)
+        .font_paths(font_paths.to_vec());
+    render_html(html, &opts, OutputFormat::Jpeg { quality })
+}
+
+/// Render raw HTML to WebP bytes (in-memory).
+///
+/// Lossless output goes through the `image` crate's encoder and ignores `quality`. Lossy
+/// output uses libwebp with `quality` clamped to `0.0..=100.0`; alpha is kept in both modes.
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded or the WebP encoding fails.
+#[cfg(feature = "webp")]
+#[allow(
+    clippy::too_many_arguments,
+    reason = "Mirrors the PNG renderer's parameters plus the WebP encoding knobs."
+)]
+pub fn render_html_to_webp_bytes(
+    html: &str,
+    width: u32,
+    height: u32,
+    scale: f64,
+    current_time_for_animations: f64,
+    font_paths: &[PathBuf],
+    lossless: bool,
+    quality: f32,
+) -> Result<Vec<u8>> {
+    let opts = RenderOptions::new(width, height)
+        .scale(scale)
+        .animation_time(current_time_for_animations)
+        .font_paths(font_paths.to_vec());
+    render_html(html, &opts, OutputFormat::Webp { lossless, quality })
+}
+
+/// Render raw HTML to an uncompressed 32-bit BMP (in-memory) with straight alpha.
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded or the BMP encoding fails.
+pub fn render_html_to_bmp_bytes(
+    html: &str,
+    width: u32,
+    height: u32,
+    scale: f64,
+    current_time_for_animations: f64,
+    font_paths: &[PathBuf],
+) -> Result<Vec<u8>> {
+    let opts = RenderOptions::new(width, height)
+        .scale(scale)
+        .animation_time(current_time_for_animations)
+        .font_paths(font_paths.to_vec());
+    render_html(html, &opts, OutputFormat::Bmp)
+}
+
+/// Render raw HTML to an uncompressed RGBA TIFF (in-memory) with straight alpha.
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded or the TIFF encoding fails.
+pub fn render_html_to_tiff_bytes(
+    html: &str,
+    width: u32,
+    height: u32,
+    scale: f64,
+    current_time_for_animations: f64,
+    font_paths: &[PathBuf],
+) -> Result<Vec<u8>> {
+    let opts = RenderOptions::new(width, height)
+        .scale(scale)
+        .animation_time(current_time_for_animations)
+        .font_paths(font_paths.to_vec());
+    render_html(html, &opts, OutputFormat::Tiff)
+}
+
+/// A full-size PNG and a downscaled preview produced from a single render.
+#[derive(Debug, Clone)]
+pub struct PngWithThumbnail {
+    pub full: Vec<u8>,
+    pub thumbnail: Vec<u8>,
+    pub thumbnail_width: u32,
+    pub thumbnail_height: u32,
+}
+
+/// Render raw HTML once as described by `opts` and encode both the full PNG and a
+/// thumbnail of it.
+///
+/// The thumbnail keeps the aspect ratio; `thumbnail_width` is clamped to `1..=width` of
+/// the finished render.
+///
+/// # Errors
+/// Returns an error if fonts cannot be loaded, rendering fails, or the PNG encoding fails.
+pub fn render_html_to_png_with_thumbnail(
+    html: &str,
+    opts: &RenderOptions,
+    thumbnail_width: u32,
+) -> Result<PngWithThumbnail> {
+    let Painted {
+        rgba: mut full,
+        width: full_width,
+        height: full_height,
+    } = render_premultiplied(html, opts)?;
+
+    let thumbnail_width = thumbnail_width.clamp(1, full_width.max(1));
+    let thumbnail_height = scaled_dimension(full_height, thumbnail_width, full_width);
+    // Resample the premultiplied buffer so transparent edges don't bleed dark fringes.
+    let mut thumbnail = resize_rgba(
+        &full,
+        (full_width, full_height),
+        (thumbnail_width, thumbnail_height),
+        FilterType::Lanczos3,
+    );
+    finish_pixels(
+        &mut thumbnail,
+        thumbnail_width,
+        opts.premultiplied_alpha,
+        opts.dither,
+    );
+    finish_pixels(&mut full, full_width, opts.premultiplied_alpha, opts.dither);
+
+    Ok(PngWithThumbnail {
+        full: encode_png(&full, full_width, full_height, opts)?,
+        thumbnail: encode_png(&thumbnail, thumbnail_width, thumbnail_height, opts)?,
+        thumbnail_width,
+        thumbnail_height,
+    })
+}
+
+/// Scale `value` by `numerator / denominator`, rounding and never returning zero.
+fn scaled_dimension(value: u32, numerator: u32, denominator: u32) -> u32 {
+    let scaled = (u64::from(value) * u64::from(numerator) + u64::from(denominator) / 2)
+        / u64::from(denominator.max(1));
+    u32::try_from(scaled).unwrap_or(u32::MAX).max(1)
+}
+
+fn resize_rgba(
+    rgba: &[u8],
+    (width, height): (u32, u32),
+    (new_width, new_height): (u32, u32),
+    filter: FilterType,
+) -> Vec<u8> {
+    let source = ImageBuffer::<Rgba<u8>, &[u8]>::from_raw(width, height, rgba)
+        .expect("render buffer matches its dimensions");
+    imageops::resize(&source, new_width, new_height, filter).into_raw()
+}
+
+/// A painted RGBA8 buffer (premultiplied alpha) and its pixel dimensions.
+struct Painted {
+    rgba: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+/// Paint `html` into an RGBA8 buffer with premultiplied alpha (the Vello CPU convention).
+fn render_premultiplied(html: &str, opts: &RenderOptions) -> Result<Painted> {
+    let font_ctx = font_context(opts)?;
+    paint_html_to_rgba(html, opts, font_ctx)
+}
+
+/// Lay out and paint HTML with an already-populated font context.
+///
+/// `opts.font_paths` is ignored; the fonts come from `font_ctx`.
+fn paint_html_to_rgba(html: &str, opts: &RenderOptions, font_ctx: FontContext) -> Result<Painted> {
+    let (mut doc, _warnings) = load_document(html, opts, font_ctx)?;
+    paint_document(&mut doc, opts, opts.animation_time, &mut None)
+}
+
+/// Parse `html` and apply any local assets. Styles and layout are resolved per paint, so
+/// one document can be painted at several animation times.
+///
+/// With `opts.collect_warnings`, every resource the document requested but that was not
+/// loaded is returned as a warning; the list is empty otherwise.
+fn load_document(
+    html: &str,
+    opts: &RenderOptions,
+    font_ctx: FontContext,
+) -> Result<(HtmlDocument, Vec<String>)> {
+    load_document_with(html, opts, font_ctx, local_assets(opts)?)
+}
+
+/// The assets under `opts.base_dir`, or a provider that only records requests when
+/// warnings are collected without one.
+fn local_assets(opts: &RenderOptions) -> Result<Option<Assets>> {
+    match &opts.base_dir {
+        Some(base_dir) => Assets::new(base_dir).map(Some),
+        None => Ok(opts.collect_warnings.then(Assets::none)),
+    }
+}
+
+/// [`load_document`] with the document's resources served by `assets`.
+fn load_document_with(
+    html: &str,
+    opts: &RenderOptions,
+    font_ctx: FontContext,
+    assets: Option<Assets>,
+) -> Result<(HtmlDocument, Vec<String>)> {
+    let measure_width = opts.width == 0;
+    let width = if measure_width {
+        MAX_DIMENSION
+    } else {
+        device_pixels(opts.width, opts)?
+    };
+    let scale = paint_scale(opts);
+    let measure_height = opts.height == 0;
+    // Measuring needs the real width for line wrapping; the 1px height just lets the
+    // content overflow so its full extent ends up in the layout. A measured width lays out
+    // at the widest allowed canvas and lets the shrink-to-fit body pick its own width.
+    let viewport = (measure_height || measure_width).then(|| {
+        let height = if measure_height {
+            Ok(1)
+        } else {
+            device_pixels(opts.height, opts)
+        };
+        height.map(|height| Viewport::new(width, height, viewport_scale(scale), ColorScheme::Light))
+    });
+    let viewport = viewport.transpose()?;
+    let html = if opts.sanitize {
+        Cow::Owned(sanitize_html(html))
+    } else {
+        Cow::Borrowed(html)
+    };
+    let html = if measure_width {
+        Cow::Owned(format!("{html}{SHRINK_TO_FIT_STYLE}"))
+    } else {
+        html
+    };
+    let assets = assets.map(Arc::new);
+    let cfg = DocumentConfig {
+        font_ctx: Some(font_ctx),
+        viewport,
+        base_url: assets.as_ref().and_then(|assets| assets.base_url()),
+        net_provider: assets.clone().map(|assets| {
+            let provider: Arc<dyn NetProvider<Resource>> = assets;
+            provider
+        }),
+        ..Default::default()
+    };
+
+    let mut doc = HtmlDocument::from_html(&html, cfg);
+    if let Some(assets) = &assets {
+        assets.load_into(&mut doc);
+    }
+    // Animations start when styles are first resolved; doing that at zero makes every
+    // paint's `animation_time` count from the first keyframe, not from the paint itself.
+    doc.resolve(0.0);
+    let warnings = match &assets {
+        Some(assets) if opts.collect_warnings => assets.warnings(),
+        _ => Vec::new(),
+    };
+    Ok((doc, warnings))
+}
+
+/// Resolve styles and layout at `animation_time`, then paint the document.
+///
+/// `canvas_size` is the device-pixel `(width, height)` to paint; when `None` it is derived
+/// from `opts` (measuring the content if `opts.width` or `opts.height` is `0`) and stored
+/// back, so repeated paints of one document keep the first paint's size.
+fn paint_document(
+    doc: &mut HtmlDocument,
+    opts: &RenderOptions,
+    animation_time: f64,
+    canvas_size: &mut Option<(u32, u32)>,
+) -> Result<Painted> {
+    let scale = paint_scale(opts);
+    doc.resolve(animation_time);
+    doc.resolve_layout();
+
+    let (width, height) = match *canvas_size {
+        Some(size) => size,
+        None => output_size(doc, opts, scale)?,
+    };
+    *canvas_size = Some((width, height));
+
+    let factor = u32::from(opts.supersample.clamp(1, MAX_SUPERSAMPLE));
+    let too_large = || RenderError::DimensionsTooLarge { width, height };
+    let paint_width = width.checked_mul(factor).ok_or_else(too_large)?;
+    let paint_height = height.checked_mul(factor).ok_or_else(too_large)?;
+    let len = rgba_len(paint_width, paint_height)?;
+    let mut renderer = VelloCpuImageRenderer::new(paint_width, paint_height);
+    let mut rgba = vec![0_u8; len];
+
+    renderer.render(
+        |scene| {
+            paint::paint_scene(
+                scene,
+                doc,
+                scale * f64::from(factor),
+                paint_width,
+                paint_height,
+            );
+        },
+        &mut rgba,
+    );
+    if factor > 1 {
+        // The buffer is still premultiplied, so translucent edges average without fringes.
+        rgba = resize_rgba(
+            &rgba,
+            (paint_width, paint_height),
+            (width, height),
+            FilterType::Triangle,
+        );
+    }
+
+    if opts.debug_boxes {
+        let boxes = debug::layout_boxes(doc);
+        debug::draw_layout_boxes(&mut rgba, width, height, &boxes, scale);
+    }
+    if let Some(color) = opts.background {
+        composite_over_background(&mut rgba, color);
+    }
+
+    let painted = Painted {
+        rgba,
+        width,
+        height,
+    };
+    let painted = match &opts.clip_selector {
+        Some(selector) => clip_to_selector(doc, &painted, selector, scale)?,
+        None => painted,
+    };
+    let painted = match opts.crop {
+        Some(rect) => crop_painted(&painted, rect)?,
+        None => painted,
+    };
+    match opts.resize_to {
+        Some(size) => resize_painted(painted, size, opts.resize_filter),
+        None => Ok(painted),
+    }
+}
+
+/// Resample `painted` to `(width, height)`, deriving a `0` dimension from the aspect ratio.
+fn resize_painted(
+    painted: Painted,
+    (width, height): (u32, u32),
+    filter: ResizeFilter,
+) -> Result<Painted> {
+    let (width, height) = match (width, height) {
+        (0, 0) => return Ok(painted),
+        (0, height) => (
+            scaled_dimension(painted.width, height, painted.height),
+            height,
+        ),
+        (width, 0) => (
+            width,
+            scaled_dimension(painted.height, width, painted.width),
+        ),
+        size => size,
+    };
+    if width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return Err(RenderError::DimensionsTooLarge { width, height });
+    }
+    Ok(Painted {
+        // Still premultiplied here, so translucent edges resample without dark fringes.
+        rgba: resize_rgba(
+            &painted.rgba,
+            (painted.width, painted.height),
+            (width, height),
+            filter.into(),
+        ),
+        width,
+        height,
+    })
+}
+
+/// Keep only the `(x, y, width, height)` rectangle of `painted`.
+fn crop_painted(painted: &Painted, (x, y, width, height): (u32, u32, u32, u32)) -> Result<Painted> {
+    let fits = |start: u32, len: u32, limit: u32| {
+        len > 0 && start.checked_add(len).is_some_and(|end| end <= limit)
+    };
+    if !fits(x, width, painted.width) || !fits(y, height, painted.height) {
+        return Err(RenderError::CropOutOfBounds {
+            x,
+            y,
+            width,
+            height,
+            image_width: painted.width,
+            image_height: painted.height,
+        });
+    }
+    Ok(Painted {
+        rgba: crop_rgba(&painted.rgba, painted.width, x, y, width, height),
+        width,
+        height,
+    })
+}
+
+/// Crop `painted` to the border box of the first element matching `selector`.
+fn clip_to_selector(
+    doc: &BaseDocument,
+    painted: &Painted,
+    selector: &str,
+    scale: f64,
+) -> Result<Painted> {
+    let not_found = || RenderError::SelectorNotFound {
+        selector: selector.to_owned(),
+    };
+    let node = doc
+        .query_selector(selector)
+        .map_err(|_parse_error| RenderError::InvalidSelector {
+            selector: selector.to_owned(),
+        })?
+        .and_then(|id| doc.get_node(id))
+        .ok_or_else(not_found)?;
+
+    let origin = node.absolute_position(0.0, 0.0);
+    let size = node.final_layout.size;
+    let to_pixel = |css: f32, limit: u32| {
+        let pixel = (f64::from(css) * scale)
+            .round()
+            .clamp(0.0, f64::from(limit));
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            reason = "Clamped to 0..=limit above; NaN saturates to 0"
+        )]
+        let pixel = pixel as u32;
+        pixel
+    };
+    let left = to_pixel(origin.x, painted.width);
+    let top = to_pixel(origin.y, painted.height);
+    let right = to_pixel(origin.x + size.width, painted.width);
+    let bottom = to_pixel(origin.y + size.height, painted.height);
+    if right <= left || bottom <= top {
+        return Err(not_found());
+    }
+
+    let (width, height) = (right - left, bottom - top);
+    Ok(Painted {
+        rgba: crop_rgba(&painted.rgba, painted.width, left, top, width, height),
+        width,
+        height,
+    })
+}
+
+/// Copy the `width` x `height` region at (`left`, `top`) out of an RGBA8 buffer that is
+/// `stride_width` pixels wide. The region must lie inside the buffer.
+fn crop_rgba(
+    rgba: &[u8],
+    stride_width: u32,
+    left: u32,
+    top: u32,
+    width: u32,
+    height: u32,
+) -> Vec<u8> {
+    let stride = stride_width as usize * 4;
+    let row_len = width as usize * 4;
+    let mut cropped = Vec::with_capacity(row_len * height as usize);
+    for row in rgba
+        .chunks_exact(stride)
+        .skip(top as usize)
+        .take(height as usize)
+    {
+        let start = left as usize * 4;
+        cropped.extend_from_slice(row.get(start..start + row_len).unwrap_or_default());
+    }
+    cropped
+}
+
+/// Byte length of an RGBA8 buffer, or [`RenderError::DimensionsTooLarge`] if it does not
+/// fit in memory's address space.
+fn rgba_len(width: u32, height: u32) -> Result<usize> {
+    usize::try_from(width)
+        .ok()
+        .zip(usize::try_from(height).ok())
+        .and_then(|(width_px, height_px)| width_px.checked_mul(height_px))
+        .and_then(|pixels| pixels.checked_mul(4))
+        .filter(|&len| isize::try_from(len).is_ok())
+        .ok_or(RenderError::DimensionsTooLarge { width, height })
+}
+
+/// Pixel height of the laid-out document: the root element's box or its overflowing
+/// content, whichever is taller. Fails if it exceeds `max_height` (or [`MAX_DIMENSION`]).
+fn measure_content_height(doc: &BaseDocument, scale: f64, max_height: u32) -> Result<u32> {
+    let max = max_height.min(MAX_DIMENSION);
+    let layout = &doc.root_element().final_layout;
+    let css_height = layout.size.height.max(layout.content_size.height);
+    let pixels = (f64::from(css_height) * scale).ceil();
+    if pixels > f64::from(max) {
+        return Err(RenderError::ContentTooTall {
+            height: pixels,
+            max,
+        });
+    }
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        reason = "Bounded to 0..=max above; NaN saturates to 0"
+    )]
+    let height = pixels as u32;
+    Ok(height.max(1))
+}
+
+/// Device-pixel `(width, height)` from `opts`, measuring the laid-out document in each
+/// dimension that is `0`.
+fn output_size(doc: &BaseDocument, opts: &RenderOptions, scale: f64) -> Result<(u32, u32)> {
+    let width = if opts.width == 0 {
+        measure_content_width(doc, scale)
+    } else {
+        device_pixels(opts.width, opts)?
+    };
+    let height = if opts.height == 0 {
+        measure_content_height(doc, scale, opts.max_height)?
+    } else {
+        device_pixels(opts.height, opts)?
+    };
+    Ok((width, height))
+}
+
+/// Pixel width of the shrink-to-fit `<body>`'s margin box, or of the root's content when
+/// the document has no body element.
+fn measure_content_width(doc: &BaseDocument, scale: f64) -> u32 {
+    let root = doc.root_element();
+    let body = root
+        .children
+        .iter()
+        .filter_map(|&id| doc.get_node(id))
+        .find(|node| {
+            node.element_data()
+                .is_some_and(|element| &*element.name.local == "body")
+        });
+    let css_width = body.map_or(root.final_layout.content_size.width, |body| {
+        let layout = &body.final_layout;
+        layout.location.x + layout.size.width + layout.margin.right
+    });
+    let pixels = (f64::from(css_width) * scale).ceil();
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        reason = "Clamped to 1..=MAX_DIMENSION; NaN saturates to 0 before the clamp"
+    )]
+    let width = pixels as u32;
+    width.clamp(1, MAX_DIMENSION)
+}
+
+/// Scale a CSS dimension by the device pixel ratio.
+fn device_pixels(css: u32, opts: &RenderOptions) -> Result<u32> {
+    let ratio = opts.device_pixel_ratio;
+    if !(ratio.is_finite() && ratio > 0.0) {
+        return Err(RenderError::InvalidDevicePixelRatio { ratio });
+    }
+    let pixels = (f64::from(css) * ratio).round();
+    if pixels > f64::from(u32::MAX) {
+        return Err(RenderError::DimensionsTooLarge {
+            width: opts.width,
+            height: opts.height,
+        });
+    }
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        reason = "Bounded to 0..=u32::MAX above"
+    )]
+    let pixels = pixels as u32;
+    Ok(pixels)
+}
+
+/// Scale applied while painting: the layout scale times the device pixel ratio.
+fn paint_scale(opts: &RenderOptions) -> f64 {
+    opts.scale * opts.device_pixel_ratio
+}
+
+fn viewport_scale(scale: f64) -> f32 {
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "Blitz takes the viewport scale as f32; render scales are small"
+    )]
+    let scale = scale as f32;
+    scale
+}
+
+/// Composite premultiplied RGBA8 pixels over a straight-alpha background color in place.
+///
+/// The renderer overwrites the whole buffer, so the background is applied after painting
+/// (`page over background`) rather than pre-filled.
+fn composite_over_background(rgba: &mut [u8], [red, green, blue, alpha]: [u8; 4]) {
+    let background_alpha = u16::from(alpha);
+    let background = [red, green, blue, u8::MAX]
+        .map(|channel| (u16::from(channel) * background_alpha + 127) / 255);
+
+    for pixel in rgba.chunks_exact_mut(4) {
+        let coverage = pixel.get(3).copied().map_or(0, u16::from);
+        let inverse = 255 - coverage;
+        for (channel, under) in pixel.iter_mut().zip(background) {
+            let blended = u16::from(*channel) + (under * inverse + 127) / 255;
+            *channel = u8::try_from(blended).unwrap_or(u8::MAX);
+        }
+    }
+}
+
+/// 4x4 Bayer matrix, row by row, in sixteenths.
+const BAYER_4X4: [u8; 16] = [0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5];
+
+/// Bring a premultiplied buffer `width` pixels wide into the alpha convention it is
+/// encoded with and, with `dither`, dither it. Every resample and crop is done by then, so
+/// dithering is the last thing to touch the pixels.
+pub(crate) fn finish_pixels(rgba: &mut [u8], width: u32, premultiplied: bool, dither: bool) {
+    if !premultiplied {
+        unpremultiply_alpha(rgba);
+    }
+    if dither {
+        dither_rgba(rgba, width, premultiplied);
+    }
+}
+
+/// Break up 8-bit banding in RGBA8 pixels `width` wide, in place.
+///
+/// The renderer only produces 8 bits per channel, so there is no extra precision to keep;
+/// instead a fixed ordered pattern darkens a quarter of each 4x4 tile by one step and
+/// brightens another quarter. Flat areas keep their average, while the straight edges
+/// between gradient bands become interleaved pixels. The pattern is deterministic, so
+/// renders stay reproducible. Alpha is left alone; fully transparent pixels are skipped,
+/// and `premultiplied` colors are kept at or below their alpha.
+fn dither_rgba(rgba: &mut [u8], width: u32, premultiplied: bool) {
+    let width = usize::try_from(width).unwrap_or(usize::MAX).max(1);
+    for (index, pixel) in rgba.chunks_exact_mut(4).enumerate() {
+        let cell = (index / width % 4) * 4 + index % width % 4;
+        let threshold = BAYER_4X4.get(cell).copied().unwrap_or(8);
+        if let [red, green, blue, alpha] = pixel
+            && *alpha > 0
+        {
+            let ceiling = if premultiplied { *alpha } else { u8::MAX };
+            for channel in [red, green, blue] {
+                *channel = match threshold {
+                    0..4 => channel.saturating_sub(1),
+                    12.. => channel.saturating_add(1).min(ceiling),
+                    _ => *channel,
+                };
+            }
+        }
+    }
+}
+
+/// Convert premultiplied RGBA8 pixels to straight alpha in place.
+fn unpremultiply_alpha(rgba: &mut [u8]) {
+    for pixel in rgba.chunks_exact_mut(4) {
+        if let [red, green, blue, alpha] = pixel {
+            match *alpha {
+                0 => {
+                    *red = 0;
+                    *green = 0;
+                    *blue = 0;
+                }
+                u8::MAX => {}
+                coverage => {
+                    for channel in [red, green, blue] {
+                        *channel = unpremultiply_channel(*channel, coverage);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn unpremultiply_channel(value: u8, alpha: u8) -> u8 {
+    let alpha = u16::from(alpha);
+    let straight = (u16::from(value) * 255 + alpha / 2) / alpha;
+    u8::try_from(straight).unwrap_or(u8::MAX)
+}
+
+fn encode_png(rgba: &[u8], width: u32, height: u32, opts: &RenderOptions) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    write_png(rgba, width, height, opts, &mut buffer).map_err(|source| RenderError::WritePng {
+        source: image::ImageError::IoError(source),
+        path: PathBuf::from("in-memory"),
+    })?;
+    Ok(buffer)
+}
+
+/// Encode straight-alpha RGBA8 pixels as PNG into `writer`, compressing and writing
+/// `IDAT` data incrementally instead of building the whole file first.
+fn write_png<W: Write>(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    opts: &RenderOptions,
+    writer: W,
+) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_compression(match opts.compression {
+        CompressionLevel::Fast => png::Compression::Fast,
+        CompressionLevel::Balanced => png::Compression::Default,
+        CompressionLevel::Best => png::Compression::Best,
+    });
+    // Same filtering the `image` crate's encoder used before metadata chunks were needed.
+    encoder.set_filter(png::FilterType::Sub);
+    encoder.set_adaptive_filter(png::AdaptiveFilterType::Adaptive);
+    if let Some(dpi) = opts.dpi {
+        let pixels_per_meter = dots_per_inch_to_meter(dpi);
+        encoder.set_pixel_dims(Some(png::PixelDimensions {
+            xppu: pixels_per_meter,
+            yppu: pixels_per_meter,
+            unit: png::Unit::Meter,
+        }));
+    }
+    if opts.srgb {
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+        // gAMA fallback for decoders that ignore sRGB (PNG spec 11.3.2.5).
+        encoder.set_source_gamma(png::ScaledFloat::from_scaled(45_455));
+    }
+
+    let mut header = encoder.write_header()?;
+    let mut stream = header.stream_writer()?;
+    stream.write_all(rgba)?;
+    stream.finish()?;
+    header.finish()?;
+    Ok(())
+}
+
+/// pHYs stores pixels per meter; one inch is 0.0254 m.
+fn dots_per_inch_to_meter(dpi: u32) -> u32 {
+    let per_meter = (u64::from(dpi) * 10_000 + 127) / 254;
+    u32::try_from(per_meter).unwrap_or(u32::MAX)
+}
+
+/// Encode premultiplied RGBA as JPEG; dropping premultiplied alpha composites onto black.
+#[cfg(feature = "jpeg")]
+fn encode_jpeg(rgba: &[u8], width: u32, height: u32, quality: u8) -> Result<Vec<u8>> {
+    let rgb: Vec<u8> = rgba
+        .chunks_exact(4)
+        .flat_map(|pixel| pixel.iter().take(3).copied())
+        .collect();
+
+    let mut buffer = Vec::new();
+    let encoder = JpegEncoder::new_with_quality(&mut buffer, quality.clamp(1, 100));
+    encoder
+        .write_image(&rgb, width, height, image::ExtendedColorType::Rgb8)
+        .map_err(|source| RenderError::EncodeJpeg { source })?;
+    Ok(buffer)
+}
+
+/// Encode straight-alpha RGBA as WebP.
+#[cfg(feature = "webp")]
+fn encode_webp(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    lossless: bool,
+    quality: f32,
+) -> Result<Vec<u8>> {
+    if lossless {
+        let mut buffer = Vec::new();
+        WebPEncoder::new_lossless(&mut buffer)
+            .write_image(rgba, width, height, image::ExtendedColorType::Rgba8)
+            .map_err(|err| RenderError::EncodeWebp {
+                reason: err.to_string(),
+            })?;
+        return Ok(buffer);
+    }
+
+    let quality = if quality.is_nan() {
+        DEFAULT_WEBP_QUALITY
+    } else {
+        quality.clamp(0.0, 100.0)
+    };
+    let memory = webp::Encoder::from_rgba(rgba, width, height)
+        .encode_simple(false, quality)
+        .map_err(|err| RenderError::EncodeWebp {
+            reason: format!("{err:?}"),
+        })?;
+    Ok(memory.to_vec())
+}
+
+/// Render any `MiniJinja` template with arbitrary serializable data.
+///
+/// # Errors
+/// Returns an error if reading the template, rendering HTML, or writing the PNG fails.
+#[allow(
+    clippy::too_many_arguments,
+    reason = "Render configuration is explicit and stable for callers"
+)]
+pub fn render_to_png<T: Serialize>(
+    template_path: &Path,
+    data: &T,
+    out_path: &Path,
+    width: u32,
+    height: u32,
+    scale: f64,
+    animation_time: f64,
+    font_paths: &[PathBuf],
+) -> Result<()> {
+    let template = load_template(template_path)?;
+    let html = render_template(&template, data)?;
+    render_html_to_png(
+        &html,
+        out_path,
+        width,
+        height,
+        scale,
+        animation_time,
+        font_paths,
+    )
+}
+
+/// Register in-memory font files (e.g. embedded with `include_bytes!`) with `font_ctx`.
+///
+/// Each entry is one font file; collections (`.ttc`) register every face they contain.
+///
+/// # Errors
+/// Returns [`RenderError::RegisterFontBytes`] with the entry's index if it holds no usable
+/// font faces.
+pub fn register_fonts_from_bytes(font_ctx: &mut FontContext, fonts: &[Arc<Vec<u8>>]) -> Result<()> {
+    register_font_blobs(font_ctx, fonts).map(drop)
+}
+
+/// [`register_fonts_from_bytes`], returning the families the fonts were added to.
+#[allow(
+    clippy::rc_buffer,
+    reason = "Takes the same font handles as the public register_fonts_from_bytes"
+)]
+fn register_font_blobs(
+    font_ctx: &mut FontContext,
+    fonts: &[Arc<Vec<u8>>],
+) -> Result<Vec<FamilyId>> {
+    let mut families = Vec::new();
+    for (index, data) in fonts.iter().enumerate() {
+        let shared: Arc<dyn AsRef<[u8]> + Send + Sync> = Arc::<Vec<u8>>::clone(data);
+        let added = font_ctx.collection.register_fonts(Blob::new(shared), None);
+        if added.is_empty() {
+            return Err(RenderError::RegisterFontBytes { index });
+        }
+        families.extend(added.into_iter().map(|(family, _)| family));
+    }
+    Ok(families)
+}
+
+/// Generic text families extended by the default, fallback, and emoji fonts.
+const TEXT_GENERICS: [GenericFamily; 3] = [
+    GenericFamily::SansSerif,
+    GenericFamily::Serif,
+    GenericFamily::SystemUi,
+];
+
+/// Emoji font registered by `RenderOptions::enable_emoji` (Noto Emoji, monochrome).
+#[cfg(feature = "emoji")]
+const BUNDLED_EMOJI_FONT: &[u8] = include_bytes!("../../assets/fonts/NotoEmoji-Regular.ttf");
+
+/// A font context holding `opts.default_font`, `opts.fallback_fonts`, the bundled emoji
+/// font if enabled, and then `opts.font_paths`.
+///
+/// The default font's families are put first in the generic families, so text without a
+/// `font-family` (or with a generic one) uses it; fonts named explicitly in CSS still win.
+/// Fallback fonts follow in order, so characters the earlier faces lack find a face that
+/// covers their script. The emoji font leads the `emoji` family and trails the text
+/// families.
+///
+/// Fails with [`RenderError::EmojiNotBundled`] if `opts.enable_emoji` is set without the
+/// `emoji` feature.
+pub(crate) fn font_context(opts: &RenderOptions) -> Result<FontContext> {
+    let mut font_ctx = FontContext::new();
+    if let Some(default_font) = &opts.default_font {
+        let families = register_fonts(&mut font_ctx, slice::from_ref(default_font))?;
+        for generic in TEXT_GENERICS {
+            prefer_families(&mut font_ctx, generic, &families);
+        }
+    }
+    let fallbacks = register_fonts(&mut font_ctx, &opts.fallback_fonts)?;
+    for generic in TEXT_GENERICS {
+        font_ctx
+            .collection
+            .append_generic_families(generic, fallbacks.iter().copied());
+    }
+    if opts.enable_emoji {
+        register_bundled_emoji(&mut font_ctx)?;
+    }
+    register_fonts(&mut font_ctx, &opts.font_paths)?;
+    Ok(font_ctx)
+}
+
+/// Register [`BUNDLED_EMOJI_FONT`] ahead of the `emoji` family and behind the text families.
+#[cfg(feature = "emoji")]
+#[allow(
+    clippy::unnecessary_wraps,
+    reason = "Matches the fallible build without the `emoji` feature"
+)]
+fn register_bundled_emoji(font_ctx: &mut FontContext) -> Result<()> {
+    let families: Vec<FamilyId> = font_ctx
+        .collection
+        .register_fonts(Blob::new(Arc::new(BUNDLED_EMOJI_FONT)), None)
+        .into_iter()
+        .map(|(family, _)| family)
+        .collect();
+    prefer_families(font_ctx, GenericFamily::Emoji, &families);
+    for generic in TEXT_GENERICS {
+        font_ctx
+            .collection
+            .append_generic_families(generic, families.iter().copied());
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "emoji"))]
+const fn register_bundled_emoji(_font_ctx: &mut FontContext) -> Result<()> {
+    Err(RenderError::EmojiNotBundled)
+}
+
+/// Put `families` ahead of whatever `generic` already resolves to.
+fn prefer_families(font_ctx: &mut FontContext, generic: GenericFamily, families: &[FamilyId]) {
+    let fallbacks: Vec<FamilyId> = font_ctx.collection.generic_families(generic).collect();
+    font_ctx
+        .collection
+        .set_generic_families(generic, families.iter().copied().chain(fallbacks));
+}
+
+/// Register font files from disk, returning the families the fonts were added to.
+///
+/// A collection (`.ttc`/`.otc`) registers every face it contains, each under its own family.
+fn register_fonts(font_ctx: &mut FontContext, font_paths: &[PathBuf]) -> Result<Vec<FamilyId>> {
+    let mut families = Vec::new();
+    for path in font_paths {
+        let data = fs::read(path).map_err(|source| RenderError::ReadFont {
+            source,
+            path: path.clone(),
+        })?;
+        let added = font_ctx
+            .collection
+            .register_fonts(Blob::new(Arc::new(data)), None);
+        if added.is_empty() {
+            return Err(RenderError::RegisterFont { path: path.clone() });
+        }
+        families.extend(added.into_iter().map(|(family, _)| family));
+    }
+
+    Ok(families)
+}
+
+/// Sorted, de-duplicated names of `families`.
+fn family_names(font_ctx: &mut FontContext, families: &[FamilyId]) -> Vec<String> {
+    let mut names: Vec<String> = families
+        .iter()
+        .filter_map(|&family| font_ctx.collection.family_name(family).map(str::to_owned))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::{error::Error as StdError, result::Result as StdResult};
+
+    use super::*;
+    use tempfile::tempdir;
+    type TestResult<T = ()> = StdResult<T, Box<dyn StdError>>;
+
+    const DEFAULT_WIDTH: u32 = 420;
+
+    #[test]
+    fn load_template_reads_file() -> TestResult {
+        let dir = tempdir()?;
+        let path = dir.path().join("template.html");
+        fs::write(&path, "hello template")?;
+
+        let contents = load_template(&path)?;
+        if contents != "hello template" {
+            return Err(format!("unexpected contents: {contents}").into());
+        }
+        Ok(())
+    }
+
+    #[derive(Serialize)]
+    struct TestData {
+        user: &'static str,
+        icon: &'static str,
+        message: &'static str,
+        width: u32,
+    }
+
+    #[test]
+    fn render_template_injects_data() -> TestResult {
+        let template = "<div>{{ user }} {{ icon }} {{ message }} {{ width }}</div>";
+        let data = TestData {
+            user: "User",
+            icon: "★",
+            message: "hi",
+            width: DEFAULT_WIDTH,
+        };
+
+        let rendered = render_template(template, &data)?;
+        if !rendered.contains("User") {
+            return Err("user missing".into());
+        }
+        if !rendered.contains("★") {
+            return Err("icon missing".into());
+        }
+        if !rendered.contains("hi") {
+            return Err("message missing".into());
+        }
+        if !rendered.contains(&DEFAULT_WIDTH.to_string()) {
+            return Err("width missing".into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn render_template_only_returns_the_expanded_html() -> TestResult {
+        let data = BTreeMap::from([("name", "<b>Ada</b>")]);
+
+        let html = render_template_only("<p>{{ name }}</p>", &data)?;
+
+        if html != "<p>&lt;b&gt;Ada&lt;&#x2f;b&gt;</p>" {
+            return Err(format!("unexpected HTML: {html}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn template_syntax_error_reports_line() -> TestResult {
+        let template = "<html>\n<body>\n{% if %}\n</body>\n</html>";
+
+        match render_template(template, &()) {
+            Err(
+                err @ RenderError::RegisterTemplate {
+                    line: Some(line), ..
+                },
+            ) if line > 0 => {
+                if !err.to_string().contains(&format!(":{line}")) {
+                    return Err(format!("location missing from message: {err}").into());
+                }
+                Ok(())
+            }
+            other => Err(format!("expected RegisterTemplate with a line, got {other:?}").into()),
+        }
+    }
+
+    #[test]
+    fn render_template_with_registers_custom_filters() -> TestResult {
+        let html = render_template_with(
+            "<p>{{ price | currency }}</p>",
+            &minijinja::context! { price => 12.5 },
+            |env| {
+                env.add_filter("currency", |value: f64| format!("${value:.2}"));
+            },
+        )?;
+
+        if html != "<p>$12.50</p>" {
+            return Err(format!("unexpected html: {html}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn render_template_strict_rejects_undefined_variables() -> TestResult {
+        let data = minijinja::context! { name => "Ada" };
+
+        let html = render_template_strict("Hi {{ name }}{{ title | default('') }}", &data)?;
+        if html != "Hi Ada" {
+            return Err(format!("unexpected html: {html}").into());
+        }
+        if render_template("Hi {{ nmae }}", &data)? != "Hi " {
+            return Err("render_template should keep printing undefined values as blanks".into());
+        }
+        match render_template_strict("Hi\n{{ nmae }}", &data) {
+            Err(RenderError::RenderTemplate { line: Some(2), .. }) => Ok(()),
+            other => Err(format!("expected RenderTemplate at line 2, got {other:?}").into()),
+        }
+    }
+
+    #[test]
+    fn render_template_with_limit_stops_runaway_loops() -> TestResult {
+        let template =
+            "{% for _ in range(n) %}{% for _ in range(n) %}<p>row</p>{% endfor %}{% endfor %}";
+
+        let html =
+            render_template_with_limit(template, &minijinja::context! { n => 2 }, 64, |_| {})?;
+        if html != "<p>row</p>".repeat(4) {
+            return Err(format!("unexpected html: {html}").into());
+        }
+        let bomb = minijinja::context! { n => 10_000 };
+        match render_template_with_limit(template, &bomb, 1024, |_| {}) {
+            Err(RenderError::TemplateTooLarge { size, limit: 1024 }) if size > 1024 => Ok(()),
+            other => Err(format!("expected TemplateTooLarge, got {other:?}").into()),
+        }
+    }
+
+    #[test]
+    fn safe_filter_renders_trusted_values_as_markup() -> TestResult {
+        let data = minijinja::context! { snippet => "<b>bold</b> & more" };
+
+        let html = render_template("<p>{{ snippet | safe }}</p>|{{ snippet }}", &data)?;
+
+        if html != "<p><b>bold</b> & more</p>|&lt;b&gt;bold&lt;&#x2f;b&gt; &amp; more" {
+            return Err(format!("unexpected html: {html}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn render_template_with_escape_controls_escaping() -> TestResult {
+        let data = minijinja::context! { value => "<b>\"A&B\"</b>" };
+
+        let html = render_template_with_escape("{{ value }}", &data, AutoEscapeMode::Html)?;
+        if html != render_template("{{ value }}", &data)? || html.contains('<') {
+            return Err(format!("html mode should escape like render_template: {html}").into());
+        }
+        let text = render_template_with_escape("{{ value }}", &data, AutoEscapeMode::None)?;
+        if text != "<b>\"A&B\"</b>" {
+            return Err(format!("none mode should be verbatim: {text}").into());
+        }
+        let custom = render_template_with_escape(
+            "{{ value }}",
+            &data,
+            AutoEscapeMode::Custom(|name| {
+                if Path::new(name)
+                    .extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("html"))
+                {
+                    minijinja::AutoEscape::None
+                } else {
+                    minijinja::AutoEscape::Html
+                }
+            }),
+        )?;
+        if custom != text {
+            return Err(format!("custom callback should have been used: {custom}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn render_template_dir_resolves_includes() -> TestResult {
+        let dir = tempdir()?;
+        fs::create_dir_all(dir.path().join("partials"))?;
+        fs::write(
+            dir.path().join("partials/header.html"),
+            "<h1>{{ title }}</h1>",
+        )?;
+        fs::write(
+            dir.path().join("card.html"),
+            "{% include \"partials/header.html\" %}<p>body</p>",
+        )?;
+
+        let html = render_template_dir(
+            dir.path(),
+            "card.html",
+            &minijinja::context! { title => "A & B" },
+        )?;
+
+        if html != "<h1>A &amp; B</h1><p>body</p>" {
+            return Err(format!("unexpected html: {html}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn render_template_dir_rejects_traversal() -> TestResult {
+        let root = tempdir()?;
+        let templates = root.path().join("templates");
+        fs::create_dir_all(&templates)?;
+        fs::write(root.path().join("secret.html"), "secret")?;
+        fs::write(
+            templates.join("card.html"),
+            "{% include \"../secret.html\" %}",
+        )?;
+
+        for entry in ["card.html", "../secret.html"] {
+            let result = render_template_dir(&templates, entry, &minijinja::context! {});
+            if result.is_ok() {
+                return Err(format!("{entry} should not read outside the directory").into());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn ensure_html_not_empty_rejects_whitespace() -> TestResult {
+        let rendered = render_template("  {{ body }}\n", &())?;
+
+        match ensure_html_not_empty(&rendered) {
+            Err(RenderError::EmptyHtml) => {}
+            other => return Err(format!("expected EmptyHtml, got {other:?}").into()),
+        }
+        ensure_html_not_empty("<div>content</div>")?;
+        Ok(())
+    }
+
+    #[test]
+    fn unpremultiply_alpha_restores_straight_colors() -> TestResult {
+        let mut pixels = vec![128, 0, 64, 128, 10, 20, 30, 0, 1, 2, 3, 255];
+
+        unpremultiply_alpha(&mut pixels);
+
+        if pixels != [255, 0, 128, 128, 0, 0, 0, 0, 1, 2, 3, 255] {
+            return Err(format!("unexpected pixels: {pixels:?}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn composite_over_background_fills_transparent_pixels() -> TestResult {
+        // Transparent, half-covered red, and opaque blue pixels (premultiplied).
+        let mut pixels = vec![0, 0, 0, 0, 128, 0, 0, 128, 0, 0, 255, 255];
+
+        composite_over_background(&mut pixels, [255, 255, 255, 255]);
+
+        if pixels != [255, 255, 255, 255, 255, 127, 127, 255, 0, 0, 255, 255] {
+            return Err(format!("unexpected pixels: {pixels:?}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn dither_breaks_up_gradient_bands() -> TestResult {
+        // Four rows of a 256px gradient stepping through 16 levels, 16px per band.
+        let width = 256;
+        let mut pixels: Vec<u8> = (0..4)
+            .flat_map(|_row| 0..width)
+            .flat_map(|x| {
+                let level = u8::try_from(x / 16 + 100).unwrap_or(u8::MAX);
+                [level, level, level, u8::MAX]
+            })
+            .collect();
+        let widest_band = |rgba: &[u8]| {
+            rgba.chunks_exact(4)
+                .collect::<Vec<_>>()
+                .chunk_by(|left, right| left == right)
+                .map(<[_]>::len)
+                .max()
+                .unwrap_or(0)
+        };
+
+        let banded = widest_band(&pixels);
+        let total: u32 = pixels.iter().map(|&channel| u32::from(channel)).sum();
+        dither_rgba(&mut pixels, width, false);
+        let dithered = widest_band(&pixels);
+
+        if banded != 16 || dithered > 3 {
+            return Err(format!("bands of {dithered}px remain (from {banded}px)").into());
+        }
+        let dithered_total: u32 = pixels.iter().map(|&channel| u32::from(channel)).sum();
+        if dithered_total != total {
+            return Err(format!("dithering shifted the brightness: {dithered_total}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn dither_keeps_translucent_gradients_straight() -> TestResult {
+        // A half-transparent premultiplied gradient, as the renderer paints it.
+        let width = 64;
+        let premultiplied: Vec<u8> = (0..4)
+            .flat_map(|_row| 0..width)
+            .flat_map(|x| {
+                let level = u8::try_from(x / 16 + 100).unwrap_or(u8::MAX);
+                [level, level, level, 128]
+            })
+            .collect();
+        let mut plain = premultiplied.clone();
+        finish_pixels(&mut plain, width, false, false);
+        let mut dithered = premultiplied;
+        finish_pixels(&mut dithered, width, false, true);
+
+        // Dithering the straight colors moves each by at most one step, where dithering
+        // the premultiplied ones would move them by two once the alpha is divided out.
+        let steps: Vec<u8> = plain
+            .iter()
+            .zip(&dithered)
+            .map(|(&before, &after)| before.abs_diff(after))
+            .collect();
+        if steps.iter().any(|&step| step > 1) || !steps.contains(&1) {
+            return Err(format!("unexpected dither steps: {steps:?}").into());
+        }
+        let sum = |rgba: &[u8]| rgba.iter().map(|&channel| u32::from(channel)).sum::<u32>();
+        if sum(&dithered) != sum(&plain) {
+            return Err(format!("dithering shifted the brightness: {dithered:?}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn translucent_div_blends_over_white_background() -> TestResult {
+        let html = "<html><body style=\"margin:0\">\
+            <div style=\"width:64px;height:48px;background:rgba(255,0,0,0.5)\"></div>\
+            </body></html>";
+
+        let transparent =
+            render_html_to_png_bytes_with_background(html, 64, 48, 1.0, 0.0, &[], None)?;
+        let on_white = render_html_to_png_bytes_with_background(
+            html,
+            64,
+            48,
+            1.0,
+            0.0,
+            &[],
+            Some([255, 255, 255, 255]),
+        )?;
+
+        let transparent = image::load_from_memory(&transparent)?.to_rgba8();
+        let [_, _, _, transparent_alpha] = transparent.get_pixel(10, 10).0;
+        if transparent_alpha.abs_diff(128) > 1 {
+            return Err(
+                format!("default canvas should stay translucent: {transparent_alpha}").into(),
+            );
+        }
+        let on_white = image::load_from_memory(&on_white)?.to_rgba8();
+        let [red, green, blue, alpha] = on_white.get_pixel(10, 10).0;
+        if red != 255 || green.abs_diff(127) > 2 || blue.abs_diff(127) > 2 || alpha != 255 {
+            return Err(format!("unexpected blended pixel: {red},{green},{blue},{alpha}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn png_alpha_matches_requested_convention() -> TestResult {
+        let html = "<html><body style=\"margin:0\">\
+            <div style=\"width:64px;height:48px;background:rgba(255,0,0,0.5)\"></div>\
+            </body></html>";
+
+        let straight = render_html_to_png_bytes_with_alpha(html, 64, 48, 1.0, 0.0, &[], false)?;
+        let premultiplied = render_html_to_png_bytes_with_alpha(html, 64, 48, 1.0, 0.0, &[], true)?;
+
+        let straight = image::load_from_memory(&straight)?.to_rgba8();
+        let premultiplied = image::load_from_memory(&premultiplied)?.to_rgba8();
+        for (x, y) in [(0, 0), (63, 0), (0, 47), (63, 47)] {
+            let [straight_red, _, _, straight_alpha] = straight.get_pixel(x, y).0;
+            if straight_red != 255 || straight_alpha.abs_diff(128) > 1 {
+                return Err(format!(
+                    "straight edge pixel ({x},{y}) = {straight_red},{straight_alpha}"
+                )
+                .into());
+            }
+            let [premul_red, _, _, premul_alpha] = premultiplied.get_pixel(x, y).0;
+            if premul_red.abs_diff(premul_alpha) > 1 {
+                return Err(format!(
+                    "premultiplied edge pixel ({x},{y}) = {premul_red},{premul_alpha}"
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn rgba_pixels_composite_to_the_same_color_in_both_conventions() -> TestResult {
+        let html = "<html><body style=\"margin:0\">\
+            <div style=\"width:16px;height:16px;background:rgba(255,0,0,0.5)\"></div>\
+            </body></html>";
+        // Source-over onto opaque white: straight alpha scales the color, premultiplied
+        // color channels are added as-is.
+        let over_white = |pixels: &RgbaPixels| -> Option<[u8; 3]> {
+            let Some(&[red, green, blue, alpha]) = pixels.rgba.get(..4) else {
+                return None;
+            };
+            let alpha = f64::from(alpha) / 255.0;
+            let channel = |value: u8| {
+                let color = if pixels.premultiplied {
+                    f64::from(value)
+                } else {
+                    f64::from(value) * alpha
+                };
+                (color + 255.0 * (1.0 - alpha)).round()
+            };
+            Some([red, green, blue].map(|value| {
+                #[allow(
+                    clippy::cast_possible_truncation,
+                    clippy::cast_sign_loss,
+                    reason = "Blending 0..=255 channels stays within 0..=255"
+                )]
+                let blended = channel(value) as u8;
+                blended
+            }))
+        };
+
+        let opts = RenderOptions::new(16, 16);
+        let straight = render_html_to_rgba_pixels(html, &opts)?;
+        let premultiplied = render_html_to_rgba_pixels(html, &opts.premultiplied_alpha(true))?;
+
+        for pixels in [&straight, &premultiplied] {
+            let Some([red, green, blue]) = over_white(pixels) else {
+                return Err("render has no pixels".into());
+            };
+            if red != 255 || green.abs_diff(127) > 2 || blue.abs_diff(127) > 2 {
+                return Err(format!(
+                    "premultiplied={} composited to {red},{green},{blue}",
+                    pixels.premultiplied
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn rgba_buffer_matches_requested_dimensions() -> TestResult {
+        let opts = RenderOptions::new(24, 12).device_pixel_ratio(2.0);
+
+        let (rgba, width, height) = render_html_to_rgba("<div>raw</div>", &opts)?;
+
+        if (width, height) != (48, 24) || rgba.len() != 48 * 24 * 4 {
+            return Err(
+                format!("unexpected buffer: {width}x{height}, {} bytes", rgba.len()).into(),
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn collect_warnings_reports_unloaded_resources() -> TestResult {
+        let html = "<html><body><img src=\"https://example.com/logo.png\"></body></html>";
+
+        let quiet = render_html_output(html, &RenderOptions::new(32, 16))?;
+        let noisy = render_html_output(html, &RenderOptions::new(32, 16).collect_warnings(true))?;
+
+        if !quiet.warnings.is_empty() {
+            return Err(format!("warnings without collect_warnings: {:?}", quiet.warnings).into());
+        }
+        if !noisy
+            .warnings
+            .iter()
+            .any(|warning| warning.contains("https://example.com/logo.png"))
+        {
+            return Err(format!("missing resource warning: {:?}", noisy.warnings).into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn sanitize_renders_script_laden_input_without_fetching() -> TestResult {
+        let html = "<html><body onload=\"steal()\"><script>document.body.remove()</script>\
+            <img src=\"https://example.com/track.png\" onerror=\"steal()\">\
+            <div style=\"width: 8px; height: 8px; background: url(https://example.com/bg.png)\">\
+            </div></body></html>";
+        let opts = RenderOptions::new(32, 16)
+            .sanitize(true)
+            .collect_warnings(true);
+
+        let output = render_html_output(html, &opts)?;
+
+        if (output.width, output.height) != (32, 16) {
+            return Err(format!("unexpected size {}x{}", output.width, output.height).into());
+        }
+        if !output.warnings.is_empty() {
+            return Err(format!("sanitized page requested: {:?}", output.warnings).into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn layout_html_reports_warnings_without_painting() -> TestResult {
+        let html = "<html><body><img src=\"https://example.com/logo.png\"></body></html>";
+
+        let warnings = layout_html(html, &RenderOptions::new(32, 16).collect_warnings(true))?;
+
+        if !warnings
+            .iter()
+            .any(|warning| warning.contains("https://example.com/logo.png"))
+        {
+            return Err(format!("missing resource warning: {warnings:?}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn debug_boxes_change_the_render() -> TestResult {
+        let html = "<html><body><div style=\"width:40px;height:20px\">Hi</div></body></html>";
+
+        let normal = render_html_to_png_bytes(html, 64, 48, 1.0, DEFAULT_ANIMATION_TIME, &[])?;
+        let debug = render_html_to_debug_png_bytes(html, 64, 48, 1.0, DEFAULT_ANIMATION_TIME, &[])?;
+
+        if normal == debug {
+            return Err("debug overlay should change the output".into());
+        }
+        Ok(())
+    }
 
-    use super::*;
-    use tempfile::tempdir;
-    type TestResult<T = ()> = StdResult<T, Box<dyn StdError>>;
+    #[test]
+    fn resize_to_resamples_the_finished_render() -> TestResult {
+        let html = "<html><body style=\"margin:0;background:rgb(0,0,255)\"></body></html>";
+        let opts = RenderOptions::new(64, 48).resize_to(Some((32, 0)));
 
-    const DEFAULT_WIDTH: u32 = 420;
+        let output = render_html_output(html, &opts)?;
+        let pixels = render_html_to_rgba_pixels(html, &opts.resize_filter(ResizeFilter::Nearest))?;
+
+        if (output.width, output.height) != (32, 24) {
+            return Err(format!("unexpected size {}x{}", output.width, output.height).into());
+        }
+        if (pixels.width, pixels.height, pixels.rgba.len()) != (32, 24, 32 * 24 * 4) {
+            return Err(format!("unexpected pixels {}x{}", pixels.width, pixels.height).into());
+        }
+        Ok(())
+    }
 
     #[test]
-    fn load_template_reads_file() -> TestResult {
-        let dir = tempdir()?;
-        let path = dir.path().join("template.html");
-        fs::write(&path, "hello template")?;
+    fn supersample_smooths_text_edges() -> TestResult {
+        let fonts = Path::new(env!("CARGO_MANIFEST_DIR")).join("../assets/fonts");
+        let html = "<html><body style=\"margin:0;font:italic 14px sans-serif\">\
+            WAVY /// xyz</body></html>";
+        let opts =
+            RenderOptions::new(96, 24).default_font(Some(fonts.join("FiraSans-Regular.ttf")));
 
-        let contents = load_template(&path)?;
-        if contents != "hello template" {
-            return Err(format!("unexpected contents: {contents}").into());
+        let (plain, _, _) = render_html_to_rgba(html, &opts)?;
+        let (smooth, _, _) = render_html_to_rgba(html, &opts.clone().supersample(4))?;
+
+        // Summed alpha steps between horizontal neighbours: harder edges step further.
+        let edge_contrast = |rgba: &[u8]| -> u64 {
+            let alpha: Vec<u8> = rgba
+                .chunks_exact(4)
+                .filter_map(|pixel| pixel.get(3).copied())
+                .collect();
+            alpha
+                .chunks_exact(96)
+                .flat_map(|row| row.windows(2))
+                .map(|pair| {
+                    u64::from(
+                        pair.first()
+                            .zip(pair.get(1))
+                            .map_or(0, |(left, right)| left.abs_diff(*right)),
+                    )
+                })
+                .sum()
+        };
+        let (plain_contrast, smooth_contrast) = (edge_contrast(&plain), edge_contrast(&smooth));
+        if plain_contrast == 0 || smooth_contrast >= plain_contrast {
+            return Err(format!(
+                "supersampling did not soften edges: {smooth_contrast} vs {plain_contrast}"
+            )
+            .into());
         }
         Ok(())
     }
 
-    #[derive(Serialize)]
-    struct TestData {
-        user: &'static str,
-        icon: &'static str,
-        message: &'static str,
-        width: u32,
+    #[test]
+    #[cfg(feature = "jpeg")]
+    fn render_html_to_jpeg_bytes_returns_jpeg() -> TestResult {
+        let html = "<html><body style=\"background:white\"><div>Hello jpeg</div></body></html>";
+
+        let bytes = render_html_to_jpeg_bytes(html, 64, 48, 1.0, DEFAULT_ANIMATION_TIME, &[], 85)?;
+
+        if !bytes.starts_with(&[0xFF, 0xD8]) {
+            return Err("output is not a JPEG".into());
+        }
+        Ok(())
     }
 
     #[test]
-    fn render_template_injects_data() -> TestResult {
-        let template = "<div>{{ user }} {{ icon }} {{ message }} {{ width }}</div>";
-        let data = TestData {
-            user: "User",
-            icon: "★",
-            message: "hi",
-            width: DEFAULT_WIDTH,
+    fn render_html_dispatches_on_output_format() -> TestResult {
+        let html = "<html><body style=\"background:white\"><div>formats</div></body></html>";
+        let opts = RenderOptions::new(32, 16);
+        let cases: &[(OutputFormat, &[u8])] = &[
+            (OutputFormat::default(), b"\x89PNG\r\n\x1a\n"),
+            #[cfg(feature = "jpeg")]
+            (OutputFormat::Jpeg { quality: 85 }, &[0xFF, 0xD8]),
+            #[cfg(feature = "webp")]
+            (OutputFormat::WEBP, b"RIFF"),
+            (OutputFormat::Bmp, b"BM"),
+            (OutputFormat::Tiff, b"II*\0"),
+        ];
+
+        for &(format, magic) in cases {
+            let bytes = render_html(html, &opts, format)?;
+            if !bytes.starts_with(magic) {
+                return Err(
+                    format!("{} output has the wrong magic bytes", format.mime_type()).into(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn render_html_to_bmp_bytes_returns_bmp() -> TestResult {
+        let html = "<html><body><div>Hello bmp</div></body></html>";
+
+        let bytes = render_html_to_bmp_bytes(html, 64, 48, 1.0, DEFAULT_ANIMATION_TIME, &[])?;
+
+        if !bytes.starts_with(b"BM") {
+            return Err("output is not a BMP".into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn render_html_to_tiff_bytes_returns_tiff() -> TestResult {
+        let html = "<html><body><div>Hello tiff</div></body></html>";
+
+        let bytes = render_html_to_tiff_bytes(html, 64, 48, 1.0, DEFAULT_ANIMATION_TIME, &[])?;
+
+        if !bytes.starts_with(b"II*\0") && !bytes.starts_with(b"MM\0*") {
+            return Err("output is not a TIFF".into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "webp")]
+    fn render_html_to_webp_bytes_returns_webp() -> TestResult {
+        let html = "<html><body><div>Hello webp</div></body></html>";
+
+        for lossless in [true, false] {
+            let bytes = render_html_to_webp_bytes(
+                html,
+                64,
+                48,
+                1.0,
+                DEFAULT_ANIMATION_TIME,
+                &[],
+                lossless,
+                DEFAULT_WEBP_QUALITY,
+            )?;
+            if bytes.get(0..4) != Some(b"RIFF") || bytes.get(8..12) != Some(b"WEBP") {
+                return Err(format!("output is not a WebP (lossless: {lossless})").into());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn render_html_to_png_output_reports_dimensions() -> TestResult {
+        let html = "<html><body><div>Hello output</div></body></html>";
+
+        let output = render_html_to_png_output(html, 72, 40, 1.0, DEFAULT_ANIMATION_TIME, &[])?;
+
+        if (output.width, output.height) != (72, 40) {
+            return Err(format!("unexpected size {}x{}", output.width, output.height).into());
+        }
+        let ihdr_width = output.bytes.get(16..20).map(<[u8]>::to_vec);
+        if ihdr_width != Some(72_u32.to_be_bytes().to_vec()) {
+            return Err("PNG header width does not match the reported width".into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn zero_height_measures_content() -> TestResult {
+        let html = "<html><body style=\"margin:0\">\
+            <div style=\"height:300px\">tall</div></body></html>";
+
+        let output = render_html_output(html, &RenderOptions::new(64, 0))?;
+
+        if (output.width, output.height) != (64, 300) {
+            return Err(format!("measured {}x{}", output.width, output.height).into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn zero_width_shrinks_to_the_content() -> TestResult {
+        let html = "<html><body style=\"margin:0\">\
+            <div style=\"width:40px;height:20px;background:red\"></div></body></html>";
+
+        let output = render_html_output(html, &RenderOptions::new(0, 0))?;
+
+        if (output.width, output.height) != (40, 20) {
+            return Err(format!("measured {}x{}", output.width, output.height).into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn zero_height_rejects_content_taller_than_max() -> TestResult {
+        let html = "<html><body style=\"margin:0\">\
+            <div style=\"height:5000px\"></div></body></html>";
+
+        match render_html_output(html, &RenderOptions::new(64, 0)) {
+            Err(RenderError::ContentTooTall { max, .. }) if max == MAX_DIMENSION => Ok(()),
+            other => Err(format!(
+                "expected ContentTooTall, got {:?}",
+                other.map(|out| out.height)
+            )
+            .into()),
+        }
+    }
+
+    #[test]
+    fn max_height_rejects_taller_content_before_painting() -> TestResult {
+        let html = "<html><body style=\"margin:0\">\
+            <div style=\"height:200px\"></div></body></html>";
+
+        match layout_html(html, &RenderOptions::new(64, 0).max_height(100)) {
+            Err(RenderError::ContentTooTall { max: 100, .. }) => Ok(()),
+            other => Err(format!("expected ContentTooTall, got {other:?}").into()),
+        }
+    }
+
+    #[test]
+    fn best_compression_is_not_larger_than_default() -> TestResult {
+        let html = "<html><body style=\"margin:0\">\
+            <div style=\"width:96px;height:64px;background:linear-gradient(#7c3aed,#06b6d4)\">\
+            Compress me</div></body></html>";
+
+        let default = render_html_to_png_bytes(html, 96, 64, 1.0, DEFAULT_ANIMATION_TIME, &[])?;
+        let best = render_html_to_png_bytes_with(
+            html,
+            96,
+            64,
+            1.0,
+            DEFAULT_ANIMATION_TIME,
+            &[],
+            CompressionLevel::Best,
+        )?;
+
+        if best.len() > default.len() {
+            return Err(format!("best {} > default {} bytes", best.len(), default.len()).into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn base_dir_loads_relative_images() -> TestResult {
+        let dir = tempdir()?;
+        image::save_buffer(
+            dir.path().join("logo.png"),
+            &[255, 0, 0, 255].repeat(16 * 16),
+            16,
+            16,
+            image::ColorType::Rgba8,
+        )?;
+        let html = "<html><body style=\"margin:0\">\
+            <img src=\"logo.png\" style=\"width:16px;height:16px;display:block\"></body></html>";
+
+        let opts = RenderOptions::new(32, 32).base_dir(Some(dir.path().to_path_buf()));
+        let bytes = render_html_bytes(html, &opts)?;
+
+        let image = image::load_from_memory(&bytes)?.to_rgba8();
+        let [red, green, blue, alpha] = image.get_pixel(8, 8).0;
+        if (red, green, blue, alpha) != (255, 0, 0, 255) {
+            return Err(format!("image not drawn: {red},{green},{blue},{alpha}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn base_dir_applies_linked_stylesheets() -> TestResult {
+        let dir = tempdir()?;
+        fs::write(
+            dir.path().join("card.css"),
+            "body { background: rgb(0, 0, 255); }",
+        )?;
+        let html = "<html><head><link rel=\"stylesheet\" href=\"card.css\"></head>\
+            <body style=\"margin:0\"></body></html>";
+
+        let opts = RenderOptions::new(16, 16).base_dir(Some(dir.path().to_path_buf()));
+        let (rgba, _, _) = render_html_to_rgba(html, &opts)?;
+
+        let Some(&[red, green, blue, alpha]) = rgba.get(..4) else {
+            return Err("empty render".into());
         };
+        if (red, green, blue, alpha) != (0, 0, 255, 255) {
+            return Err(format!("stylesheet not applied: {red},{green},{blue},{alpha}").into());
+        }
+        Ok(())
+    }
 
-        let rendered = render_template(template, &data)?;
-        if !rendered.contains("User") {
-            return Err("user missing".into());
+    #[test]
+    fn missing_stylesheet_is_reported_as_a_warning() -> TestResult {
+        let dir = tempdir()?;
+        let html = "<html><head><link rel=\"stylesheet\" href=\"missing.css\"></head></html>";
+
+        let opts = RenderOptions::new(16, 16)
+            .base_dir(Some(dir.path().to_path_buf()))
+            .collect_warnings(true);
+        let output = render_html_output(html, &opts)?;
+
+        if !output
+            .warnings
+            .iter()
+            .any(|warning| warning.contains("missing.css") && warning.contains("file not found"))
+        {
+            return Err(format!("missing stylesheet warning: {:?}", output.warnings).into());
         }
-        if !rendered.contains("★") {
-            return Err("icon missing".into());
+        Ok(())
+    }
+
+    #[test]
+    fn png_data_uri_decodes_to_png() -> TestResult {
+        let html = "<html><body><div>Hello data uri</div></body></html>";
+
+        let uri = render_html_to_png_data_uri(html, 32, 24, 1.0, DEFAULT_ANIMATION_TIME, &[])?;
+
+        let payload = uri
+            .strip_prefix("data:image/png;base64,")
+            .ok_or("missing data uri prefix")?;
+        let decoded = BASE64.decode(payload)?;
+        let image = image::load_from_memory_with_format(&decoded, image::ImageFormat::Png)?;
+        if (image.width(), image.height()) != (32, 24) {
+            return Err("decoded PNG has the wrong size".into());
         }
-        if !rendered.contains("hi") {
-            return Err("message missing".into());
+        Ok(())
+    }
+
+    #[test]
+    fn data_uri_uses_the_format_mime_type() -> TestResult {
+        let html = "<html><body><div>Hello data uri</div></body></html>";
+        let opts = RenderOptions::new(32, 24);
+
+        let uri = render_html_data_uri(html, &opts, OutputFormat::Bmp)?;
+
+        let payload = uri
+            .strip_prefix("data:image/bmp;base64,")
+            .ok_or("missing data uri prefix")?;
+        let decoded = BASE64.decode(payload)?;
+        let image = image::load_from_memory_with_format(&decoded, image::ImageFormat::Bmp)?;
+        if (image.width(), image.height()) != (32, 24) {
+            return Err("decoded BMP has the wrong size".into());
         }
-        if !rendered.contains(&DEFAULT_WIDTH.to_string()) {
-            return Err("width missing".into());
+        Ok(())
+    }
+
+    #[test]
+    fn timeout_render_returns_png_within_budget() -> TestResult {
+        let html = "<html><body><div>in time</div></body></html>";
+        let opts = RenderOptions::new(32, 16);
+
+        let bytes = render_html_bytes_timeout(html, &opts, Duration::from_secs(30))?;
+
+        if !bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            return Err("expected PNG bytes".into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn timeout_render_gives_up_when_budget_is_exhausted() -> TestResult {
+        let html = "<html><body><div>too slow</div></body></html>";
+        let opts = RenderOptions::new(1024, 1024);
+
+        match render_html_bytes_timeout(html, &opts, Duration::ZERO) {
+            Err(RenderError::Timeout { timeout }) if timeout.is_zero() => Ok(()),
+            other => Err(format!("expected Timeout, got {:?}", other.map(|_| ())).into()),
+        }
+    }
+
+    #[test]
+    fn cancellable_render_stops_waiting_once_cancelled() -> TestResult {
+        let html = "<html><body><div>cancelled</div></body></html>";
+        let opts = RenderOptions::new(32, 16);
+
+        let bytes = render_html_bytes_cancellable(html, &opts, &AtomicBool::new(false))?;
+        if !bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            return Err("expected PNG bytes".into());
+        }
+        match render_html_bytes_cancellable(html, &opts, &AtomicBool::new(true)) {
+            Err(RenderError::Cancelled) => Ok(()),
+            other => Err(format!("expected Cancelled, got {:?}", other.map(|_| ())).into()),
+        }
+    }
+
+    #[test]
+    fn clip_selector_crops_to_the_matching_element() -> TestResult {
+        let html = r#"<html><body style="margin:0">
+            <div id="card" style="position:absolute;left:10px;top:5px;width:40px;height:20px;background:red"></div>
+        </body></html>"#;
+        let opts = RenderOptions::new(100, 100).clip_selector(Some("#card".to_owned()));
+
+        let output = render_html_output(html, &opts)?;
+
+        if (output.width, output.height) != (40, 20) {
+            return Err(format!("unexpected size {}x{}", output.width, output.height).into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn clip_selector_without_a_match_is_an_error() -> TestResult {
+        let html = "<html><body><div>no card here</div></body></html>";
+        let opts = RenderOptions::new(100, 100).clip_selector(Some("#card".to_owned()));
+
+        match render_html_output(html, &opts) {
+            Err(RenderError::SelectorNotFound { selector }) if selector == "#card" => Ok(()),
+            other => Err(format!("expected SelectorNotFound, got {:?}", other.map(|_| ())).into()),
+        }
+    }
+
+    #[test]
+    fn unreadable_default_font_is_reported() -> TestResult {
+        let dir = tempdir()?;
+        let missing = dir.path().join("missing.ttf");
+        let opts = RenderOptions::new(64, 48).default_font(Some(missing.clone()));
+
+        match render_html_output("<div>text</div>", &opts) {
+            Err(RenderError::ReadFont { path, .. }) if path == missing => Ok(()),
+            other => Err(format!("expected ReadFont, got {:?}", other.map(|_| ())).into()),
+        }
+    }
+
+    #[test]
+    fn default_font_draws_generic_families() -> TestResult {
+        let bold = Path::new(env!("CARGO_MANIFEST_DIR")).join("../assets/fonts/FiraSans-Bold.ttf");
+        let html = "<html><body style=\"margin:0;font:32px sans-serif\">Hg</body></html>";
+        let plain = RenderOptions::new(96, 48);
+        let with_default = plain.clone().default_font(Some(bold));
+
+        let (without, _, _) = render_html_to_rgba(html, &plain)?;
+        let (with, _, _) = render_html_to_rgba(html, &with_default)?;
+
+        if without == with {
+            return Err("sans-serif text ignored the default font".into());
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "emoji")]
+    #[test]
+    fn enable_emoji_draws_emoji_without_font_paths() -> TestResult {
+        let html = "<html><body style=\"margin:0;font-size:32px\">\u{1F600}</body></html>";
+        let opts = RenderOptions::new(48, 48).enable_emoji(true);
+
+        let (rgba, _, _) = render_html_to_rgba(html, &opts)?;
+
+        if !rgba.chunks_exact(4).any(|pixel| pixel.get(3) > Some(&0)) {
+            return Err("no emoji pixels were painted".into());
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "emoji"))]
+    #[test]
+    fn enable_emoji_needs_the_emoji_feature() -> TestResult {
+        let opts = RenderOptions::new(48, 48).enable_emoji(true);
+
+        match render_html_output("<div>\u{1F600}</div>", &opts) {
+            Err(RenderError::EmojiNotBundled) => Ok(()),
+            other => Err(format!("expected EmojiNotBundled, got {:?}", other.map(|_| ())).into()),
+        }
+    }
+
+    #[test]
+    fn fallback_fonts_cover_characters_the_first_face_lacks() -> TestResult {
+        let fonts = Path::new(env!("CARGO_MANIFEST_DIR")).join("../assets/fonts");
+        let latin = fonts.join("FiraSans-Regular.ttf");
+        let html = "<html><body style=\"margin:0;font:32px sans-serif\">Hg\u{1F600}</body></html>";
+        let latin_only = RenderOptions::new(96, 48).fallback_fonts(vec![latin.clone()]);
+        let with_emoji = latin_only
+            .clone()
+            .fallback_fonts(vec![latin, fonts.join("NotoEmoji-Regular.ttf")]);
+
+        let (without, _, _) = render_html_to_rgba(html, &latin_only)?;
+        let (with, _, _) = render_html_to_rgba(html, &with_emoji)?;
+
+        if without == with {
+            return Err("the second fallback font was not used".into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn animation_time_counts_from_the_first_keyframe() -> TestResult {
+        let html = "<html><head><style>\
+            @keyframes fade { from { background: rgb(255, 0, 0); } to { background: rgb(0, 0, 255); } }\
+            body { margin: 0; animation: fade 10s linear forwards; }\
+            </style></head><body></body></html>";
+
+        for (time, expected) in [(0.0, [255, 0, 0, 255]), (20.0, [0, 0, 255, 255])] {
+            let opts = RenderOptions::new(8, 8).animation_time(time);
+            let (rgba, _, _) = render_html_to_rgba(html, &opts)?;
+            if rgba.get(..4) != Some(&expected[..]) {
+                return Err(
+                    format!("at {time}s expected {expected:?}, got {:?}", rgba.get(..4)).into(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn crop_keeps_the_requested_rectangle() -> TestResult {
+        let html = "<html><body><div>crop</div></body></html>";
+        let opts = RenderOptions::new(32, 16).crop(Some((2, 3, 10, 5)));
+
+        let output = render_html_output(html, &opts)?;
+
+        if (output.width, output.height) != (10, 5) {
+            return Err(format!("unexpected size {}x{}", output.width, output.height).into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn crop_outside_the_render_is_rejected() -> TestResult {
+        let html = "<html><body><div>crop</div></body></html>";
+        for rect in [(30, 0, 10, 5), (0, 0, 0, 5), (0, u32::MAX, 1, 2)] {
+            let opts = RenderOptions::new(32, 16).crop(Some(rect));
+            if !matches!(
+                render_html_output(html, &opts),
+                Err(RenderError::CropOutOfBounds { .. })
+            ) {
+                return Err(format!("crop {rect:?} should be rejected").into());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn device_pixel_ratio_doubles_the_pixel_dimensions() -> TestResult {
+        let html = "<html><body><div>retina</div></body></html>";
+        let opts = RenderOptions::new(40, 24).device_pixel_ratio(2.0);
+
+        let output = render_html_output(html, &opts)?;
+
+        if (output.width, output.height) != (80, 48) {
+            return Err(format!("unexpected size {}x{}", output.width, output.height).into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn png_writer_streams_the_same_bytes_as_the_buffered_render() -> TestResult {
+        let html = "<html><body><div>stream</div></body></html>";
+        let opts = RenderOptions::new(48, 24).compression(CompressionLevel::Best);
+
+        let mut streamed = Vec::new();
+        render_html_to_png_writer(html, &opts, &mut streamed)?;
+
+        if streamed != render_html_bytes(html, &opts)? {
+            return Err("streamed PNG differs from the in-memory PNG".into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn png_writer_reports_write_failures() -> TestResult {
+        struct FailingWriter;
+        impl Write for FailingWriter {
+            fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    "client went away",
+                ))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let html = "<html><body><div>stream</div></body></html>";
+        match render_html_to_png_writer(html, &RenderOptions::new(16, 16), FailingWriter) {
+            Err(RenderError::StreamPng { .. }) => Ok(()),
+            other => Err(format!("expected StreamPng, got {other:?}").into()),
+        }
+    }
+
+    #[test]
+    fn overflowing_dimensions_are_rejected_before_allocating() -> TestResult {
+        let html = "<html><body><div>huge</div></body></html>";
+        let opts = RenderOptions::new(u32::MAX, u32::MAX);
+
+        match render_html_bytes(html, &opts) {
+            Err(RenderError::DimensionsTooLarge { width, height })
+                if width == u32::MAX && height == u32::MAX =>
+            {
+                Ok(())
+            }
+            other => {
+                Err(format!("expected DimensionsTooLarge, got {:?}", other.map(|_| ())).into())
+            }
+        }
+    }
+
+    #[test]
+    fn png_metadata_chunks_match_options() -> TestResult {
+        let html = "<html><body><div>dpi</div></body></html>";
+        let opts = RenderOptions::new(16, 16).dpi(Some(300)).srgb(true);
+
+        let bytes = render_html_bytes(html, &opts)?;
+
+        let reader = png::Decoder::new(io::Cursor::new(bytes)).read_info()?;
+        let info = reader.info();
+        let dims = info.pixel_dims.ok_or("missing pHYs chunk")?;
+        if (dims.xppu, dims.yppu, dims.unit) != (11_811, 11_811, png::Unit::Meter) {
+            return Err(format!("unexpected pHYs: {dims:?}").into());
+        }
+        if info.srgb.is_none() || info.source_gamma.is_none() {
+            return Err("missing sRGB/gAMA chunks".into());
         }
         Ok(())
     }
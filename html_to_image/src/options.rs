@@ -0,0 +1,428 @@
+use std::path::PathBuf;
+
+use image::imageops::FilterType;
+
+#[cfg(feature = "webp")]
+use crate::DEFAULT_WEBP_QUALITY;
+use crate::{DEFAULT_ANIMATION_TIME, DEFAULT_DEVICE_PIXEL_RATIO, DEFAULT_SCALE, MAX_DIMENSION};
+
+/// PNG compression effort: higher levels trade encode time for smaller files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompressionLevel {
+    /// Fastest encoding; the historical output of this crate.
+    #[default]
+    Fast,
+    /// zlib's default level.
+    Balanced,
+    /// Maximum compression, for batch jobs where size matters more than CPU.
+    Best,
+}
+
+/// Resampling filter for [`RenderOptions::resize_to`], from fastest to sharpest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResizeFilter {
+    /// Nearest neighbour: blocky, but keeps pixel art crisp.
+    Nearest,
+    /// Linear (tent) filter.
+    Triangle,
+    /// Cubic filter.
+    CatmullRom,
+    /// Gaussian filter; soft.
+    Gaussian,
+    /// Lanczos with a window of 3; the sharpest downscale.
+    #[default]
+    Lanczos3,
+}
+
+impl From<ResizeFilter> for FilterType {
+    fn from(filter: ResizeFilter) -> Self {
+        match filter {
+            ResizeFilter::Nearest => Self::Nearest,
+            ResizeFilter::Triangle => Self::Triangle,
+            ResizeFilter::CatmullRom => Self::CatmullRom,
+            ResizeFilter::Gaussian => Self::Gaussian,
+            ResizeFilter::Lanczos3 => Self::Lanczos3,
+        }
+    }
+}
+
+/// How template values are escaped, for [`crate::render_template_with_escape`].
+#[derive(Debug, Clone, Copy, Default)]
+#[non_exhaustive]
+pub enum AutoEscapeMode {
+    /// Escape values for HTML, which also covers inline SVG.
+    #[default]
+    Html,
+    /// Insert values verbatim, e.g. for plain text or hand-built markup.
+    None,
+    /// Pick the escaping from the template name, as `MiniJinja`'s auto-escape callback.
+    Custom(fn(&str) -> minijinja::AutoEscape),
+}
+
+impl AutoEscapeMode {
+    /// Install this mode as `env`'s auto-escape callback, e.g. from the `setup` closure of
+    /// [`crate::render_template_with`].
+    pub fn apply(self, env: &mut minijinja::Environment<'_>) {
+        match self {
+            Self::Html => env.set_auto_escape_callback(|_| minijinja::AutoEscape::Html),
+            Self::None => env.set_auto_escape_callback(|_| minijinja::AutoEscape::None),
+            Self::Custom(callback) => env.set_auto_escape_callback(callback),
+        }
+    }
+}
+
+/// Image codec and its encoding knobs, for [`crate::render_html`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+#[allow(
+    variant_size_differences,
+    reason = "The largest variant is 8 bytes; boxing it would only add indirection"
+)]
+pub enum OutputFormat {
+    /// PNG with straight alpha (or premultiplied, per `RenderOptions::premultiplied_alpha`).
+    Png { compression: CompressionLevel },
+    /// JPEG; alpha is dropped, so transparent areas come out black. `quality` is clamped
+    /// to `1..=100`.
+    #[cfg(feature = "jpeg")]
+    Jpeg { quality: u8 },
+    /// WebP with alpha. `quality` (`0.0..=100.0`) only applies when `lossless` is false.
+    #[cfg(feature = "webp")]
+    Webp { lossless: bool, quality: f32 },
+    /// Uncompressed 32-bit BMP.
+    Bmp,
+    /// Uncompressed RGBA TIFF.
+    Tiff,
+}
+
+impl OutputFormat {
+    /// Lossy WebP at [`DEFAULT_WEBP_QUALITY`].
+    #[cfg(feature = "webp")]
+    pub const WEBP: Self = Self::Webp {
+        lossless: false,
+        quality: DEFAULT_WEBP_QUALITY,
+    };
+
+    /// MIME type of the encoded bytes, e.g. for a `Content-Type` header.
+    #[must_use]
+    pub const fn mime_type(self) -> &'static str {
+        match self {
+            Self::Png { .. } => "image/png",
+            #[cfg(feature = "jpeg")]
+            Self::Jpeg { .. } => "image/jpeg",
+            #[cfg(feature = "webp")]
+            Self::Webp { .. } => "image/webp",
+            Self::Bmp => "image/bmp",
+            Self::Tiff => "image/tiff",
+        }
+    }
+
+    /// Conventional file extension, without the dot.
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Png { .. } => "png",
+            #[cfg(feature = "jpeg")]
+            Self::Jpeg { .. } => "jpg",
+            #[cfg(feature = "webp")]
+            Self::Webp { .. } => "webp",
+            Self::Bmp => "bmp",
+            Self::Tiff => "tiff",
+        }
+    }
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        Self::Png {
+            compression: CompressionLevel::default(),
+        }
+    }
+}
+
+/// Everything that controls a single render, built with chainable setters.
+///
+/// Start from [`RenderOptions::new`] and override only what differs, e.g.
+/// `RenderOptions::new(420, 155).scale(2.0)`, then pass it to [`crate::render_html_bytes`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+#[allow(
+    clippy::struct_excessive_bools,
+    reason = "Each bool is an independent render switch with its own setter"
+)]
+pub struct RenderOptions {
+    /// Output width in pixels at a device pixel ratio of 1; `0` shrinks it to the content's
+    /// natural width.
+    pub width: u32,
+    /// Output height in pixels at a device pixel ratio of 1; `0` measures it from the
+    /// laid-out content.
+    pub height: u32,
+    /// Largest height in device pixels that a measured (`height == 0`) render may reach.
+    /// It is checked after layout, before anything is painted; [`crate::MAX_DIMENSION`]
+    /// caps it.
+    pub max_height: u32,
+    /// Scale factor applied during painting. It does not grow the buffer, so values above
+    /// `1.0` paint the page larger and crop it to `width` x `height`; use
+    /// `device_pixel_ratio` to render the same layout at a higher resolution.
+    pub scale: f64,
+    /// Device pixels per CSS pixel. The buffer is `width * device_pixel_ratio` by
+    /// `height * device_pixel_ratio` and painting is scaled to match, so `2.0` gives a
+    /// crisp retina render of the same layout.
+    pub device_pixel_ratio: f64,
+    /// Virtual time (seconds) used to resolve CSS animations, counted from `0.0` when
+    /// every animation starts. `0.0` paints the first keyframe, or the element's own
+    /// style while an `animation-delay` is pending (unless `animation-fill-mode` is
+    /// `backwards` or `both`).
+    pub animation_time: f64,
+    /// Supersampling factor for anti-aliasing: paint at this many times the resolution and
+    /// downscale to the requested size, smoothing text and edges at the cost of
+    /// `supersample²` times the painting work. `0` and `1` disable it; values above
+    /// [`crate::MAX_SUPERSAMPLE`] are clamped.
+    pub supersample: u8,
+    /// Font registered ahead of `font_paths` and preferred for the generic families
+    /// (`serif`, `sans-serif`, `system-ui`), so unstyled text looks the same everywhere.
+    pub default_font: Option<PathBuf>,
+    /// Font files registered before layout.
+    pub font_paths: Vec<PathBuf>,
+    /// Fonts tried in order for characters the chosen font lacks, e.g. Arabic or CJK faces
+    /// behind a Latin one. They extend the generic families, so CSS font stacks should end
+    /// with one (`sans-serif`, `serif`, or `system-ui`).
+    pub fallback_fonts: Vec<PathBuf>,
+    /// Register the bundled Noto Emoji font (monochrome) as the `emoji` family and as a
+    /// fallback for the generic text families, so emoji render without shipping a font. For
+    /// color, pass a color emoji font in `font_paths` instead. Needs the `emoji` feature;
+    /// without it, renders fail with [`crate::RenderError::EmojiNotBundled`].
+    pub enable_emoji: bool,
+    /// Straight-alpha RGBA composited under the page; `None` keeps it transparent.
+    pub background: Option<[u8; 4]>,
+    /// Encode premultiplied pixels instead of converting to straight alpha.
+    pub premultiplied_alpha: bool,
+    /// Overlay each element's layout box and tag name.
+    pub debug_boxes: bool,
+    /// PNG compression effort.
+    pub compression: CompressionLevel,
+    /// Write a `pHYs` chunk declaring this density (dots per inch) in PNG output.
+    pub dpi: Option<u32>,
+    /// Dither the 8-bit output with a fixed ordered pattern to hide banding in smooth
+    /// gradients, at the cost of ±1 noise per channel. It is applied last, after
+    /// `crop` and `resize_to`, to the pixels as encoded. The renderer has no higher
+    /// precision to offer, so 16-bit output is not available.
+    pub dither: bool,
+    /// Tag PNG output as sRGB (`sRGB` chunk plus a `gAMA` fallback).
+    pub srgb: bool,
+    /// CSS selector of the element to crop the output to; `None` keeps the full viewport.
+    pub clip_selector: Option<String>,
+    /// `(x, y, width, height)` pixel rectangle to keep, applied after `clip_selector`.
+    pub crop: Option<(u32, u32, u32, u32)>,
+    /// `(width, height)` to resample the finished image to before encoding, applied after
+    /// `crop`; a `0` dimension follows the aspect ratio. Cheaper than a second render when
+    /// a smaller copy is all that differs.
+    pub resize_to: Option<(u32, u32)>,
+    /// Filter used by `resize_to`.
+    pub resize_filter: ResizeFilter,
+    /// Directory that relative `<img src>`, `<link href>`, and CSS `url()` references
+    /// resolve against. Only `file:` URLs inside it are loaded; `None` loads nothing.
+    pub base_dir: Option<PathBuf>,
+    /// Report best-effort diagnostics in [`crate::RenderOutput::warnings`], currently the
+    /// resources the document referenced but that were not loaded.
+    pub collect_warnings: bool,
+    /// Pass the HTML through [`crate::sanitize_html`] first, removing scripts, event
+    /// handlers, and external URL references from untrusted input.
+    pub sanitize: bool,
+}
+
+impl RenderOptions {
+    /// Options for a `width` x `height` render with every other setting at its default.
+    #[must_use]
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            max_height: MAX_DIMENSION,
+            scale: DEFAULT_SCALE,
+            device_pixel_ratio: DEFAULT_DEVICE_PIXEL_RATIO,
+            animation_time: DEFAULT_ANIMATION_TIME,
+            supersample: 1,
+            default_font: None,
+            font_paths: Vec::new(),
+            fallback_fonts: Vec::new(),
+            enable_emoji: false,
+            background: None,
+            premultiplied_alpha: false,
+            debug_boxes: false,
+            compression: CompressionLevel::Fast,
+            dpi: None,
+            srgb: false,
+            dither: false,
+            clip_selector: None,
+            crop: None,
+            resize_to: None,
+            resize_filter: ResizeFilter::Lanczos3,
+            base_dir: None,
+            collect_warnings: false,
+            sanitize: false,
+        }
+    }
+
+    #[must_use]
+    pub fn max_height(mut self, max_height: u32) -> Self {
+        self.max_height = max_height;
+        self
+    }
+
+    #[must_use]
+    pub fn scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    #[must_use]
+    pub fn device_pixel_ratio(mut self, device_pixel_ratio: f64) -> Self {
+        self.device_pixel_ratio = device_pixel_ratio;
+        self
+    }
+
+    #[must_use]
+    pub fn animation_time(mut self, animation_time: f64) -> Self {
+        self.animation_time = animation_time;
+        self
+    }
+
+    #[must_use]
+    pub fn supersample(mut self, supersample: u8) -> Self {
+        self.supersample = supersample;
+        self
+    }
+
+    #[must_use]
+    pub fn default_font(mut self, default_font: Option<PathBuf>) -> Self {
+        self.default_font = default_font;
+        self
+    }
+
+    #[must_use]
+    pub fn font_paths(mut self, font_paths: Vec<PathBuf>) -> Self {
+        self.font_paths = font_paths;
+        self
+    }
+
+    #[must_use]
+    pub fn fallback_fonts(mut self, fallback_fonts: Vec<PathBuf>) -> Self {
+        self.fallback_fonts = fallback_fonts;
+        self
+    }
+
+    #[must_use]
+    pub fn enable_emoji(mut self, enable_emoji: bool) -> Self {
+        self.enable_emoji = enable_emoji;
+        self
+    }
+
+    #[must_use]
+    pub fn background(mut self, background: Option<[u8; 4]>) -> Self {
+        self.background = background;
+        self
+    }
+
+    #[must_use]
+    pub fn premultiplied_alpha(mut self, premultiplied_alpha: bool) -> Self {
+        self.premultiplied_alpha = premultiplied_alpha;
+        self
+    }
+
+    #[must_use]
+    pub fn debug_boxes(mut self, debug_boxes: bool) -> Self {
+        self.debug_boxes = debug_boxes;
+        self
+    }
+
+    #[must_use]
+    pub fn base_dir(mut self, base_dir: Option<PathBuf>) -> Self {
+        self.base_dir = base_dir;
+        self
+    }
+
+    #[must_use]
+    pub fn collect_warnings(mut self, collect_warnings: bool) -> Self {
+        self.collect_warnings = collect_warnings;
+        self
+    }
+
+    #[must_use]
+    pub fn sanitize(mut self, sanitize: bool) -> Self {
+        self.sanitize = sanitize;
+        self
+    }
+
+    #[must_use]
+    pub fn dpi(mut self, dpi: Option<u32>) -> Self {
+        self.dpi = dpi;
+        self
+    }
+
+    #[must_use]
+    pub fn dither(mut self, dither: bool) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    #[must_use]
+    pub fn srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    #[must_use]
+    pub fn clip_selector(mut self, clip_selector: Option<String>) -> Self {
+        self.clip_selector = clip_selector;
+        self
+    }
+
+    #[must_use]
+    pub fn crop(mut self, crop: Option<(u32, u32, u32, u32)>) -> Self {
+        self.crop = crop;
+        self
+    }
+
+    #[must_use]
+    pub fn resize_to(mut self, resize_to: Option<(u32, u32)>) -> Self {
+        self.resize_to = resize_to;
+        self
+    }
+
+    #[must_use]
+    pub fn resize_filter(mut self, resize_filter: ResizeFilter) -> Self {
+        self.resize_filter = resize_filter;
+        self
+    }
+
+    #[must_use]
+    pub fn compression(mut self, compression: CompressionLevel) -> Self {
+        self.compression = compression;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error as StdError, result::Result as StdResult};
+
+    use super::*;
+    type TestResult<T = ()> = StdResult<T, Box<dyn StdError>>;
+
+    #[test]
+    fn setters_override_only_their_field() -> TestResult {
+        let opts = RenderOptions::new(320, 120)
+            .scale(2.0)
+            .background(Some([255, 255, 255, 255]));
+
+        let expected = RenderOptions {
+            scale: 2.0,
+            background: Some([255, 255, 255, 255]),
+            ..RenderOptions::new(320, 120)
+        };
+        if opts != expected {
+            return Err(format!("unexpected options: {opts:?}").into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+use std::{fmt, path::PathBuf, sync::Arc};
+
+use parley::FontContext;
+
+use crate::{
+    RenderOptions, Result, encode_png, family_names, paint_html_to_rgba, register_font_blobs,
+    register_fonts, unpremultiply_alpha,
+};
+
+/// Renders HTML with a font context that is built once and reused across calls.
+///
+/// The free `render_html_to_*` functions parse and register their fonts on every call;
+/// keep a `Renderer` around when rendering many documents with the same fonts.
+#[derive(Clone)]
+pub struct Renderer {
+    font_ctx: FontContext,
+    families: Vec<String>,
+}
+
+impl Renderer {
+    /// Load and register `font_paths` once.
+    ///
+    /// # Errors
+    /// Returns an error if a font file cannot be read or contains no usable faces.
+    pub fn new(font_paths: &[PathBuf]) -> Result<Self> {
+        let mut font_ctx = FontContext::new();
+        let families = register_fonts(&mut font_ctx, font_paths)?;
+        let families = family_names(&mut font_ctx, &families);
+        Ok(Self { font_ctx, families })
+    }
+
+    /// Register in-memory font files once, for environments without a filesystem.
+    ///
+    /// # Errors
+    /// Returns an error if an entry contains no usable font faces.
+    pub fn with_font_blobs(fonts: &[Arc<Vec<u8>>]) -> Result<Self> {
+        let mut font_ctx = FontContext::new();
+        let families = register_font_blobs(&mut font_ctx, fonts)?;
+        let families = family_names(&mut font_ctx, &families);
+        Ok(Self { font_ctx, families })
+    }
+
+    /// Family names of the fonts this renderer registered, sorted and de-duplicated.
+    ///
+    /// Use these as `font-family` values in CSS. System fonts are not included.
+    #[must_use]
+    pub fn registered_families(&self) -> Vec<String> {
+        self.families.clone()
+    }
+
+    /// Render raw HTML to PNG bytes (straight alpha) using the cached fonts.
+    ///
+    /// `height == 0` measures the height from the content, as in [`crate::render_html_output`].
+    ///
+    /// # Errors
+    /// Returns an error if the measured height is too large or the PNG encoding fails.
+    pub fn render_png_bytes(
+        &self,
+        html: &str,
+        width: u32,
+        height: u32,
+        scale: f64,
+        current_time_for_animations: f64,
+    ) -> Result<Vec<u8>> {
+        let opts = RenderOptions::new(width, height)
+            .scale(scale)
+            .animation_time(current_time_for_animations);
+        // Cloning shares the registered font data; only the per-document caches are fresh.
+        let mut painted = paint_html_to_rgba(html, &opts, self.font_ctx.clone())?;
+        unpremultiply_alpha(&mut painted.rgba);
+        encode_png(&painted.rgba, painted.width, painted.height, &opts)
+    }
+}
+
+impl fmt::Debug for Renderer {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("Renderer")
+            .field("families", &self.families)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error as StdError, fs, result::Result as StdResult};
+
+    use super::*;
+    use crate::DEFAULT_ANIMATION_TIME;
+    type TestResult<T = ()> = StdResult<T, Box<dyn StdError>>;
+
+    const FIRA_SANS: &[u8] = include_bytes!("../../assets/fonts/FiraSans-Regular.ttf");
+    const NOTO_EMOJI: &[u8] = include_bytes!("../../assets/fonts/NotoEmoji-Regular.ttf");
+
+    /// Size of a TrueType table record: tag, checksum, offset, length.
+    const TABLE_RECORD_LEN: usize = 16;
+
+    /// Pack single-face fonts into a version 1.0 `.ttc` collection.
+    ///
+    /// Table offsets in a collection are relative to the start of the file, so each face's
+    /// table records are shifted by where the face lands.
+    fn font_collection(faces: &[&[u8]]) -> TestResult<Vec<u8>> {
+        let header_len = 12 + 4 * faces.len();
+        let mut header = b"ttcf\x00\x01\x00\x00".to_vec();
+        header.extend(u32::try_from(faces.len())?.to_be_bytes());
+        let mut body = Vec::new();
+        for face in faces {
+            let base = u32::try_from(header_len + body.len())?;
+            header.extend(base.to_be_bytes());
+            let mut face = face.to_vec();
+            let num_tables = face.get(4..6).ok_or("truncated font")?;
+            let num_tables = usize::from(u16::from_be_bytes(num_tables.try_into()?));
+            for table in 0..num_tables {
+                let field = 12 + table * TABLE_RECORD_LEN + 8;
+                let offset = face.get_mut(field..field + 4).ok_or("truncated font")?;
+                let shifted = u32::from_be_bytes((&*offset).try_into()?) + base;
+                offset.copy_from_slice(&shifted.to_be_bytes());
+            }
+            body.extend(face);
+            body.resize(body.len().next_multiple_of(4), 0);
+        }
+        header.extend(body);
+        Ok(header)
+    }
+
+    const fn assert_send<T: Send>() {}
+    const _: () = assert_send::<Renderer>();
+
+    #[test]
+    fn renderer_reuses_fonts_across_renders() -> TestResult {
+        let renderer = Renderer::new(&[])?;
+
+        for width in [32, 48] {
+            let bytes = renderer.render_png_bytes(
+                "<div>reuse</div>",
+                width,
+                24,
+                1.0,
+                DEFAULT_ANIMATION_TIME,
+            )?;
+            if !bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+                return Err("renderer output is not a PNG".into());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn registered_families_lists_each_family_once() -> TestResult {
+        let font = Arc::new(FIRA_SANS.to_vec());
+        let renderer = Renderer::with_font_blobs(&[Arc::clone(&font), font])?;
+
+        let families = renderer.registered_families();
+        if families != ["Fira Sans"] {
+            return Err(format!("unexpected families: {families:?}").into());
+        }
+        if !Renderer::new(&[])?.registered_families().is_empty() {
+            return Err("a renderer without fonts should list no families".into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn font_collections_register_every_face() -> TestResult {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("pair.ttc");
+        fs::write(&path, font_collection(&[FIRA_SANS, NOTO_EMOJI])?)?;
+
+        let families = Renderer::new(&[path])?.registered_families();
+
+        if families != ["Fira Sans", "Noto Emoji"] {
+            return Err(format!("unexpected families: {families:?}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn renderer_draws_glyphs_from_font_bytes() -> TestResult {
+        let renderer = Renderer::with_font_blobs(&[Arc::new(FIRA_SANS.to_vec())])?;
+        let html = "<html><body style=\"margin:0;font-family:'Fira Sans';font-size:32px\">\
+            Hg</body></html>";
+
+        let bytes = renderer.render_png_bytes(html, 64, 48, 1.0, DEFAULT_ANIMATION_TIME)?;
+
+        let image = image::load_from_memory(&bytes)?.to_rgba8();
+        if !image.pixels().any(|pixel| pixel.0[3] > 0) {
+            return Err("no glyph pixels were painted".into());
+        }
+        Ok(())
+    }
+}
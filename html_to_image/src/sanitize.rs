@@ -0,0 +1,589 @@
+use std::borrow::Cow;
+
+/// Attributes whose value is a URL (or, for `srcset`, a list of them) the document may load.
+const URL_ATTRIBUTES: &[&str] = &[
+    "action",
+    "background",
+    "cite",
+    "data",
+    "formaction",
+    "href",
+    "imagesrcset",
+    "poster",
+    "src",
+    "srcset",
+    "xlink:href",
+];
+
+/// Named character references decoded in `style` attributes; a value using any other name is
+/// dropped rather than guessed at.
+const CHARACTER_REFERENCES: &[(&str, char)] = &[
+    ("amp", '&'),
+    ("apos", '\''),
+    ("bsol", '\\'),
+    ("colon", ':'),
+    ("comma", ','),
+    ("gt", '>'),
+    ("lpar", '('),
+    ("lt", '<'),
+    ("nbsp", '\u{a0}'),
+    ("NewLine", '\n'),
+    ("period", '.'),
+    ("quot", '"'),
+    ("rpar", ')'),
+    ("semi", ';'),
+    ("sol", '/'),
+    ("Tab", '\t'),
+];
+
+/// Strip active and external content from untrusted `html`, keeping everything else as
+/// written.
+///
+/// Removed:
+/// - `<script>` elements and their contents.
+/// - Event handler attributes (any attribute whose name starts with `on`, e.g. `onload`).
+/// - URL attributes (`src`, `href`, `srcset`, `poster`, `action`, `xlink:href`, ...) that
+///   point outside the document: any scheme other than `data:` (so `http:`, `file:`, and
+///   `javascript:` alike) or a protocol-relative `//host` reference.
+/// - The same external references in CSS, in `<style>` elements and `style` attributes:
+///   such a `url(...)` becomes `url()`, such a string in `image-set(...)` (or
+///   `-webkit-image-set(...)`) becomes `""`, and such an `@import` rule is dropped.
+///
+/// Relative references are kept, so assets under [`crate::RenderOptions::base_dir`] still
+/// load. References that hide their scheme behind character or CSS escapes count as
+/// external. CSS escapes spelling out `url(` or `@import`, and character references in
+/// `style` attributes, are decoded before matching; a `style` attribute using a named
+/// character reference outside a short list of punctuation is dropped.
+#[must_use]
+pub fn sanitize_html(html: &str) -> String {
+    // ASCII lowercasing keeps byte offsets, so positions found here index `html` too.
+    let lower = html.to_ascii_lowercase();
+    let mut out = String::with_capacity(html.len());
+    let mut pos = 0;
+
+    while let Some(offset) = lower.get(pos..).and_then(|rest| rest.find('<')) {
+        let start = pos + offset;
+        out.push_str(html.get(pos..start).unwrap_or_default());
+        let tag = lower.get(start..).unwrap_or_default();
+        pos = if tag.starts_with("<!--") {
+            copy_through(html, &lower, start, "-->", &mut out)
+        } else if tag.starts_with("<!") || tag.starts_with("<?") || tag.starts_with("</") {
+            copy_through(html, &lower, start, ">", &mut out)
+        } else {
+            match tag_name(tag) {
+                "" => {
+                    out.push('<');
+                    start + 1
+                }
+                "script" => end_of(&lower, start, "</script")
+                    .and_then(|end| end_of(&lower, end, ">"))
+                    .unwrap_or(html.len()),
+                name => {
+                    let name_end = start + 1 + name.len();
+                    out.push_str(html.get(start..name_end).unwrap_or_default());
+                    let rest = copy_attributes(html.get(name_end..).unwrap_or_default(), &mut out);
+                    let tag_end = html.len() - rest.len();
+                    if name == "style" {
+                        let body_end = lower
+                            .get(tag_end..)
+                            .and_then(|body| body.find("</style"))
+                            .map_or(html.len(), |body_len| tag_end + body_len);
+                        out.push_str(&sanitize_css(
+                            html.get(tag_end..body_end).unwrap_or_default(),
+                        ));
+                        body_end
+                    } else {
+                        tag_end
+                    }
+                }
+            }
+        };
+    }
+    out.push_str(html.get(pos..).unwrap_or_default());
+    out
+}
+
+/// Offset just past the first `marker` at or after `start`.
+fn end_of(lower: &str, start: usize, marker: &str) -> Option<usize> {
+    lower
+        .get(start..)
+        .and_then(|rest| rest.find(marker))
+        .map(|offset| start + offset + marker.len())
+}
+
+/// Copy `html` from `start` through the next `marker` (or to the end) into `out`,
+/// returning the offset after it.
+fn copy_through(html: &str, lower: &str, start: usize, marker: &str, out: &mut String) -> usize {
+    let end = end_of(lower, start, marker).unwrap_or(html.len());
+    out.push_str(html.get(start..end).unwrap_or_default());
+    end
+}
+
+/// Lowercase name of the start tag `tag` (starting at `<`), or `""` when the `<` does not
+/// open a tag.
+fn tag_name(tag: &str) -> &str {
+    let after = tag.strip_prefix('<').unwrap_or(tag);
+    if !after.starts_with(|ch: char| ch.is_ascii_alphabetic()) {
+        return "";
+    }
+    let len = after
+        .find(|ch: char| !(ch.is_ascii_alphanumeric() || matches!(ch, '-' | ':')))
+        .unwrap_or(after.len());
+    after.get(..len).unwrap_or_default()
+}
+
+/// Copy the attributes of a start tag from `rest` (just past its name) into `out`, dropping
+/// unsafe ones, and return the input after the tag's `>`.
+fn copy_attributes<'html>(mut rest: &'html str, out: &mut String) -> &'html str {
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix('>') {
+            out.push('>');
+            return after;
+        }
+        if let Some(after) = rest.strip_prefix("/>") {
+            out.push_str(" />");
+            return after;
+        }
+        if rest.is_empty() {
+            return rest;
+        }
+        if let Some(after) = rest.strip_prefix('/') {
+            rest = after;
+            continue;
+        }
+
+        let name_len = rest
+            .find(|ch: char| ch.is_ascii_whitespace() || matches!(ch, '=' | '>' | '/'))
+            .unwrap_or(rest.len());
+        let (name, after_name) = rest.split_at(name_len);
+        let value = after_name
+            .trim_start()
+            .strip_prefix('=')
+            .map(attribute_value);
+        rest = value.map_or(after_name, |(_quote, _value, after)| after);
+
+        let value = value.map(|(quote, value, _after)| (quote, value));
+        if let Some(kept) = sanitize_attribute(name, value) {
+            out.push(' ');
+            out.push_str(name);
+            if let Some((quote, kept_value)) = kept {
+                out.push('=');
+                out.extend(quote);
+                out.push_str(&kept_value);
+                out.extend(quote);
+            }
+        }
+    }
+}
+
+/// Split an attribute value (just past its `=`) into its quote character, its text, and
+/// the input after it.
+fn attribute_value(rest: &str) -> (Option<char>, &str, &str) {
+    let rest = rest.trim_start();
+    for quote in ['"', '\''] {
+        if let Some(quoted) = rest.strip_prefix(quote) {
+            let (value, after) = quoted.split_once(quote).unwrap_or((quoted, ""));
+            return (Some(quote), value, after);
+        }
+    }
+    let len = rest
+        .find(|ch: char| ch.is_ascii_whitespace() || ch == '>')
+        .unwrap_or(rest.len());
+    let (value, after) = rest.split_at(len);
+    (None, value, after)
+}
+
+/// The attribute value to keep, `Some(None)` to keep a bare attribute, or `None` to drop it.
+#[allow(
+    clippy::option_option,
+    reason = "Dropping the attribute differs from keeping one without a value"
+)]
+fn sanitize_attribute<'html>(
+    name: &str,
+    value: Option<(Option<char>, &'html str)>,
+) -> Option<Option<(Option<char>, Cow<'html, str>)>> {
+    let name = name.to_ascii_lowercase();
+    if name.is_empty() || name.starts_with("on") {
+        return None;
+    }
+    let Some((quote, value)) = value else {
+        return Some(None);
+    };
+    let external = match name.as_str() {
+        "srcset" | "imagesrcset" => value
+            .split(',')
+            .any(|candidate| is_external(candidate.split_whitespace().next().unwrap_or_default())),
+        _ => URL_ATTRIBUTES.contains(&name.as_str()) && is_external(value),
+    };
+    if external {
+        return None;
+    }
+    if name == "style" {
+        // The HTML parser decodes references before CSS sees the value, so match on that.
+        let css = decode_character_references(value)?;
+        let quote = quote.unwrap_or('"');
+        let css = escape_attribute(&sanitize_css(&css), quote);
+        return Some(Some((Some(quote), Cow::Owned(css))));
+    }
+    Some(Some((quote, Cow::Borrowed(value))))
+}
+
+/// Decode the character references in an attribute value, or `None` if it uses a named
+/// reference missing from [`CHARACTER_REFERENCES`].
+fn decode_character_references(value: &str) -> Option<Cow<'_, str>> {
+    if !value.contains('&') {
+        return Some(Cow::Borrowed(value));
+    }
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some((before, reference)) = rest.split_once('&') {
+        out.push_str(before);
+        let (decoded, after) = if let Some(number) = reference.strip_prefix('#') {
+            let (digits, radix) = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => (hex, 16),
+                None => (number, 10),
+            };
+            let len = digits
+                .find(|ch: char| !ch.is_digit(radix))
+                .unwrap_or(digits.len());
+            if len == 0 {
+                (None, reference)
+            } else {
+                let decoded = digits
+                    .get(..len)
+                    .and_then(|digits| u32::from_str_radix(digits, radix).ok())
+                    .and_then(char::from_u32)
+                    .unwrap_or(char::REPLACEMENT_CHARACTER);
+                (Some(decoded), digits.get(len..).unwrap_or_default())
+            }
+        } else {
+            let len = reference
+                .find(|ch: char| !ch.is_ascii_alphanumeric())
+                .unwrap_or(reference.len());
+            if len == 0 {
+                (None, reference)
+            } else {
+                let name = reference.get(..len).unwrap_or_default();
+                let (_, decoded) = CHARACTER_REFERENCES
+                    .iter()
+                    .find(|(known, _)| *known == name)?;
+                (Some(*decoded), reference.get(len..).unwrap_or_default())
+            }
+        };
+        if let Some(ch) = decoded {
+            out.push(ch);
+            rest = after.strip_prefix(';').unwrap_or(after);
+        } else {
+            out.push('&');
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+    Some(Cow::Owned(out))
+}
+
+/// Escape `value` for an attribute quoted with `quote`.
+fn escape_attribute(value: &str, quote: char) -> String {
+    let escaped_quote = if quote == '"' { "&quot;" } else { "&#39;" };
+    value.replace('&', "&amp;").replace(quote, escaped_quote)
+}
+
+/// Drop `@import` rules and blank `url()` references in `css` that point outside the
+/// document.
+fn sanitize_css(css: &str) -> String {
+    let css = drop_external_imports(&decode_name_escapes(css));
+    neutralize_urls(&neutralize_image_sets(&css))
+}
+
+/// Replace CSS escapes that stand for a letter, digit, `-`, or `_` with that character, so
+/// `\75 rl(` and `@\69mport` are found like `url(` and `@import`. Such escapes mean the
+/// same character wherever they appear; escapes of any other character are kept as
+/// written.
+fn decode_name_escapes(css: &str) -> Cow<'_, str> {
+    if !css.contains('\\') {
+        return Cow::Borrowed(css);
+    }
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some((before, escape)) = rest.split_once('\\') {
+        out.push_str(before);
+        let hex_len = escape
+            .find(|ch: char| !ch.is_ascii_hexdigit())
+            .unwrap_or(escape.len())
+            .min(6);
+        let (decoded, len) = if hex_len > 0 {
+            let decoded = escape
+                .get(..hex_len)
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .and_then(char::from_u32);
+            // One whitespace character after a hex escape belongs to it.
+            let space = escape
+                .get(hex_len..)
+                .and_then(|after| after.chars().next())
+                .filter(char::is_ascii_whitespace)
+                .map_or(0, char::len_utf8);
+            (decoded, hex_len + space)
+        } else {
+            let next = escape.chars().next();
+            (next, next.map_or(0, char::len_utf8))
+        };
+        match decoded {
+            Some(ch) if ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_') => out.push(ch),
+            _ => {
+                out.push('\\');
+                out.push_str(escape.get(..len).unwrap_or_default());
+            }
+        }
+        rest = escape.get(len..).unwrap_or_default();
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+fn drop_external_imports(css: &str) -> String {
+    let lower = css.to_ascii_lowercase();
+    let mut out = String::with_capacity(css.len());
+    let mut pos = 0;
+    while let Some(offset) = lower.get(pos..).and_then(|rest| rest.find("@import")) {
+        let start = pos + offset;
+        out.push_str(css.get(pos..start).unwrap_or_default());
+        let end = end_of(&lower, start, ";").unwrap_or(css.len());
+        let target = css
+            .get(start + "@import".len()..end)
+            .unwrap_or_default()
+            .trim_start();
+        let target = match target.get(.."url(".len()) {
+            Some(func) if func.eq_ignore_ascii_case("url(") => target.get(func.len()..),
+            _ => Some(target),
+        };
+        if !target.is_some_and(|target| is_external(css_string(target))) {
+            out.push_str(css.get(start..end).unwrap_or_default());
+        }
+        pos = end;
+    }
+    out.push_str(css.get(pos..).unwrap_or_default());
+    out
+}
+
+fn neutralize_urls(css: &str) -> String {
+    let lower = css.to_ascii_lowercase();
+    let mut out = String::with_capacity(css.len());
+    let mut pos = 0;
+    while let Some(start) = end_of(&lower, pos, "url(") {
+        out.push_str(css.get(pos..start).unwrap_or_default());
+        let end = lower
+            .get(start..)
+            .and_then(|rest| rest.find(')'))
+            .map_or(css.len(), |offset| start + offset);
+        let target = css.get(start..end).unwrap_or_default();
+        if !is_external(css_string(target)) {
+            out.push_str(target);
+        }
+        pos = end;
+    }
+    out.push_str(css.get(pos..).unwrap_or_default());
+    out
+}
+
+/// Empty the strings inside `image-set(...)` (and `-webkit-image-set(...)`) that point
+/// outside the document; `url()` arguments are left to [`neutralize_urls`].
+fn neutralize_image_sets(css: &str) -> String {
+    let lower = css.to_ascii_lowercase();
+    let mut out = String::with_capacity(css.len());
+    let mut pos = 0;
+    while let Some(start) = end_of(&lower, pos, "image-set(") {
+        out.push_str(css.get(pos..start).unwrap_or_default());
+        let args = css.get(start..).unwrap_or_default();
+        pos = start + copy_function_args(args, &mut out);
+    }
+    out.push_str(css.get(pos..).unwrap_or_default());
+    out
+}
+
+/// Copy a CSS function's arguments from `args` (just past its `(`) through its closing `)`
+/// into `out`, emptying external strings, and return the length copied.
+fn copy_function_args(args: &str, out: &mut String) -> usize {
+    let mut depth = 1_usize;
+    let mut rest = args;
+    while let Some(ch) = rest.chars().next() {
+        rest = rest.get(ch.len_utf8()..).unwrap_or_default();
+        if matches!(ch, '"' | '\'') {
+            let len = string_len(rest, ch);
+            let value = rest.get(..len).unwrap_or_default();
+            out.push(ch);
+            if !is_external(value) {
+                out.push_str(value);
+            }
+            rest = rest.get(len..).unwrap_or_default();
+            if let Some(after) = rest.strip_prefix(ch) {
+                out.push(ch);
+                rest = after;
+            }
+            continue;
+        }
+        out.push(ch);
+        match ch {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    args.len() - rest.len()
+}
+
+/// Length of the CSS string body at the start of `text`, up to its unescaped closing
+/// `quote` (which is not included) or the end of `text`.
+fn string_len(text: &str, quote: char) -> usize {
+    let mut escaped = false;
+    text.char_indices()
+        .find(|&(_, ch)| {
+            let closes = !escaped && ch == quote;
+            escaped = !escaped && ch == '\\';
+            closes
+        })
+        .map_or(text.len(), |(offset, _)| offset)
+}
+
+/// The leading CSS string or bare URL in `text`, without its quotes.
+fn css_string(text: &str) -> &str {
+    let text = text.trim_start();
+    for quote in ['"', '\''] {
+        if let Some(quoted) = text.strip_prefix(quote) {
+            return quoted
+                .split_once(quote)
+                .map_or(quoted, |(value, _rest)| value);
+        }
+    }
+    text.split(|ch: char| ch.is_ascii_whitespace() || matches!(ch, ')' | ';'))
+        .next()
+        .unwrap_or_default()
+}
+
+/// Whether `url` points outside the document: it has a scheme other than `data:` or is a
+/// protocol-relative `//host` reference. URL parsers drop tabs and newlines and treat `\`
+/// like `/`, and the scheme may be hidden behind `&...;` or `\..` escapes, so all of those
+/// are accounted for before deciding.
+fn is_external(url: &str) -> bool {
+    let url: String = url
+        .trim()
+        .chars()
+        .filter(|ch| !matches!(ch, '\t' | '\n' | '\r'))
+        .collect();
+    if url
+        .get(.."data:".len())
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("data:"))
+    {
+        return false;
+    }
+    let mut chars = url.chars();
+    if chars.next().is_some_and(is_slash) && chars.next().is_some_and(is_slash) {
+        return true;
+    }
+    let lead = url.split(['/', '?', '#']).next().unwrap_or_default();
+    lead.contains([':', '&', '\\'])
+}
+
+const fn is_slash(ch: char) -> bool {
+    matches!(ch, '/' | '\\')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error as StdError, result::Result as StdResult};
+
+    use super::*;
+    type TestResult<T = ()> = StdResult<T, Box<dyn StdError>>;
+
+    #[test]
+    fn scripts_and_event_handlers_are_removed() -> TestResult {
+        let html = "<p onclick=\"steal()\" class=note>hi<SCRIPT type=module>\
+            alert('<p>')</script ></p><img src=x OnError='steal()'><br/>";
+
+        let clean = sanitize_html(html);
+
+        let expected = "<p class=note>hi</p><img src=x><br />";
+        if clean != expected {
+            return Err(format!("unexpected html: {clean}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn external_references_are_removed_and_local_ones_kept() -> TestResult {
+        let html = "<a href=\"javascript:steal()\">a</a><img src=\"https://evil.test/t.png\">\
+            <img src=\"//evil.test/t.png\"><img src=\"http&#58;//evil.test\">\
+            <img src=\"logo.png\" srcset=\"a.png 1x, https://evil.test/b.png 2x\">\
+            <img src=\"data:image/png;base64,AAAA\">\
+            <div style=\"background: url('https://evil.test/bg.png'), url(local.png)\"></div>\
+            <style>@import 'https://evil.test/x.css'; @import \"site.css\";\
+            body { background: URL( //evil.test/bg ) }</style>";
+
+        let clean = sanitize_html(html);
+
+        let expected = "<a>a</a><img><img><img><img src=\"logo.png\">\
+            <img src=\"data:image/png;base64,AAAA\">\
+            <div style=\"background: url(), url(local.png)\"></div>\
+            <style> @import \"site.css\";body { background: URL() }</style>";
+        if clean != expected {
+            return Err(format!("unexpected html: {clean}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn character_references_in_style_attributes_are_decoded_first() -> TestResult {
+        let html = "<p style=\"background:u&#114;l(https://evil.test/a)\">a</p>\
+            <p style='background:url&#40;//evil.test/b&rpar;; color: red'>b</p>\
+            <p style=\"background:url&lpar;&quot;local.png&quot;)\">c</p>\
+            <p style=\"background:u&rarr;l(x)\" class=kept>d</p>";
+
+        let clean = sanitize_html(html);
+
+        let expected = "<p style=\"background:url()\">a</p>\
+            <p style='background:url(); color: red'>b</p>\
+            <p style=\"background:url(&quot;local.png&quot;)\">c</p>\
+            <p class=kept>d</p>";
+        if clean != expected {
+            return Err(format!("unexpected html: {clean}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn image_set_strings_are_checked_like_urls() -> TestResult {
+        let html = "<div style=\"background: image-set('https://evil.test/a.png' 1x, \
+            'local.png' 2x)\"></div><style>p { background: -webkit-image-set(\
+            \"//evil.test/b.png\" 1x, url(https://evil.test/c.png) 2x) }</style>";
+
+        let clean = sanitize_html(html);
+
+        let expected = "<div style=\"background: image-set('' 1x, 'local.png' 2x)\"></div>\
+            <style>p { background: -webkit-image-set(\"\" 1x, url() 2x) }</style>";
+        if clean != expected {
+            return Err(format!("unexpected html: {clean}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn css_escapes_do_not_hide_external_references() -> TestResult {
+        let html = "<div style=\"background: \\75 rl(https://evil.test/a.png)\"></div>\
+            <style>@\\69mport 'https://evil.test/x.css'; @im\\70ort url(//evil.test/y.css);\
+            p { background: \\55\\52\\4c(//evil.test/b); content: '\\201C\\\\' }</style>";
+
+        let clean = sanitize_html(html);
+
+        let expected = "<div style=\"background: url()\"></div>\
+            <style> p { background: URL(); content: '\\201C\\\\' }</style>";
+        if clean != expected {
+            return Err(format!("unexpected html: {clean}").into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+use crate::{RenderError, Result};
+
+/// Namespace added to the extracted root when the inline SVG omits it; standalone files
+/// need it to be recognized as SVG.
+const SVG_NAMESPACE: &str = "http://www.w3.org/2000/svg";
+
+/// Elements allowed around the SVG root; they only wrap it and contribute no content.
+const WRAPPER_TAGS: &[&str] = &["html", "head", "body", "meta"];
+
+/// Return the document's single `<svg>` element as a standalone SVG file, without
+/// rasterizing it.
+///
+/// Only a doctype, comments, `<html>`/`<head>`/`<body>`/`<meta>`/`<title>`, and whitespace
+/// may surround the SVG, so a template can wrap a logo in the usual HTML boilerplate. The
+/// element is returned as written, with an `xmlns` attribute added when it is missing.
+///
+/// # Errors
+/// Returns [`RenderError::NotVectorizable`] if the document has other elements or text
+/// outside the SVG, more than one `<svg>` root, or no `<svg>` at all.
+pub fn render_html_to_svg_string(html: &str) -> Result<String> {
+    // ASCII lowercasing keeps byte offsets, so positions found here index `html` too.
+    let lower = html.to_ascii_lowercase();
+    let mut pos = 0;
+    let mut svg = None;
+
+    while let Some(offset) = lower.get(pos..).and_then(|rest| rest.find('<')) {
+        ensure_blank(lower.get(pos..pos + offset))?;
+        let start = pos + offset;
+        let tag = lower.get(start..).unwrap_or_default();
+        pos = if tag.starts_with("<!--") {
+            end_of(&lower, start, "-->")?
+        } else if tag.starts_with("<!") || tag.starts_with("<?") {
+            end_of(&lower, start, ">")?
+        } else {
+            match tag_name(tag) {
+                (false, "svg") => {
+                    if svg.is_some() {
+                        return Err(not_vectorizable(
+                            "the document has more than one <svg> root",
+                        ));
+                    }
+                    let end = svg_end(&lower, start)?;
+                    svg = html.get(start..end);
+                    end
+                }
+                (false, "title") => end_of(&lower, start, "</title>")?,
+                (_, name) if WRAPPER_TAGS.contains(&name) => end_of(&lower, start, ">")?,
+                (_, name) => {
+                    return Err(not_vectorizable(format!("<{name}> is outside the <svg>")));
+                }
+            }
+        };
+    }
+    ensure_blank(lower.get(pos..))?;
+
+    let svg = svg.ok_or_else(|| not_vectorizable("the document has no <svg> element"))?;
+    Ok(with_namespace(svg))
+}
+
+fn not_vectorizable(reason: impl Into<String>) -> RenderError {
+    RenderError::NotVectorizable {
+        reason: reason.into(),
+    }
+}
+
+/// Reject visible text outside the SVG.
+fn ensure_blank(text: Option<&str>) -> Result<()> {
+    if text.is_some_and(|text| !text.trim().is_empty()) {
+        return Err(not_vectorizable("the document has text outside the <svg>"));
+    }
+    Ok(())
+}
+
+/// Offset just past the first `marker` at or after `start`.
+fn end_of(lower: &str, start: usize, marker: &str) -> Result<usize> {
+    lower
+        .get(start..)
+        .and_then(|rest| rest.find(marker))
+        .map(|offset| start + offset + marker.len())
+        .ok_or_else(|| not_vectorizable(format!("unterminated markup, expected {marker:?}")))
+}
+
+/// Whether `tag` (starting at `<`) is a closing tag, and its lowercase name.
+fn tag_name(tag: &str) -> (bool, &str) {
+    let after = tag.strip_prefix('<').unwrap_or(tag);
+    let (closing, after) = match after.strip_prefix('/') {
+        Some(name) => (true, name),
+        None => (false, after),
+    };
+    let len = after
+        .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '-'))
+        .unwrap_or(after.len());
+    (closing, after.get(..len).unwrap_or_default())
+}
+
+/// Offset just past the `</svg>` that closes the `<svg>` opened at `start`.
+fn svg_end(lower: &str, start: usize) -> Result<usize> {
+    let mut depth = 0_usize;
+    let mut pos = start;
+    while let Some(offset) = lower.get(pos..).and_then(|rest| rest.find('<')) {
+        let tag_start = pos + offset;
+        let tag = lower.get(tag_start..).unwrap_or_default();
+        pos = end_of(lower, tag_start, ">")?;
+        let self_closing = lower
+            .get(..pos)
+            .is_some_and(|before| before.ends_with("/>"));
+        match tag_name(tag) {
+            (false, "svg") if !self_closing => depth += 1,
+            (true, "svg") => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if depth == 0 {
+            return Ok(pos);
+        }
+    }
+    Err(not_vectorizable("the <svg> element is never closed"))
+}
+
+/// Add the SVG namespace to the root element unless it declares a default one; prefixed
+/// declarations such as `xmlns:xlink` do not count.
+fn with_namespace(svg: &str) -> String {
+    let root_tag = svg.split_once('>').map_or(svg, |(tag, _rest)| tag);
+    let declares_default = root_tag
+        .to_ascii_lowercase()
+        .split(|ch: char| ch.is_ascii_whitespace())
+        .any(|attribute| {
+            attribute
+                .strip_prefix("xmlns")
+                .is_some_and(|rest| rest.trim_start().starts_with('=') || rest.is_empty())
+        });
+    if declares_default {
+        return svg.to_owned();
+    }
+    let (open, attributes) = svg.split_at_checked("<svg".len()).unwrap_or((svg, ""));
+    format!("{open} xmlns=\"{SVG_NAMESPACE}\"{attributes}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error as StdError, result::Result as StdResult};
+
+    use super::*;
+    type TestResult<T = ()> = StdResult<T, Box<dyn StdError>>;
+
+    #[test]
+    fn svg_is_extracted_from_html_boilerplate() -> TestResult {
+        let html = "<!DOCTYPE html>\n<html><HEAD><meta charset=\"utf-8\"><title>Logo</title>\
+            </head><body>\n<!-- logo -->\n<svg viewBox=\"0 0 10 10\"><svg x=\"1\"><rect/></svg>\
+            <circle cx=\"5\" cy=\"5\" r=\"4\"/></svg>\n</body></html>";
+
+        let svg = render_html_to_svg_string(html)?;
+
+        let expected = "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 10 10\">\
+            <svg x=\"1\"><rect/></svg><circle cx=\"5\" cy=\"5\" r=\"4\"/></svg>";
+        if svg != expected {
+            return Err(format!("unexpected svg: {svg}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn existing_namespace_is_kept() -> TestResult {
+        let html = "<svg xmlns=\"http://www.w3.org/2000/svg\"><path d=\"M0 0\"/></svg>";
+
+        let svg = render_html_to_svg_string(html)?;
+
+        if svg != html {
+            return Err(format!("unexpected svg: {svg}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn prefixed_namespaces_still_get_the_default_one() -> TestResult {
+        let html =
+            "<svg xmlns:xlink=\"http://www.w3.org/1999/xlink\"><use xlink:href=\"#a\"/></svg>";
+
+        let svg = render_html_to_svg_string(html)?;
+
+        let expected = "<svg xmlns=\"http://www.w3.org/2000/svg\" \
+            xmlns:xlink=\"http://www.w3.org/1999/xlink\"><use xlink:href=\"#a\"/></svg>";
+        if svg != expected {
+            return Err(format!("unexpected svg: {svg}").into());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn non_vector_documents_are_rejected() -> TestResult {
+        for html in [
+            "<html><body><div>hi</div></body></html>",
+            "<body>hi <svg></svg></body>",
+            "<svg></svg><svg></svg>",
+            "<html><body></body></html>",
+            "<svg><rect/>",
+        ] {
+            let result = render_html_to_svg_string(html);
+            if !matches!(result, Err(RenderError::NotVectorizable { .. })) {
+                return Err(
+                    format!("expected NotVectorizable for {html:?}, got {result:?}").into(),
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,22 +1,75 @@
 use std::{
-    io::{self, Write},
-    path::PathBuf,
+    fs,
+    io::{self, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
 };
 
-use anyhow::{Context, Result};
-use clap::Parser;
-use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
-use serde::Serialize;
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum, builder::RangedU64ValueParser};
 
-use html_to_image::{DEFAULT_ANIMATION_TIME, DEFAULT_SCALE, render_to_png};
+use html_to_image::{
+    CompressionLevel, DEFAULT_ANIMATION_TIME, DEFAULT_CARD_HEIGHT, DEFAULT_CARD_WIDTH,
+    DEFAULT_SCALE, DEFAULT_WEBP_QUALITY, OutputFormat, RenderOptions, Renderer, generate_card,
+    load_template, render_html, render_template, render_template_strict,
+};
 
-#[derive(Debug, Clone, Serialize)]
-struct CardData {
-    user: String,
-    icon: String,
-    message: String,
-    width: u32,
-    height: u32,
+use serde_json::{Map, Value};
+
+use crate::{manifest::Batch, watch::watch};
+
+mod manifest;
+mod watch;
+
+/// Stands for stdin as `--template` and for stdout as `--out`.
+const STDIO_PATH: &str = "-";
+/// Greeting name used when neither `--name` nor the data file sets `user`.
+const DEFAULT_NAME: &str = "User";
+/// JPEG quality used when `--quality` is not given.
+const DEFAULT_JPEG_QUALITY: u8 = 85;
+
+/// Image formats the CLI can write.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Png,
+    Jpeg,
+    Webp,
+}
+
+impl Format {
+    /// Match a file extension (case-insensitive) to a format.
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Some(Self::Png),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "webp" => Some(Self::Webp),
+            _ => None,
+        }
+    }
+
+    /// The name accepted by `--format`.
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpeg",
+            Self::Webp => "webp",
+        }
+    }
+
+    /// The core encoder settings for this format at `quality` (lossy formats only).
+    fn output_format(self, quality: Option<u8>) -> OutputFormat {
+        match self {
+            Self::Png => OutputFormat::Png {
+                compression: CompressionLevel::default(),
+            },
+            Self::Jpeg => OutputFormat::Jpeg {
+                quality: quality.unwrap_or(DEFAULT_JPEG_QUALITY),
+            },
+            Self::Webp => OutputFormat::Webp {
+                lossless: false,
+                quality: quality.map_or(DEFAULT_WEBP_QUALITY, f32::from),
+            },
+        }
+    }
 }
 
 #[derive(Parser, Debug)]
@@ -25,25 +78,41 @@ struct CardData {
     version,
     about = "Render an HTML template to a PNG using Blitz + anyrender_vello_cpu (no browser, CPU-only)."
 )]
+#[allow(
+    clippy::struct_excessive_bools,
+    reason = "Each bool is an independent command-line switch."
+)]
 struct Cli {
-    /// Path to the HTML template (`MiniJinja` syntax)
+    /// Path to the HTML template (`MiniJinja` syntax), or `-` to read it from stdin
     #[arg(short, long, default_value = "templates/card.html")]
     template: PathBuf,
 
-    /// Output PNG file path (directories will be created)
+    /// Read the template from stdin (same as `--template -`)
+    #[arg(long, conflicts_with = "template")]
+    stdin: bool,
+
+    /// Output PNG file path (directories will be created), or `-` to write it to stdout
     #[arg(short, long, default_value = "card.png")]
     out: PathBuf,
 
-    /// Name to render into the greeting
-    #[arg(short, long, default_value = "User")]
-    name: String,
+    /// Image format to write; inferred from the `--out` extension when omitted, else PNG
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
+
+    /// Encoding quality for jpeg and webp (1-100)
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=100))]
+    quality: Option<u8>,
+
+    /// Name to render into the greeting (defaults to "User")
+    #[arg(short, long)]
+    name: Option<String>,
 
     /// Fixed output width in pixels
-    #[arg(long, default_value_t = 420)]
+    #[arg(long, default_value_t = DEFAULT_CARD_WIDTH)]
     width: u32,
 
     /// Fixed output height in pixels
-    #[arg(long, default_value_t = 155)]
+    #[arg(long, default_value_t = DEFAULT_CARD_HEIGHT)]
     height: u32,
 
     /// Scale factor used by the painter (1.0 is normal)
@@ -71,71 +140,277 @@ struct Cli {
     #[arg(long)]
     message: Option<String>,
 
+    /// JSON object file whose keys become template variables
+    #[arg(long, value_name = "FILE", conflicts_with = "data_stdin")]
+    data: Option<PathBuf>,
+
+    /// Read the template variables as a JSON object from stdin
+    #[arg(long, conflicts_with = "stdin")]
+    data_stdin: bool,
+
+    /// Template variable as `KEY=VALUE` (repeatable); VALUE is parsed as JSON if possible
+    #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_var)]
+    vars: Vec<(String, Value)>,
+
+    /// Fail on template variables that are not defined instead of rendering them empty
+    #[arg(long)]
+    strict: bool,
+
     /// Seed for deterministic random icon/message selection
     #[arg(long)]
     seed: Option<u64>,
+
+    /// JSON array of `{"out": ..., "data": {...}}` entries to render in one run
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["out", "dump_html"])]
+    manifest: Option<PathBuf>,
+
+    /// How many manifest entries to render in parallel
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    jobs: usize,
+
+    /// Re-render whenever the template, data, manifest, or font files change
+    #[arg(long)]
+    watch: bool,
+
+    /// Print the font families registered from `--font-path` and exit
+    #[arg(long)]
+    list_fonts: bool,
+
+    /// Print the expanded HTML to stdout instead of rendering a PNG
+    #[arg(long)]
+    dump_html: bool,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    if cli.list_fonts {
+        return list_fonts(&cli.font_paths);
+    }
+    if cli.watch {
+        return watch(&cli);
+    }
+    render(&cli)
+}
 
-    let mut rng = match cli.seed {
-        Some(seed) => StdRng::seed_from_u64(seed),
-        None => StdRng::from_os_rng(),
-    };
+/// Render the template once, to `--out` or every `--manifest` entry.
+fn render(cli: &Cli) -> Result<()> {
+    let from_stdin = cli.stdin || cli.template == Path::new(STDIO_PATH);
+    if from_stdin && cli.data_stdin {
+        bail!("--data-stdin cannot be combined with reading the template from stdin");
+    }
+    let data = template_data(cli)?;
 
-    let icon = cli.icon.unwrap_or_else(|| pick_icon(&mut rng).to_owned());
-    let message = cli
-        .message
-        .unwrap_or_else(|| pick_message(&mut rng).to_owned());
-
-    let data = CardData {
-        user: cli.name,
-        icon,
-        message,
-        width: cli.width,
-        height: cli.height,
+    let template = if from_stdin {
+        let mut template = String::new();
+        io::stdin()
+            .read_to_string(&mut template)
+            .context("failed to read template from stdin")?;
+        template
+    } else {
+        load_template(&cli.template)
+            .with_context(|| format!("failed to load {}", cli.template.display()))?
     };
+    if let Some(manifest_path) = &cli.manifest {
+        return run_manifest(cli, manifest_path, &template, &data);
+    }
+    let html = expand_template(&template, &data, cli.strict)?;
 
-    render_to_png(
-        &cli.template,
-        &data,
-        &cli.out,
-        cli.width,
-        cli.height,
-        cli.scale,
-        cli.animation_time,
-        &cli.font_paths,
-    )
-    .with_context(|| {
-        format!(
-            "render failed (template={}, out={})",
-            cli.template.display(),
-            cli.out.display()
-        )
-    })?;
+    if cli.dump_html {
+        writeln!(io::stdout(), "{html}")?;
+        return Ok(());
+    }
 
+    let to_stdout = cli.out == Path::new(STDIO_PATH);
+    if to_stdout && io::stdout().is_terminal() {
+        bail!("refusing to write image bytes to a terminal; redirect stdout or pass --out <FILE>");
+    }
+    let format = output_format(cli.format, cli.quality, &cli.out)?;
+    let bytes = render_html(&html, &render_options(cli), format)
+        .with_context(|| format!("render failed (out={})", cli.out.display()))?;
+
+    if to_stdout {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(&bytes)?;
+        stdout.flush()?;
+        return Ok(());
+    }
+
+    write_output(&cli.out, &bytes)?;
     writeln!(io::stdout(), "Wrote {}", cli.out.display())?;
     Ok(())
 }
 
-fn pick_icon(rng: &mut StdRng) -> &'static str {
-    const ICONS: &[&str] = &[
-        "★", "✨", "🚀", "🎉", "✅", "💎", "🌙", "☕", "⚡", "🔔", "🧠",
-    ];
-    ICONS.choose(rng).copied().unwrap_or("★")
+/// Print the family names registered from `font_paths`, one per line.
+fn list_fonts(font_paths: &[PathBuf]) -> Result<()> {
+    let families = Renderer::new(font_paths)
+        .context("failed to load fonts")?
+        .registered_families();
+    if families.is_empty() {
+        writeln!(
+            io::stderr(),
+            "No fonts registered; pass --font-path to load font files (system fonts are not listed)"
+        )?;
+    }
+    let mut stdout = io::stdout().lock();
+    for family in families {
+        writeln!(stdout, "{family}")?;
+    }
+    Ok(())
+}
+
+/// Expand `template` with `data`, rejecting undefined variables when `strict`.
+fn expand_template(template: &str, data: &Map<String, Value>, strict: bool) -> Result<String> {
+    let html = if strict {
+        render_template_strict(template, data)
+    } else {
+        render_template(template, data)
+    };
+    html.context("template render failed")
+}
+
+/// Render every `--manifest` entry, print a summary, and fail if any entry failed.
+fn run_manifest(
+    cli: &Cli,
+    manifest_path: &Path,
+    template: &str,
+    data: &Map<String, Value>,
+) -> Result<()> {
+    let entries = manifest::read_manifest(manifest_path)?;
+    let opts = render_options(cli);
+    let batch = Batch {
+        template,
+        data,
+        opts: &opts,
+        format: cli.format,
+        quality: cli.quality,
+        strict: cli.strict,
+    };
+    let outcomes = batch.render_all(&entries, cli.jobs);
+
+    let mut stdout = io::stdout().lock();
+    let mut stderr = io::stderr().lock();
+    let mut failed = 0_usize;
+    for (entry, outcome) in entries.iter().zip(&outcomes) {
+        match outcome {
+            Ok(()) => writeln!(stdout, "Wrote {}", entry.out.display())?,
+            Err(err) => {
+                failed += 1;
+                writeln!(stderr, "Failed {}: {err:#}", entry.out.display())?;
+            }
+        }
+    }
+    writeln!(
+        stdout,
+        "Rendered {} of {} entries",
+        entries.len() - failed,
+        entries.len()
+    )?;
+    if failed > 0 {
+        bail!("{failed} of {} manifest entries failed", entries.len());
+    }
+    Ok(())
+}
+
+/// Render settings shared by the single-image and manifest paths.
+fn render_options(cli: &Cli) -> RenderOptions {
+    RenderOptions::new(cli.width, cli.height)
+        .scale(cli.scale)
+        .animation_time(cli.animation_time)
+        .font_paths(cli.font_paths.clone())
+}
+
+/// Pick the encoder from `--format`, falling back to the `out` extension and then PNG.
+fn output_format(
+    requested: Option<Format>,
+    quality: Option<u8>,
+    out: &Path,
+) -> Result<OutputFormat> {
+    let inferred = out
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(Format::from_extension);
+    let format = match (requested, inferred) {
+        (Some(requested), Some(from_out)) if requested != from_out => bail!(
+            "--format {} does not match the extension of {}",
+            requested.as_str(),
+            out.display()
+        ),
+        (Some(format), _) | (None, Some(format)) => format,
+        (None, None) => Format::Png,
+    };
+    if format == Format::Png && quality.is_some() {
+        bail!("--quality only applies to jpeg and webp output");
+    }
+    Ok(format.output_format(quality))
+}
+
+/// Write encoded image bytes to `out`, creating missing parent directories.
+fn write_output(out: &Path, bytes: &[u8]) -> Result<()> {
+    if let Some(parent) = out.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(out, bytes).with_context(|| format!("failed to write {}", out.display()))
 }
 
-fn pick_message(rng: &mut StdRng) -> &'static str {
-    const MESSAGES: &[&str] = &[
-        "Your shiny Discord-sized card is ready. Crisp, compact, and screenshot-friendly.",
-        "New render dropped: clean edges, smooth gradients, zero browser drama.",
-        "Everything compiled. Nothing exploded. This is your sign to ship it. ✅",
-        "A small card with big energy. Have a great one. ✨",
-        "Pixels are aligned and vibes are immaculate.",
-    ];
-    MESSAGES
-        .choose(rng)
-        .copied()
-        .unwrap_or("Your shiny Discord-sized card is ready.")
+/// Build the template variables: the `--data`/`--data-stdin` object, with the generated
+/// card filling in missing keys and explicit flags (`--var` last) overriding both.
+fn template_data(cli: &Cli) -> Result<Map<String, Value>> {
+    let mut data = if cli.data_stdin {
+        let mut raw = String::new();
+        io::stdin()
+            .read_to_string(&mut raw)
+            .context("failed to read data from stdin")?;
+        serde_json::from_str(&raw).context("data from stdin must be a JSON object")?
+    } else if let Some(path) = &cli.data {
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&raw)
+            .with_context(|| format!("{} must contain a JSON object", path.display()))?
+    } else {
+        Map::new()
+    };
+
+    let card_name = cli
+        .name
+        .as_deref()
+        .or_else(|| data.get("user").and_then(Value::as_str))
+        .unwrap_or(DEFAULT_NAME);
+    if let Value::Object(card) = serde_json::to_value(generate_card(cli.seed, card_name))? {
+        for (key, value) in card {
+            data.entry(key).or_insert(value);
+        }
+    }
+
+    if let Some(name) = &cli.name {
+        data.insert("user".to_owned(), Value::from(name.as_str()));
+    }
+    if let Some(icon) = &cli.icon {
+        data.insert("icon".to_owned(), Value::from(icon.as_str()));
+    }
+    if let Some(message) = &cli.message {
+        data.insert("message".to_owned(), Value::from(message.as_str()));
+    }
+    data.insert("width".to_owned(), Value::from(cli.width));
+    data.insert("height".to_owned(), Value::from(cli.height));
+    data.extend(cli.vars.iter().cloned());
+    Ok(data)
+}
+
+/// Parse a `--var KEY=VALUE` flag, keeping VALUE as a string unless it is valid JSON.
+fn parse_var(raw: &str) -> Result<(String, Value), String> {
+    let Some((key, value)) = raw.split_once('=') else {
+        return Err("expected KEY=VALUE".to_owned());
+    };
+    if key.is_empty() {
+        return Err("missing variable name before `=`".to_owned());
+    }
+    let value = serde_json::from_str(value).unwrap_or_else(|_err| Value::from(value));
+    Ok((key.to_owned(), value))
 }
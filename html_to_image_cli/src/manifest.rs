@@ -0,0 +1,86 @@
+use std::{
+    fs, panic,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use anyhow::{Context, Result};
+use html_to_image::{RenderOptions, render_html};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::{Format, expand_template, output_format, write_output};
+
+/// One `--manifest` record: where to write the image and its own template variables.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ManifestEntry {
+    /// Output path; its extension picks the format unless `--format` is given.
+    pub(crate) out: PathBuf,
+    /// Variables layered over the shared ones from the command line.
+    #[serde(default)]
+    pub(crate) data: Map<String, Value>,
+}
+
+/// Read a manifest file: a JSON array of [`ManifestEntry`] objects.
+pub(crate) fn read_manifest(path: &Path) -> Result<Vec<ManifestEntry>> {
+    let raw =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_str(&raw).with_context(|| format!("invalid manifest {}", path.display()))
+}
+
+/// Settings shared by every entry of a manifest run.
+pub(crate) struct Batch<'run> {
+    pub(crate) template: &'run str,
+    pub(crate) data: &'run Map<String, Value>,
+    pub(crate) opts: &'run RenderOptions,
+    pub(crate) format: Option<Format>,
+    pub(crate) quality: Option<u8>,
+    pub(crate) strict: bool,
+}
+
+impl Batch<'_> {
+    /// Render `entries` on up to `jobs` threads, returning one outcome per entry in order.
+    pub(crate) fn render_all(&self, entries: &[ManifestEntry], jobs: usize) -> Vec<Result<()>> {
+        let next = AtomicUsize::new(0);
+        let mut outcomes: Vec<(usize, Result<()>)> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..jobs.min(entries.len()))
+                .map(|_worker| {
+                    scope.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(entry) = entries.get(index) else {
+                                return done;
+                            };
+                            done.push((index, self.render(entry)));
+                        }
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| {
+                    worker
+                        .join()
+                        .unwrap_or_else(|payload| panic::resume_unwind(payload))
+                })
+                .collect()
+        });
+        outcomes.sort_by_key(|(index, _outcome)| *index);
+        outcomes
+            .into_iter()
+            .map(|(_index, outcome)| outcome)
+            .collect()
+    }
+
+    fn render(&self, entry: &ManifestEntry) -> Result<()> {
+        let mut data = self.data.clone();
+        data.extend(entry.data.clone());
+        let html = expand_template(self.template, &data, self.strict)?;
+        let format = output_format(self.format, self.quality, &entry.out)?;
+        let bytes = render_html(&html, self.opts, format).context("render failed")?;
+        write_output(&entry.out, &bytes)
+    }
+}
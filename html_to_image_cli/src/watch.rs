@@ -0,0 +1,69 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{Result, bail};
+
+use crate::{Cli, STDIO_PATH, render};
+
+/// How often watched files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Render once, then re-render every time a watched input changes, until interrupted.
+pub(crate) fn watch(cli: &Cli) -> Result<()> {
+    if cli.stdin || cli.data_stdin || cli.template == Path::new(STDIO_PATH) {
+        bail!("--watch needs the template and data in files, not on stdin");
+    }
+    if cli.out == Path::new(STDIO_PATH) {
+        bail!("--watch cannot write to stdout");
+    }
+
+    let inputs = watched_paths(cli);
+    let mut seen = modified_times(&inputs);
+    loop {
+        let started = Instant::now();
+        match render(cli) {
+            Ok(()) => writeln!(
+                io::stdout(),
+                "Rendered in {} ms",
+                started.elapsed().as_millis()
+            )?,
+            Err(err) => writeln!(io::stderr(), "Error: {err:#}")?,
+        }
+        writeln!(
+            io::stdout(),
+            "Watching {} file(s) for changes (Ctrl+C to stop)",
+            inputs.len()
+        )?;
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let current = modified_times(&inputs);
+            if current != seen {
+                seen = current;
+                break;
+            }
+        }
+    }
+}
+
+/// The files a render reads: template, `--data`, `--manifest`, and fonts.
+fn watched_paths(cli: &Cli) -> Vec<PathBuf> {
+    let mut paths = vec![cli.template.clone()];
+    paths.extend(cli.data.iter().cloned());
+    paths.extend(cli.manifest.iter().cloned());
+    paths.extend(cli.font_paths.iter().cloned());
+    paths
+}
+
+/// Last-modified time of each path; `None` while a file is missing (e.g. mid-save).
+fn modified_times(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok())
+        .collect()
+}
@@ -1,7 +1,22 @@
-use napi::bindgen_prelude::*;
+use html_to_image::RenderOutput;
+use napi::{Env, bindgen_prelude::*};
 use napi_derive::napi;
 use serde_json::Value;
-use std::path::PathBuf;
+use std::{
+    fs,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    thread,
+    time::Instant,
+};
+use tokio::{sync::Notify, task};
+
+/// `name` of the error a promise rejects with once its `AbortSignal` fires, as in `fetch`.
+const ABORT_ERROR_NAME: &str = "AbortError";
 
 #[napi(object)]
 pub struct RenderRequest {
@@ -21,54 +36,533 @@ pub struct RenderRequest {
     pub font_paths: Option<Vec<String>>,
 }
 
+/// Like [`RenderRequest`], without `out_path`: the PNG is returned instead of written.
+#[napi(object)]
+pub struct RenderBufferRequest {
+    pub template_path: String,
+    pub width: u32,
+    pub height: u32,
+
+    /// Arbitrary JSON-like object from TS (Record<string, any>)
+    pub data: Value,
+
+    /// Optional render tuning
+    pub scale: Option<f64>,
+    pub animation_time: Option<f64>,
+
+    /// Optional extra fonts (paths on disk)
+    pub font_paths: Option<Vec<String>>,
+}
+
+/// Options for [`render_html_to_png`]: final HTML is passed separately, no template.
+#[napi(object)]
+pub struct RenderHtmlRequest {
+    pub out_path: String,
+    pub width: u32,
+    pub height: u32,
+
+    /// Optional render tuning
+    pub scale: Option<f64>,
+    pub animation_time: Option<f64>,
+
+    /// Optional extra fonts (paths on disk)
+    pub font_paths: Option<Vec<String>>,
+}
+
+/// Like [`RenderHtmlRequest`], without `out_path`: the PNG is returned instead of written.
+#[napi(object)]
+pub struct RenderHtmlBufferRequest {
+    pub width: u32,
+    pub height: u32,
+
+    /// Optional render tuning
+    pub scale: Option<f64>,
+    pub animation_time: Option<f64>,
+
+    /// Optional extra fonts (paths on disk)
+    pub font_paths: Option<Vec<String>>,
+}
+
+/// What [`render_template_to_png_result`] rendered.
+#[napi(object)]
+pub struct RenderResult {
+    /// Image width in pixels
+    pub width: u32,
+    /// Image height in pixels
+    pub height: u32,
+    /// Size of the written PNG in bytes
+    pub bytes: u32,
+    /// Wall time of the render and write, in milliseconds
+    pub duration_ms: f64,
+}
+
+/// Outcome of one [`render_batch`] item: `result` on success, `error` otherwise.
+#[napi(object)]
+pub struct BatchResult {
+    /// Position of the item in the request
+    pub index: u32,
+    pub result: Option<RenderResult>,
+    /// Why the item failed
+    pub error: Option<String>,
+}
+
+/// Render settings shared by every entrypoint, with JS defaults applied.
+struct RenderSettings {
+    width: u32,
+    height: u32,
+    scale: f64,
+    animation_time: f64,
+    font_paths: Vec<PathBuf>,
+}
+
+impl RenderSettings {
+    fn new(
+        width: u32,
+        height: u32,
+        scale: Option<f64>,
+        animation_time: Option<f64>,
+        font_paths: Option<Vec<String>>,
+    ) -> Self {
+        Self {
+            width,
+            height,
+            scale: scale.unwrap_or(1.0),
+            animation_time: animation_time.unwrap_or(html_to_image::DEFAULT_ANIMATION_TIME),
+            font_paths: font_paths
+                .unwrap_or_default()
+                .into_iter()
+                .map(PathBuf::from)
+                .collect(),
+        }
+    }
+}
+
+/// Where a render's HTML comes from.
+enum HtmlSource {
+    /// A `MiniJinja` template file expanded with `data`.
+    Template { path: PathBuf, data: Value },
+    /// Final HTML, painted as is.
+    Html(String),
+}
+
+/// One render, skipped once `cancel` is set by the caller's `AbortSignal`.
+struct PngRender {
+    source: HtmlSource,
+    settings: RenderSettings,
+    cancel: Arc<AtomicBool>,
+}
+
+impl PngRender {
+    fn new(source: HtmlSource, settings: RenderSettings) -> Self {
+        Self::with_cancel(source, settings, Arc::default())
+    }
+
+    /// A render sharing `cancel` with others, e.g. the items of one batch.
+    fn with_cancel(source: HtmlSource, settings: RenderSettings, cancel: Arc<AtomicBool>) -> Self {
+        Self {
+            source,
+            settings,
+            cancel,
+        }
+    }
+
+    /// Build the render for one template request, without its `out_path`.
+    fn from_request(req: RenderRequest, cancel: Arc<AtomicBool>) -> (Self, PathBuf) {
+        let render = Self::with_cancel(
+            HtmlSource::Template {
+                path: PathBuf::from(req.template_path),
+                data: req.data,
+            },
+            RenderSettings::new(
+                req.width,
+                req.height,
+                req.scale,
+                req.animation_time,
+                req.font_paths,
+            ),
+            cancel,
+        );
+        (render, PathBuf::from(req.out_path))
+    }
+
+    /// Expand the HTML and render it to a PNG, unless already cancelled.
+    fn output(&self) -> html_to_image::Result<RenderOutput> {
+        if self.is_cancelled() {
+            return Err(html_to_image::RenderError::Cancelled);
+        }
+        let template_html;
+        let html = match &self.source {
+            HtmlSource::Template { path, data } => {
+                let template = html_to_image::load_template(path)?;
+                template_html = html_to_image::render_template(&template, data)?;
+                &template_html
+            }
+            HtmlSource::Html(html) => html,
+        };
+        let settings = &self.settings;
+        let opts = html_to_image::RenderOptions::new(settings.width, settings.height)
+            .scale(settings.scale)
+            .animation_time(settings.animation_time)
+            .font_paths(settings.font_paths.clone());
+        html_to_image::render_html_output(html, &opts)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Acquire)
+    }
+
+    /// [`Self::output`], mapping render failures to `Status::GenericFailure`.
+    fn compute(&self) -> Result<RenderOutput> {
+        self.output()
+            .map_err(|render_error| Error::new(Status::GenericFailure, render_error.to_string()))
+    }
+
+    /// Render and write the PNG to `out_path`, creating its directory if needed.
+    fn write_to(&self, out_path: &Path) -> Result<RenderOutput> {
+        let output = self.compute()?;
+        // Don't leave a file behind for a caller that already gave up.
+        if self.is_cancelled() {
+            return Err(Error::new(
+                Status::Cancelled,
+                html_to_image::RenderError::Cancelled.to_string(),
+            ));
+        }
+        if let Some(parent) = out_path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent).map_err(|io_error| {
+                Error::new(
+                    Status::GenericFailure,
+                    format!(
+                        "failed to create output directory {}: {io_error}",
+                        parent.display()
+                    ),
+                )
+            })?;
+        }
+        fs::write(out_path, &output.bytes).map_err(|io_error| {
+            Error::new(
+                Status::GenericFailure,
+                format!("failed to write png {}: {io_error}", out_path.display()),
+            )
+        })?;
+        Ok(output)
+    }
+
+    /// [`Self::write_to`], timed and summarized as a [`RenderResult`].
+    fn write_result(&self, out_path: &Path) -> Result<RenderResult> {
+        let started = Instant::now();
+        let output = self.write_to(out_path)?;
+        Ok(RenderResult {
+            width: output.width,
+            height: output.height,
+            bytes: u32::try_from(output.bytes.len()).unwrap_or(u32::MAX),
+            duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+        })
+    }
+}
+
+/// Run `job` on a blocking thread via `tokio::task::spawn_blocking`, so the event loop
+/// stays responsive, and settle the returned promise with `to_js` of its output.
+///
+/// Aborting `signal` sets `cancel` and rejects the promise with an `AbortError` at once.
+/// It does not interrupt a render that is already painting: that render keeps its blocking
+/// thread until it finishes, and its result is then discarded.
+fn spawn_render<T, V, J, F>(
+    env: &Env,
+    signal: Option<AbortSignal>,
+    cancel: Arc<AtomicBool>,
+    job: J,
+    to_js: F,
+) -> Result<PromiseRaw<'_, V>>
+where
+    T: Send + 'static,
+    V: ToNapiValue,
+    J: FnOnce() -> Result<T> + Send + 'static,
+    F: FnOnce(T) -> V + 'static,
+{
+    let aborted = Arc::new(Notify::new());
+    if let Some(signal) = signal {
+        let cancel = Arc::clone(&cancel);
+        let aborted = Arc::clone(&aborted);
+        signal.on_abort(move || {
+            cancel.store(true, Ordering::Release);
+            aborted.notify_one();
+        });
+    }
+    // `None` once aborted; the outcome travels to the callback so that a render finishing
+    // just as `signal` fires still rejects with an `AbortError`.
+    let render = async move {
+        let outcome = tokio::select! {
+            joined = task::spawn_blocking(job) => Some(joined.map_err(|join_error| {
+                Error::new(Status::GenericFailure, join_error.to_string())
+            })),
+            () = aborted.notified() => None,
+        };
+        Ok(outcome)
+    };
+    env.spawn_future_with_callback(render, move |env, outcome| match outcome {
+        Some(joined) if !cancel.load(Ordering::Acquire) => joined?.map(to_js),
+        _ => Err(abort_error(*env)?),
+    })
+}
+
+/// The `AbortError` a promise rejects with once its `AbortSignal` fires, as in `fetch`.
+fn abort_error(env: Env) -> Result<Error> {
+    let mut error = env.create_error(Error::new(
+        Status::Cancelled,
+        "The operation was aborted".to_owned(),
+    ))?;
+    error.set_named_property("name", ABORT_ERROR_NAME)?;
+    Ok(Error::from(error.to_unknown()))
+}
+
+/// Render each item to its `out_path`, several at a time, returning one [`BatchResult`]
+/// per item in input order.
+fn render_items(items: &[(PngRender, PathBuf)]) -> Vec<BatchResult> {
+    let workers = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(items.len());
+    let next = AtomicUsize::new(0);
+    let mut outcomes: Vec<Option<Result<RenderResult>>> = items.iter().map(|_item| None).collect();
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_worker| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some((render, out_path)) = items.get(index) else {
+                            break done;
+                        };
+                        done.push((index, render.write_result(out_path)));
+                    }
+                })
+            })
+            .collect();
+        for (index, outcome) in handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap_or_default())
+        {
+            if let Some(slot) = outcomes.get_mut(index) {
+                *slot = Some(outcome);
+            }
+        }
+    });
+
+    outcomes
+        .into_iter()
+        .enumerate()
+        .map(|(index, outcome)| {
+            let index = u32::try_from(index).unwrap_or(u32::MAX);
+            match outcome {
+                Some(Ok(result)) => BatchResult {
+                    index,
+                    result: Some(result),
+                    error: None,
+                },
+                Some(Err(err)) => BatchResult {
+                    index,
+                    result: None,
+                    error: Some(err.reason),
+                },
+                // Its worker panicked, losing the outcome along with it.
+                None => BatchResult {
+                    index,
+                    result: None,
+                    error: Some("render worker panicked".to_owned()),
+                },
+            }
+        })
+        .collect()
+}
+
 /// Render a `MiniJinja` HTML template to a PNG on disk.
 ///
 /// This is the Node-API entrypoint. Rendering is executed on a blocking thread via
-/// `tokio::task::spawn_blocking` to avoid blocking the async runtime.
+/// `tokio::task::spawn_blocking` to avoid blocking the event loop. Aborting `signal`
+/// rejects the promise with an `AbortError`, as described for [`spawn_render`].
 ///
 /// # Errors
 ///
-/// Returns a `napi::Error` with `Status::GenericFailure` if:
+/// The promise rejects with `Status::GenericFailure` if:
 /// - The renderer fails to load or render the template (e.g., invalid `template_path`,
 ///   template/rendering error, missing assets/fonts, or other `html_to_image` failures).
 /// - Writing the PNG fails (e.g., invalid `out_path` or permission/IO errors).
 /// - The blocking task fails to join (e.g., the task panicked), in which case the join
 ///   error is surfaced as `GenericFailure`.
 #[napi]
-pub async fn render_template_to_png(req: RenderRequest) -> Result<()> {
-    let template_path = PathBuf::from(req.template_path);
-    let out_path = PathBuf::from(req.out_path);
-    let width = req.width;
-    let height = req.height;
-
-    let data = req.data;
-    let scale = req.scale.unwrap_or(1.0);
-    let animation_time = req
-        .animation_time
-        .unwrap_or(html_to_image::DEFAULT_ANIMATION_TIME);
-
-    let font_paths: Vec<PathBuf> = req
-        .font_paths
-        .unwrap_or_default()
-        .into_iter()
-        .map(PathBuf::from)
-        .collect();
+pub fn render_template_to_png(
+    env: &Env,
+    req: RenderRequest,
+    signal: Option<AbortSignal>,
+) -> Result<PromiseRaw<'_, ()>> {
+    let (render, out_path) = PngRender::from_request(req, Arc::default());
+    spawn_render(
+        env,
+        signal,
+        Arc::clone(&render.cancel),
+        move || render.write_to(&out_path).map(drop),
+        |()| (),
+    )
+}
 
-    spawn_blocking(move || {
-        html_to_image::render_to_png(
-            template_path.as_path(),
-            &data,
-            out_path.as_path(),
-            width,
-            height,
-            scale,
-            animation_time,
-            &font_paths,
-        )
-        .map_err(|render_error| Error::new(Status::GenericFailure, render_error.to_string()))
-    })
-    .await
-    .map_err(|join_error| Error::new(Status::GenericFailure, join_error.to_string()))??;
+/// Render a `MiniJinja` HTML template to a PNG on disk and report what was rendered.
+///
+/// Works like [`render_template_to_png`]; the promise resolves to the PNG's dimensions,
+/// its size in bytes, and how long the render and write took.
+///
+/// # Errors
+///
+/// The promise rejects like [`render_template_to_png`]'s.
+#[napi]
+pub fn render_template_to_png_result(
+    env: &Env,
+    req: RenderRequest,
+    signal: Option<AbortSignal>,
+) -> Result<PromiseRaw<'_, RenderResult>> {
+    let (render, out_path) = PngRender::from_request(req, Arc::default());
+    spawn_render(
+        env,
+        signal,
+        Arc::clone(&render.cancel),
+        move || render.write_result(&out_path),
+        |result| result,
+    )
+}
+
+/// Render a `MiniJinja` HTML template to PNG bytes returned as a Node `Buffer`.
+///
+/// Same rendering path as [`render_template_to_png`], without the temp file: useful in
+/// serverless functions that upload or stream the image straight away.
+///
+/// # Errors
+///
+/// The promise rejects with `Status::GenericFailure` if the template cannot be loaded or
+/// rendered, and with an `AbortError` once `signal` is aborted.
+#[napi]
+pub fn render_template_to_png_buffer(
+    env: &Env,
+    req: RenderBufferRequest,
+    signal: Option<AbortSignal>,
+) -> Result<PromiseRaw<'_, Buffer>> {
+    let render = PngRender::new(
+        HtmlSource::Template {
+            path: PathBuf::from(req.template_path),
+            data: req.data,
+        },
+        RenderSettings::new(
+            req.width,
+            req.height,
+            req.scale,
+            req.animation_time,
+            req.font_paths,
+        ),
+    );
+    spawn_png_buffer(env, render, signal)
+}
 
-    Ok(())
+/// Render final HTML held in memory to a PNG on disk, without a template file.
+///
+/// # Errors
+///
+/// The promise rejects with `Status::GenericFailure` if rendering or writing the PNG
+/// fails, and with an `AbortError` once `signal` is aborted.
+#[napi]
+pub fn render_html_to_png(
+    env: &Env,
+    html: String,
+    opts: RenderHtmlRequest,
+    signal: Option<AbortSignal>,
+) -> Result<PromiseRaw<'_, ()>> {
+    let render = PngRender::new(
+        HtmlSource::Html(html),
+        RenderSettings::new(
+            opts.width,
+            opts.height,
+            opts.scale,
+            opts.animation_time,
+            opts.font_paths,
+        ),
+    );
+    let out_path = PathBuf::from(opts.out_path);
+    spawn_render(
+        env,
+        signal,
+        Arc::clone(&render.cancel),
+        move || render.write_to(&out_path).map(drop),
+        |()| (),
+    )
+}
+
+/// Render final HTML held in memory to PNG bytes returned as a Node `Buffer`.
+///
+/// # Errors
+///
+/// The promise rejects with `Status::GenericFailure` if rendering fails, and with an
+/// `AbortError` once `signal` is aborted.
+#[napi]
+pub fn render_html_to_png_buffer(
+    env: &Env,
+    html: String,
+    opts: RenderHtmlBufferRequest,
+    signal: Option<AbortSignal>,
+) -> Result<PromiseRaw<'_, Buffer>> {
+    let render = PngRender::new(
+        HtmlSource::Html(html),
+        RenderSettings::new(
+            opts.width,
+            opts.height,
+            opts.scale,
+            opts.animation_time,
+            opts.font_paths,
+        ),
+    );
+    spawn_png_buffer(env, render, signal)
+}
+
+/// [`spawn_render`] for the entrypoints resolving to the PNG bytes.
+fn spawn_png_buffer(
+    env: &Env,
+    render: PngRender,
+    signal: Option<AbortSignal>,
+) -> Result<PromiseRaw<'_, Buffer>> {
+    spawn_render(
+        env,
+        signal,
+        Arc::clone(&render.cancel),
+        move || render.compute().map(|output| output.bytes),
+        Buffer::from,
+    )
+}
+
+/// Render many `MiniJinja` templates to PNGs on disk in one call.
+///
+/// Items render concurrently, at most one per available CPU, each to its own `out_path`.
+/// The promise resolves to one [`BatchResult`] per item in input order; a failing item
+/// only fails its own entry. Aborting `signal` rejects the whole batch with an
+/// `AbortError` and skips the items that have not started yet.
+///
+/// # Errors
+///
+/// The promise rejects with an `AbortError` once `signal` is aborted; render and write
+/// failures only set the failing item's `error`.
+#[napi]
+pub fn render_batch(
+    env: &Env,
+    items: Vec<RenderRequest>,
+    signal: Option<AbortSignal>,
+) -> Result<PromiseRaw<'_, Vec<BatchResult>>> {
+    let cancel: Arc<AtomicBool> = Arc::default();
+    let items: Vec<_> = items
+        .into_iter()
+        .map(|req| PngRender::from_request(req, Arc::clone(&cancel)))
+        .collect();
+    spawn_render(
+        env,
+        signal,
+        cancel,
+        move || Ok(render_items(&items)),
+        |results| results,
+    )
 }
@@ -0,0 +1,61 @@
+use std::{collections::HashSet, sync::Arc};
+
+use poem::{Endpoint, Middleware, Request, Result, http::header::AUTHORIZATION};
+
+use crate::{ApiError, constant_time_eq};
+
+/// Rejects requests without `Authorization: Bearer <key>` for one of the configured keys.
+///
+/// The admin token is accepted as well, so `/admin` requests need only their usual header.
+pub(crate) struct ApiKeyAuth {
+    keys: Arc<HashSet<String>>,
+}
+
+impl ApiKeyAuth {
+    pub(crate) fn new(api_keys: &HashSet<String>, admin_token: Option<&str>) -> Self {
+        let mut keys = api_keys.clone();
+        keys.extend(admin_token.map(str::to_owned));
+        Self {
+            keys: Arc::new(keys),
+        }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for ApiKeyAuth {
+    type Output = ApiKeyAuthEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        ApiKeyAuthEndpoint {
+            inner: ep,
+            keys: Arc::clone(&self.keys),
+        }
+    }
+}
+
+pub(crate) struct ApiKeyAuthEndpoint<E> {
+    inner: E,
+    keys: Arc<HashSet<String>>,
+}
+
+impl<E: Endpoint> Endpoint for ApiKeyAuthEndpoint<E> {
+    type Output = E::Output;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        let presented = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim);
+        // Compare against every key so the response time does not reveal which one matched.
+        let authorized = presented.is_some_and(|presented| {
+            self.keys.iter().fold(false, |found, key| {
+                constant_time_eq(key.as_bytes(), presented.as_bytes()) | found
+            })
+        });
+        if !authorized {
+            return Err(ApiError::Unauthorized.into());
+        }
+        self.inner.call(req).await
+    }
+}
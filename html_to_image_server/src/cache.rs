@@ -0,0 +1,175 @@
+use std::{
+    collections::HashMap,
+    fs,
+    hash::{DefaultHasher, Hash as _, Hasher as _},
+    path::{Path, PathBuf},
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::SystemTime,
+};
+
+use html_to_image::{RenderOptions, RenderOutput};
+
+/// Everything that determines the PNG bytes of a `/render/png` request.
+///
+/// The template is keyed after expansion, so the request's `data` is covered by `html`.
+/// Floats are compared bit-for-bit.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct RenderKey {
+    html: String,
+    width: u32,
+    height: u32,
+    scale: u64,
+    animation_time: u64,
+    default_font: Option<PathBuf>,
+    font_paths: Vec<PathBuf>,
+    enable_emoji: bool,
+    debug_boxes: bool,
+    collect_warnings: bool,
+}
+
+impl RenderKey {
+    pub(crate) fn new(html: &str, opts: &RenderOptions) -> Self {
+        Self {
+            html: html.to_owned(),
+            width: opts.width,
+            height: opts.height,
+            scale: opts.scale.to_bits(),
+            animation_time: opts.animation_time.to_bits(),
+            default_font: opts.default_font.clone(),
+            font_paths: opts.font_paths.clone(),
+            enable_emoji: opts.enable_emoji,
+            debug_boxes: opts.debug_boxes,
+            collect_warnings: opts.collect_warnings,
+        }
+    }
+
+    /// Strong `ETag` naming this render's PNG: the quoted hex hash of the key without
+    /// `collect_warnings`, which does not change the image, plus each font file's size and
+    /// modification time, so replacing a font on disk changes the tag. The hash is stable
+    /// for a given server build; a rebuild may change it, costing clients one full download.
+    pub(crate) fn etag(&self) -> String {
+        let Self {
+            html,
+            width,
+            height,
+            scale,
+            animation_time,
+            default_font,
+            font_paths,
+            enable_emoji,
+            debug_boxes,
+            collect_warnings: _,
+        } = self;
+        let mut hasher = DefaultHasher::new();
+        (
+            html,
+            width,
+            height,
+            scale,
+            animation_time,
+            default_font,
+            font_paths,
+            enable_emoji,
+            debug_boxes,
+        )
+            .hash(&mut hasher);
+        for path in default_font.iter().chain(font_paths) {
+            font_stamp(path).hash(&mut hasher);
+        }
+        format!("\"{:016x}\"", hasher.finish())
+    }
+}
+
+/// Size and modification time of a font file, or `None` if either cannot be read.
+fn font_stamp(path: &Path) -> Option<(u64, SystemTime)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.len(), metadata.modified().ok()?))
+}
+
+/// In-memory LRU of rendered PNGs and their dimensions, bounded by entry count.
+#[derive(Debug)]
+pub(crate) struct RenderCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    /// Monotonic use counter; the entry with the smallest `last_used` is evicted first.
+    clock: u64,
+    map: HashMap<RenderKey, CachedPng>,
+}
+
+#[derive(Debug)]
+struct CachedPng {
+    output: RenderOutput,
+    last_used: u64,
+}
+
+impl RenderCache {
+    /// A cache holding up to `capacity` renders, or `None` when `capacity` is zero.
+    pub(crate) fn new(capacity: usize) -> Option<Self> {
+        (capacity > 0).then(|| Self {
+            capacity,
+            entries: Mutex::default(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        })
+    }
+
+    pub(crate) fn get(&self, key: &RenderKey) -> Option<RenderOutput> {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries.clock += 1;
+        let clock = entries.clock;
+        let found = entries.map.get_mut(key).map(|cached| {
+            cached.last_used = clock;
+            cached.output.clone()
+        });
+
+        let counter = if found.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+
+    pub(crate) fn insert(&self, key: RenderKey, output: RenderOutput) {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        if entries.map.len() >= self.capacity && !entries.map.contains_key(&key) {
+            let oldest = entries
+                .map
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(oldest, _)| oldest.clone());
+            if let Some(oldest) = oldest {
+                entries.map.remove(&oldest);
+            }
+        }
+        entries.clock += 1;
+        let last_used = entries.clock;
+        entries.map.insert(key, CachedPng { output, last_used });
+    }
+
+    pub(crate) fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .map
+            .len()
+    }
+}
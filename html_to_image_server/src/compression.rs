@@ -0,0 +1,93 @@
+use poem::{
+    Endpoint, IntoResponse, Middleware, Request, Response, Result,
+    http::{
+        HeaderValue, Method, StatusCode,
+        header::{ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE, VARY},
+    },
+    web::{Compress, CompressionAlgo},
+};
+
+use crate::parse_quality_list;
+
+/// Compresses text and JSON responses (the API spec, base64 renders, errors) with
+/// gzip or deflate, as negotiated from `Accept-Encoding`.
+///
+/// Image bodies are already compressed and pass through untouched, as do bodiless `HEAD`,
+/// `204`, and `304` responses, whose `Content-Length` describes a body they do not send.
+/// Unlike poem's `Compression`, request bodies are never decompressed, so body size limits
+/// keep applying to the bytes a client actually sends.
+pub(crate) struct ResponseCompression;
+
+impl<E: Endpoint> Middleware<E> for ResponseCompression {
+    type Output = ResponseCompressionEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        ResponseCompressionEndpoint { inner: ep }
+    }
+}
+
+pub(crate) struct ResponseCompressionEndpoint<E> {
+    inner: E,
+}
+
+impl<E: Endpoint> Endpoint for ResponseCompressionEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        let algo = req
+            .headers()
+            .get(ACCEPT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+            .and_then(negotiate_encoding)
+            .filter(|_| req.method() != Method::HEAD);
+        let mut response = self.inner.get_response(req).await;
+        response
+            .headers_mut()
+            .append(VARY, HeaderValue::from_static("Accept-Encoding"));
+
+        match algo {
+            Some(algo) if is_compressible(&response) => {
+                Ok(Compress::new(response, algo).into_response())
+            }
+            _ => Ok(response),
+        }
+    }
+}
+
+/// Whether `response` is worth compressing: it has a body, not already encoded and not an
+/// image.
+fn is_compressible(response: &Response) -> bool {
+    if matches!(
+        response.status(),
+        StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED
+    ) {
+        return false;
+    }
+    let headers = response.headers();
+    let is_image = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("image/"));
+    !is_image && !headers.contains_key(CONTENT_ENCODING)
+}
+
+/// Pick gzip or deflate from an `Accept-Encoding` value, preferring the higher `q` and then
+/// gzip; `*` stands for gzip.
+fn negotiate_encoding(accept: &str) -> Option<CompressionAlgo> {
+    parse_quality_list(accept)
+        .into_iter()
+        .filter_map(|(coding, quality)| {
+            let (algo, preference) = match coding.as_str() {
+                "gzip" | "*" => (CompressionAlgo::GZIP, 1),
+                "deflate" => (CompressionAlgo::DEFLATE, 0),
+                _ => return None,
+            };
+            (quality > 0.0).then_some((algo, quality, preference))
+        })
+        .max_by(|left, right| {
+            left.1
+                .total_cmp(&right.1)
+                .then_with(|| left.2.cmp(&right.2))
+        })
+        .map(|(algo, _, _)| algo)
+}
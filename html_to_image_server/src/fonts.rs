@@ -0,0 +1,146 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use poem_openapi::Object;
+use tracing::{debug, warn};
+
+pub const DEFAULT_MAX_FONTS_SCANNED: usize = 1024;
+
+const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "ttc", "otc"];
+
+/// Public font aliases mapped to files relative to the fonts directory. When configured,
+/// requests name fonts by alias only and never see the underlying file names.
+pub type FontManifest = BTreeMap<String, String>;
+
+/// Bounds for scanning the fonts directory.
+#[derive(Debug, Clone)]
+pub struct FontScanConfig {
+    /// Stop scanning after this many font files; the rest of the directory is ignored.
+    pub max_fonts_scanned: usize,
+    /// Rescan at most this often. `None` scans once at startup and keeps that result.
+    pub refresh_interval: Option<Duration>,
+}
+
+impl Default for FontScanConfig {
+    fn default() -> Self {
+        Self {
+            max_fonts_scanned: DEFAULT_MAX_FONTS_SCANNED,
+            refresh_interval: None,
+        }
+    }
+}
+
+/// Font files found under the fonts directory, as paths relative to it.
+#[derive(Object, Debug, Clone, Default, PartialEq, Eq)]
+pub struct FontScan {
+    pub fonts: Vec<String>,
+    /// `true` when the scan stopped at `max_fonts_scanned`.
+    pub truncated: bool,
+}
+
+/// Cached scan of a fonts directory, refreshed lazily and at most once per interval.
+#[derive(Debug)]
+pub struct FontCatalog {
+    dir: PathBuf,
+    config: FontScanConfig,
+    cached: Mutex<CachedScan>,
+}
+
+#[derive(Debug)]
+struct CachedScan {
+    scanned_at: Instant,
+    scan: FontScan,
+}
+
+impl FontCatalog {
+    /// Scan `dir` once and cache the result.
+    #[must_use]
+    pub fn new(dir: PathBuf, config: FontScanConfig) -> Self {
+        let scan = scan_fonts_dir(&dir, config.max_fonts_scanned);
+        Self {
+            dir,
+            config,
+            cached: Mutex::new(CachedScan {
+                scanned_at: Instant::now(),
+                scan,
+            }),
+        }
+    }
+
+    /// The cached scan, rescanning first if the refresh interval has elapsed.
+    pub fn fonts(&self) -> FontScan {
+        let mut cached = self.cached.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(interval) = self.config.refresh_interval
+            && cached.scanned_at.elapsed() >= interval
+        {
+            cached.scan = scan_fonts_dir(&self.dir, self.config.max_fonts_scanned);
+            cached.scanned_at = Instant::now();
+        }
+        cached.scan.clone()
+    }
+}
+
+/// Recursively collect font files under `dir`, stopping after `max_fonts` entries.
+///
+/// Directories are visited in sorted order so truncation is deterministic.
+#[must_use]
+pub fn scan_fonts_dir(dir: &Path, max_fonts: usize) -> FontScan {
+    let mut scan = FontScan::default();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        let mut entries = match fs::read_dir(&current) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .collect::<Vec<_>>(),
+            Err(err) => {
+                warn!(dir = %current.display(), %err, "failed to read fonts directory");
+                continue;
+            }
+        };
+        entries.sort();
+
+        let mut subdirs = Vec::new();
+        for path in entries {
+            if path.is_dir() {
+                subdirs.push(path);
+                continue;
+            }
+            if !is_font_file(&path) {
+                continue;
+            }
+            if scan.fonts.len() >= max_fonts {
+                scan.truncated = true;
+                warn!(
+                    dir = %dir.display(),
+                    max_fonts_scanned = max_fonts,
+                    "font scan truncated"
+                );
+                return scan;
+            }
+            if let Ok(relative) = path.strip_prefix(dir) {
+                scan.fonts.push(relative.to_string_lossy().into_owned());
+            }
+        }
+        // Pop order is LIFO, so push in reverse to descend alphabetically.
+        pending.extend(subdirs.into_iter().rev());
+    }
+
+    debug!(dir = %dir.display(), fonts = scan.fonts.len(), "scanned fonts directory");
+    scan
+}
+
+fn is_font_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            FONT_EXTENSIONS
+                .iter()
+                .any(|known| ext.eq_ignore_ascii_case(known))
+        })
+}
@@ -0,0 +1,265 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use html_to_image::RenderOptions;
+use poem_openapi::{Enum, Object};
+use reqwest::{Client, Url, redirect::Policy};
+use serde::Serialize;
+use tokio::net;
+use tracing::{info, warn};
+
+use crate::{ApiError, EncodedRender, pool::RenderPool};
+
+/// Finished jobs kept for polling; older ones are forgotten first.
+const MAX_FINISHED_JOBS: usize = 1024;
+/// How long a callback receiver may take to answer.
+const CALLBACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// A `/render/async` job as reported by `GET /render/async/{id}`.
+#[derive(Object, Debug, Clone, Serialize)]
+pub struct RenderJob {
+    pub id: String,
+    pub status: JobStatus,
+    /// Why the render failed, once `status` is `failed`.
+    pub error: Option<String>,
+    /// Whether the receiver accepted the callback; unset until delivery was attempted.
+    pub callback_delivered: Option<bool>,
+    /// Why the callback could not be delivered.
+    pub callback_error: Option<String>,
+}
+
+/// Body sent to the job's `callback_url`: the job plus, on success, the base64 PNG.
+#[derive(Debug, Serialize)]
+struct JobCallback<'job> {
+    id: &'job str,
+    status: JobStatus,
+    error: Option<&'job str>,
+    #[serde(flatten)]
+    render: Option<EncodedRender>,
+}
+
+/// In-memory table of async render jobs.
+#[derive(Debug, Default)]
+pub(crate) struct JobStore {
+    jobs: Mutex<Jobs>,
+}
+
+#[derive(Debug, Default)]
+struct Jobs {
+    map: HashMap<String, RenderJob>,
+    /// Finished job ids, oldest first.
+    finished: VecDeque<String>,
+}
+
+impl JobStore {
+    pub(crate) fn insert(&self, id: String) -> RenderJob {
+        let job = RenderJob {
+            id: id.clone(),
+            status: JobStatus::Queued,
+            error: None,
+            callback_delivered: None,
+            callback_error: None,
+        };
+        self.lock().map.insert(id, job.clone());
+        job
+    }
+
+    pub(crate) fn get(&self, id: &str) -> Option<RenderJob> {
+        self.lock().map.get(id).cloned()
+    }
+
+    fn update(&self, id: &str, update: impl FnOnce(&mut RenderJob)) {
+        if let Some(job) = self.lock().map.get_mut(id) {
+            update(job);
+        }
+    }
+
+    fn finish(&self, id: &str, error: Option<String>) {
+        let mut jobs = self.lock();
+        let Some(job) = jobs.map.get_mut(id) else {
+            return;
+        };
+        job.status = if error.is_some() {
+            JobStatus::Failed
+        } else {
+            JobStatus::Succeeded
+        };
+        job.error = error;
+
+        jobs.finished.push_back(id.to_owned());
+        while jobs.finished.len() > MAX_FINISHED_JOBS {
+            if let Some(oldest) = jobs.finished.pop_front() {
+                jobs.map.remove(&oldest);
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Jobs> {
+        self.jobs.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A checked callback URL and the addresses its host resolved to when it was checked.
+pub(crate) struct CallbackTarget {
+    url: Url,
+    addrs: Vec<SocketAddr>,
+}
+
+/// Accept only absolute `http`/`https` callback URLs whose host is in `allowed_hosts`, or,
+/// without an allow-list, resolves only to public addresses.
+///
+/// The checked addresses are kept and delivery connects to them, so a second DNS answer
+/// cannot point the callback at an internal service afterwards.
+pub(crate) async fn resolve_callback_url(
+    raw: &str,
+    allowed_hosts: Option<&HashSet<String>>,
+) -> Result<CallbackTarget, ApiError> {
+    let url = Url::parse(raw)
+        .map_err(|err| ApiError::validation(format!("invalid callback_url: {err}")))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(ApiError::validation("callback_url must use http or https"));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| ApiError::validation("callback_url must name a host"))?;
+    if let Some(allowed) = allowed_hosts
+        && !allowed.iter().any(|known| known.eq_ignore_ascii_case(host))
+    {
+        return Err(ApiError::validation(
+            "callback_url host is not an allowed callback host",
+        ));
+    }
+
+    let bare_host = host.trim_start_matches('[').trim_end_matches(']');
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addrs: Vec<SocketAddr> = net::lookup_host((bare_host, port))
+        .await
+        .map_err(|err| ApiError::validation(format!("cannot resolve callback_url: {err}")))?
+        .collect();
+    if addrs.is_empty()
+        || (allowed_hosts.is_none() && !addrs.iter().all(|addr| is_public(addr.ip())))
+    {
+        return Err(ApiError::validation(
+            "callback_url must resolve to public addresses",
+        ));
+    }
+    Ok(CallbackTarget { url, addrs })
+}
+
+/// Whether `ip` is reachable on the public internet: not loopback, private, link-local
+/// (cloud metadata lives there), shared, documentation, multicast, or unspecified.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_v4(mapped),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [first, second, ..] = ip.octets();
+    let shared = first == 100 && (64..128).contains(&second);
+    !(ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || shared
+        || first == 0)
+}
+
+/// Everything a background job needs, detached from the request.
+pub(crate) struct JobRunner {
+    pub(crate) jobs: Arc<JobStore>,
+    pub(crate) pool: RenderPool,
+}
+
+impl JobRunner {
+    /// Render job `id`, record the outcome, and POST it to `callback_url`.
+    pub(crate) async fn run(
+        self,
+        id: String,
+        html: String,
+        opts: RenderOptions,
+        callback: CallbackTarget,
+    ) {
+        self.jobs.update(&id, |job| job.status = JobStatus::Running);
+        let (render, error) = match self.pool.render_png_output(html, opts).await {
+            Ok(output) => (Some(EncodedRender::from(output)), None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        self.jobs.finish(&id, error.clone());
+
+        let body = JobCallback {
+            id: &id,
+            status: if error.is_some() {
+                JobStatus::Failed
+            } else {
+                JobStatus::Succeeded
+            },
+            error: error.as_deref(),
+            render,
+        };
+        match deliver(callback, &body).await {
+            Ok(()) => {
+                info!(job = %id, "render callback delivered");
+                self.jobs
+                    .update(&id, |job| job.callback_delivered = Some(true));
+            }
+            Err(err) => {
+                warn!(job = %id, %err, "render callback failed");
+                self.jobs.update(&id, |job| {
+                    job.callback_delivered = Some(false);
+                    job.callback_error = Some(err);
+                });
+            }
+        }
+    }
+}
+
+/// POST `body` to `callback`, connecting only to its checked addresses and without
+/// following redirects.
+async fn deliver(callback: CallbackTarget, body: &JobCallback<'_>) -> Result<(), String> {
+    let mut client = Client::builder()
+        .redirect(Policy::none())
+        .timeout(CALLBACK_TIMEOUT);
+    if let Some(domain) = callback.url.domain() {
+        client = client.resolve_to_addrs(domain, &callback.addrs);
+    }
+    let client = client.build().map_err(|err| err.to_string())?;
+    let response = client
+        .post(callback.url)
+        .json(body)
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("callback answered {}", response.status()))
+    }
+}
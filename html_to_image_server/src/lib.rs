@@ -3,7 +3,17 @@
     reason = "Package shares dependencies across lib/bin/test targets; some are bin-only."
 )]
 
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashSet,
+    num::NonZeroUsize,
+    path::{Component, Path, PathBuf},
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
 
 // Ensure package-level unused dependency lint stays satisfied when building the library target.
 #[allow(
@@ -22,36 +32,92 @@ use dotenvy as _;
 )]
 use tracing_subscriber as _;
 
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 use html_to_image::{
-    DEFAULT_ANIMATION_TIME, DEFAULT_SCALE, RenderError, render_html_to_png_bytes, render_template,
+    AutoEscapeMode, CARD_TEMPLATE, DEFAULT_ANIMATION_TIME, DEFAULT_CARD_HEIGHT, DEFAULT_CARD_WIDTH,
+    DEFAULT_SCALE, OutputFormat, RenderError, RenderOptions, RenderOutput, ensure_html_not_empty,
+    generate_card, layout_html, render_html, render_html_bytes_with_fonts,
+    render_html_to_png_with_thumbnail, render_template, render_template_with_limit,
 };
+use minijinja::UndefinedBehavior;
 use poem::{
-    Endpoint, EndpointExt, IntoResponse, Response, Route,
+    Endpoint, EndpointExt, IntoResponse, Middleware, Request, Response, Route,
     endpoint::make_sync,
-    error::ResponseError,
-    http::StatusCode,
-    middleware::{SizeLimit, Tracing},
+    error::{ResponseError, SizedLimitError},
+    http::{HeaderValue, Method, StatusCode, header::RETRY_AFTER},
+    middleware::{Cors, SizeLimit, SizeLimitEndpoint, Tracing},
     web::Json as PoemJson,
 };
 use poem_openapi::{
-    ApiResponse, Object, OpenApi, OpenApiService,
-    payload::{Binary, Json as OpenApiJson},
-    types::Any,
+    ApiResponse, Enum, Multipart, Object, OpenApi, OpenApiService, SecurityScheme,
+    auth::Bearer,
+    param::{self, Header, Query},
+    payload::{Binary, Html, Json as OpenApiJson},
+    registry::{MetaResponse, MetaResponses, Registry},
+    types::{
+        Any,
+        multipart::{JsonField, Upload},
+    },
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use thiserror::Error;
-use tokio::task;
-use tracing::error;
+use tokio::task::{self, JoinSet};
+use tracing::{Instrument as _, debug, error, info, info_span, warn};
+
+use crate::{
+    auth::ApiKeyAuth,
+    cache::{RenderCache, RenderKey},
+    compression::ResponseCompression,
+    jobs::{JobRunner, JobStore, resolve_callback_url},
+    pool::RenderPool,
+    rate_limit::RateLimit,
+    request_id::{RequestId, current_request_id, random_uuid},
+};
+
+mod auth;
+mod cache;
+mod compression;
+mod fonts;
+mod jobs;
+mod pool;
+mod rate_limit;
+mod request_id;
+
+pub use fonts::{
+    DEFAULT_MAX_FONTS_SCANNED, FontCatalog, FontManifest, FontScan, FontScanConfig, scan_fonts_dir,
+};
+pub use jobs::{JobStatus, RenderJob};
 
 pub const DEFAULT_MAX_BODY_SIZE: usize = 0x0010_0000; // 1 MiB
 pub const MAX_DIMENSION: u32 = 4096;
 pub const MAX_SCALE: f64 = 8.0;
 pub const MAX_ANIMATION_TIME: f64 = 60.0;
+pub const DEFAULT_THUMBNAIL_WIDTH: u32 = 160;
+pub const DEFAULT_JPEG_QUALITY: u8 = 85;
+pub const DEFAULT_MAX_BATCH: usize = 32;
+/// Most `font_paths` entries one request may name; each is a file to open and parse.
+pub const DEFAULT_MAX_FONTS_PER_REQUEST: usize = 16;
+/// Largest HTML a request's template may expand to before rendering is abandoned.
+pub const DEFAULT_MAX_RENDERED_HTML: usize = 0x0080_0000; // 8 MiB
+pub const DEFAULT_RENDER_TIMEOUT: Duration = Duration::from_secs(30);
+pub const DEFAULT_MAX_QUEUED_RENDERS: usize = 64;
+/// How long browsers may cache a CORS preflight response.
+const CORS_MAX_AGE_SECS: i32 = 600;
+pub const DEFAULT_MAX_FONT_UPLOAD_SIZE: usize = 0x0008_0000; // 512 KiB
+pub const DEFAULT_MAX_FONT_UPLOADS_SIZE: usize = 0x000C_0000; // 768 KiB
 
-#[derive(Debug, Clone)]
+/// Must match the boundary declared on [`ThumbnailResponse::Multipart`].
+const MULTIPART_BOUNDARY: &str = "html-to-image-part-4f1c9a7e2d";
+
+#[derive(Debug, Clone, Default)]
 pub struct AppState {
     pub fonts_dir: Option<PathBuf>,
+    /// Restrict `font_paths` to these aliases instead of raw file names.
+    pub font_manifest: Option<FontManifest>,
+    /// Font registered for every render ahead of the requested `font_paths`; it backs the
+    /// generic families, so requests still pick their own fonts by name in CSS.
+    pub default_font: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone)]
@@ -59,6 +125,26 @@ pub struct AppLimits {
     pub max_dimension: u32,
     pub max_scale: f64,
     pub max_animation_time: f64,
+    /// Reject templates that render to empty/whitespace-only HTML instead of a blank image.
+    pub reject_empty_html: bool,
+    /// Most items accepted by one `/render/batch` request.
+    pub max_batch: usize,
+    /// Most `font_paths` entries accepted by one request, checked before any is resolved.
+    pub max_fonts_per_request: usize,
+    /// Lowest JPEG `quality` accepted; raise it to refuse settings that only produce mush.
+    pub min_jpeg_quality: u8,
+    /// Highest JPEG `quality` accepted; lower it to cap the size of JPEG responses.
+    pub max_jpeg_quality: u8,
+    /// Answer 413 when a template expands to more than this many bytes of HTML. The body
+    /// limit cannot catch this, since a tiny template can loop into megabytes.
+    pub max_rendered_html: usize,
+    /// Answer 504 when a single render, including its wait for a render slot, takes longer
+    /// than this.
+    pub render_timeout: Duration,
+    /// Largest single font file accepted by `/render/png/multipart`.
+    pub max_font_upload_size: usize,
+    /// Largest combined size of the font files in one `/render/png/multipart` request.
+    pub max_font_uploads_size: usize,
 }
 
 impl Default for AppLimits {
@@ -67,6 +153,15 @@ impl Default for AppLimits {
             max_dimension: MAX_DIMENSION,
             max_scale: MAX_SCALE,
             max_animation_time: MAX_ANIMATION_TIME,
+            reject_empty_html: false,
+            max_batch: DEFAULT_MAX_BATCH,
+            max_fonts_per_request: DEFAULT_MAX_FONTS_PER_REQUEST,
+            min_jpeg_quality: 1,
+            max_jpeg_quality: 100,
+            max_rendered_html: DEFAULT_MAX_RENDERED_HTML,
+            render_timeout: DEFAULT_RENDER_TIMEOUT,
+            max_font_upload_size: DEFAULT_MAX_FONT_UPLOAD_SIZE,
+            max_font_uploads_size: DEFAULT_MAX_FONT_UPLOADS_SIZE,
         }
     }
 }
@@ -77,23 +172,102 @@ pub struct AppConfig {
     pub limits: AppLimits,
     pub max_body_size: usize,
     pub server_base_url: Option<String>,
+    pub font_scan: FontScanConfig,
+    /// Bearer token for `/admin/*` endpoints; `None` rejects every admin request.
+    pub admin_token: Option<String>,
+    /// Keep up to this many `/render/png` results in an in-memory LRU; `0` disables caching.
+    pub render_cache_entries: usize,
+    /// Renders allowed to run at once; further requests wait in a queue.
+    pub max_concurrent_renders: usize,
+    /// Requests allowed to wait for a render slot; beyond this they get 503.
+    pub max_queued_renders: usize,
+    /// Serve `GET /render/png?html=...`. Off by default: links are easy to share and abuse.
+    pub allow_get_render: bool,
+    /// Require `Authorization: Bearer <key>` with one of these keys on every route except
+    /// `/healthz` and `/readyz`; `None` leaves the server open.
+    pub api_keys: Option<HashSet<String>>,
+    /// Hosts `/render/async` may POST callbacks to, private addresses included; `None`
+    /// accepts any host that resolves only to public addresses.
+    pub callback_allowed_hosts: Option<HashSet<String>>,
+    /// Requests each API key (or client IP, without keys) may make per minute, with bursts
+    /// up to the same number; `0` disables rate limiting. `/healthz` and `/readyz` are never
+    /// limited.
+    pub requests_per_minute: u32,
+    /// Origins allowed to call the API from a browser (`*` for any); `None` sends no CORS
+    /// headers.
+    pub cors_allowed_origins: Option<Vec<String>>,
+    /// Gzip/deflate non-image responses for clients that send `Accept-Encoding`.
+    pub enable_compression: bool,
+    /// Escaping applied to template values; HTML by default.
+    pub template_auto_escape: AutoEscapeMode,
+    /// Fail every request whose template uses an undefined variable, as if it had set
+    /// `strict_variables`; off by default, so undefined values render empty.
+    pub template_undefined_strict: bool,
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
-            state: AppState { fonts_dir: None },
+            state: AppState::default(),
             limits: AppLimits::default(),
             max_body_size: DEFAULT_MAX_BODY_SIZE,
             server_base_url: None,
+            font_scan: FontScanConfig::default(),
+            admin_token: None,
+            render_cache_entries: 0,
+            max_concurrent_renders: default_max_concurrent_renders(),
+            max_queued_renders: DEFAULT_MAX_QUEUED_RENDERS,
+            allow_get_render: false,
+            api_keys: None,
+            callback_allowed_hosts: None,
+            requests_per_minute: 0,
+            cors_allowed_origins: None,
+            enable_compression: true,
+            template_auto_escape: AutoEscapeMode::Html,
+            template_undefined_strict: false,
         }
     }
 }
 
+/// One render slot per available CPU.
+#[must_use]
+pub fn default_max_concurrent_renders() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
 #[must_use]
 pub fn create_app(config: &AppConfig) -> impl Endpoint<Output = Response> + 'static {
     let config = config.clone();
-    let api = RenderApi::new(config.state.clone(), config.limits.clone());
+    let fonts_ready = Arc::new(AtomicBool::new(config.state.fonts_dir.is_none()));
+    let font_catalog = config
+        .state
+        .fonts_dir
+        .clone()
+        .map(|dir| spawn_font_scan(dir, &config.font_scan, &fonts_ready));
+    let maintenance = Arc::new(AtomicBool::new(false));
+    let render_cache = RenderCache::new(config.render_cache_entries).map(Arc::new);
+    let metrics_cache = render_cache.clone();
+    let api = RenderApi {
+        state: config.state.clone(),
+        limits: config.limits.clone(),
+        font_catalog,
+        maintenance: Arc::clone(&maintenance),
+        admin_token: config.admin_token.clone(),
+        render_cache,
+        allow_get_render: config.allow_get_render,
+        max_body_size: config.max_body_size,
+        pool: RenderPool::new(
+            config.max_concurrent_renders,
+            config.max_queued_renders,
+            config.limits.render_timeout,
+        ),
+        jobs: Arc::default(),
+        callback_allowed_hosts: config.callback_allowed_hosts.clone(),
+        template: TemplateBehavior {
+            auto_escape: config.template_auto_escape,
+            undefined_strict: config.template_undefined_strict,
+        },
+    };
     let mut api_service = OpenApiService::new(api, "HTML to Image API", "0.1.0");
     if let Some(server) = &config.server_base_url {
         api_service = api_service.server(server.clone());
@@ -102,85 +276,1209 @@ pub fn create_app(config: &AppConfig) -> impl Endpoint<Output = Response> + 'sta
     let swagger = api_service.swagger_ui();
     let spec = api_service.spec_endpoint();
     let spec_alias = api_service.spec_endpoint();
-    let api_with_limit = api_service.with(SizeLimit::new(config.max_body_size));
+    let api_with_limit = api_service.with(BodySizeLimit(config.max_body_size));
 
-    Route::new()
-        .at("/healthz", make_sync(|_| "ok"))
+    let routes = Route::new()
+        .at(
+            "/metrics",
+            make_sync(move |_| render_metrics(metrics_cache.as_deref())),
+        )
         .nest("/", api_with_limit)
         .nest("/swagger", swagger)
         .nest("/spec", spec)
-        .nest("/api/spec", spec_alias)
+        .nest("/api/spec", spec_alias);
+    let routes = routes.with_if(
+        config.requests_per_minute > 0,
+        RateLimit::new(config.requests_per_minute, config.api_keys.is_some()),
+    );
+    let routes = match &config.api_keys {
+        Some(keys) => routes
+            .with(ApiKeyAuth::new(keys, config.admin_token.as_deref()))
+            .boxed(),
+        None => routes.boxed(),
+    };
+
+    // Probes stay outside API key checks and rate limiting, so orchestrators never see 401/429.
+    let app = Route::new()
+        .at("/healthz", make_sync(|_| "ok"))
+        .at(
+            "/readyz",
+            make_sync(move |_| {
+                if maintenance.load(Ordering::Relaxed) {
+                    (StatusCode::SERVICE_UNAVAILABLE, "maintenance")
+                } else if !fonts_ready.load(Ordering::Acquire) {
+                    (StatusCode::SERVICE_UNAVAILABLE, "loading fonts")
+                } else {
+                    (StatusCode::OK, "ok")
+                }
+            }),
+        )
+        .nest("/", routes);
+    let app = match &config.cors_allowed_origins {
+        Some(origins) => app.with(cors(origins)).boxed(),
+        None => app.boxed(),
+    };
+
+    app.with_if(config.enable_compression, ResponseCompression)
         .with(Tracing)
+        .with(RequestId)
+}
+
+/// Scan the fonts directory on a background thread, so the server can answer probes while
+/// it runs; `ready` is set once the catalog is in place.
+fn spawn_font_scan(
+    dir: PathBuf,
+    scan_config: &FontScanConfig,
+    ready: &Arc<AtomicBool>,
+) -> Arc<OnceLock<FontCatalog>> {
+    let catalog = Arc::new(OnceLock::new());
+    let scan = {
+        let catalog = Arc::clone(&catalog);
+        let ready = Arc::clone(ready);
+        let dir = dir.clone();
+        let scan_config = scan_config.clone();
+        move || {
+            catalog.get_or_init(|| FontCatalog::new(dir, scan_config));
+            ready.store(true, Ordering::Release);
+        }
+    };
+    if let Err(err) = thread::Builder::new()
+        .name("font-scan".to_owned())
+        .spawn(scan)
+    {
+        error!(%err, "failed to spawn font scan thread, scanning inline");
+        catalog.get_or_init(|| FontCatalog::new(dir, scan_config.clone()));
+        ready.store(true, Ordering::Release);
+    }
+    catalog
+}
+
+/// CORS for browser clients on `origins`, or on any origin if the list contains `*`.
+///
+/// Applied outside authentication, since browsers send preflight `OPTIONS` requests
+/// without credentials.
+fn cors(origins: &[String]) -> Cors {
+    let cors = Cors::new()
+        .allow_methods([Method::GET, Method::POST])
+        .expose_header(RETRY_AFTER)
+        .max_age(CORS_MAX_AGE_SECS);
+    if origins.iter().any(|origin| origin == "*") {
+        return cors;
+    }
+    origins
+        .iter()
+        .filter_map(|origin| match HeaderValue::from_str(origin) {
+            Ok(origin) => Some(origin),
+            Err(err) => {
+                warn!(%origin, %err, "ignoring invalid CORS origin");
+                None
+            }
+        })
+        .fold(cors, Cors::allow_origin)
+}
+
+/// [`SizeLimit`] for requests that carry a body.
+///
+/// `GET` and `HEAD` requests skip the check, since clients send them without
+/// `Content-Length`. Its rejections are answered with the usual [`ApiError`] JSON body.
+struct BodySizeLimit(usize);
+
+impl<E: Endpoint> Middleware<E> for BodySizeLimit {
+    type Output = BodySizeLimitEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        let ep = Arc::new(ep);
+        BodySizeLimitEndpoint {
+            limited: SizeLimit::new(self.0).transform(Arc::clone(&ep)),
+            unlimited: ep,
+            max_size: self.0,
+        }
+    }
+}
+
+struct BodySizeLimitEndpoint<E> {
+    limited: SizeLimitEndpoint<Arc<E>>,
+    unlimited: Arc<E>,
+    max_size: usize,
+}
+
+impl<E: Endpoint> Endpoint for BodySizeLimitEndpoint<E> {
+    type Output = E::Output;
+
+    async fn call(&self, req: Request) -> poem::Result<Self::Output> {
+        if matches!(*req.method(), Method::GET | Method::HEAD) {
+            self.unlimited.call(req).await
+        } else {
+            self.limited.call(req).await.map_err(|err| {
+                match err.downcast_ref::<SizedLimitError>() {
+                    Some(SizedLimitError::PayloadTooLarge) => {
+                        ApiError::BodyTooLarge(self.max_size).into()
+                    }
+                    Some(SizedLimitError::MissingContentLength) => ApiError::LengthRequired.into(),
+                    None => err,
+                }
+            })
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 struct RenderApi {
     state: AppState,
     limits: AppLimits,
+    /// Filled in by the startup font scan; empty until `/readyz` reports ready.
+    font_catalog: Option<Arc<OnceLock<FontCatalog>>>,
+    /// While set, render endpoints answer 503 and `/readyz` reports not ready.
+    maintenance: Arc<AtomicBool>,
+    admin_token: Option<String>,
+    render_cache: Option<Arc<RenderCache>>,
+    allow_get_render: bool,
+    /// Also caps the `html` query parameter of `GET /render/png`.
+    max_body_size: usize,
+    pool: RenderPool,
+    jobs: Arc<JobStore>,
+    callback_allowed_hosts: Option<HashSet<String>>,
+    template: TemplateBehavior,
+}
+
+/// Server-wide template settings from [`AppConfig`].
+#[derive(Debug, Clone, Copy)]
+struct TemplateBehavior {
+    auto_escape: AutoEscapeMode,
+    undefined_strict: bool,
 }
 
 impl RenderApi {
-    fn new(state: AppState, limits: AppLimits) -> Self {
-        Self { state, limits }
+    /// Render a PNG, serving and filling the LRU cache when it is enabled.
+    async fn render_png_cached(
+        &self,
+        html: String,
+        opts: RenderOptions,
+    ) -> Result<RenderOutput, ApiError> {
+        let Some(cache) = &self.render_cache else {
+            return self.render_png_logged(html, opts).await;
+        };
+        let key = RenderKey::new(&html, &opts);
+        if let Some(output) = cache.get(&key) {
+            debug!(
+                bytes = output.bytes.len(),
+                "served png from the render cache"
+            );
+            return Ok(output);
+        }
+        let output = self.render_png_logged(html, opts).await?;
+        cache.insert(key, output.clone());
+        Ok(output)
+    }
+
+    /// The `/render/png` body for a `GET` or `HEAD` whose parameters came in the query.
+    fn query_render_request(
+        &self,
+        html: String,
+        (width, height): (u32, u32),
+        scale: f64,
+        animation_time: f64,
+    ) -> Result<RenderRequest, ApiError> {
+        if !self.allow_get_render {
+            return Err(ApiError::GetRenderDisabled);
+        }
+        self.ensure_available()?;
+        if html.len() > self.max_body_size {
+            return Err(ApiError::validation(format!(
+                "html must be at most {} bytes",
+                self.max_body_size
+            )));
+        }
+
+        Ok(RenderRequest {
+            html,
+            width,
+            height,
+            auto_height: false,
+            scale,
+            animation_time,
+            font_paths: None,
+            enable_emoji: false,
+            data: None,
+            debug_boxes: false,
+            thumbnail_width: None,
+            quality: DEFAULT_JPEG_QUALITY,
+            strict_variables: false,
+            collect_warnings: false,
+        })
+    }
+
+    /// The cached PNG response for a render, tagged with its `ETag`, or `304 Not Modified`
+    /// without rendering when `if_none_match` already names that tag.
+    async fn png_response(
+        &self,
+        html: String,
+        opts: RenderOptions,
+        if_none_match: Option<&str>,
+    ) -> ApiResult<RenderResponse> {
+        let etag = RenderKey::new(&html, &opts).etag();
+        if if_none_match.is_some_and(|tags| etag_matches(tags, &etag)) {
+            return Ok(RenderResponse::NotModified(etag));
+        }
+        let output = self.render_png_cached(html, opts).await?;
+
+        let warnings = warnings_header(&output.warnings);
+        Ok(RenderResponse::Png(
+            Binary(output.bytes),
+            Some(output.height),
+            warnings,
+            Some(etag),
+        ))
+    }
+
+    /// Render a PNG in a `render_png` span, logging its size, output bytes, and duration so
+    /// operators can see what renders cost.
+    async fn render_png_logged(
+        &self,
+        html: String,
+        opts: RenderOptions,
+    ) -> Result<RenderOutput, ApiError> {
+        let span = info_span!(
+            "render_png",
+            width = opts.width,
+            height = opts.height,
+            scale = opts.scale
+        );
+        async move {
+            let started = Instant::now();
+            let result = self.pool.render_png_output(html, opts).await;
+            let duration_ms = started.elapsed().as_millis();
+            match &result {
+                Ok(output) => info!(
+                    width = output.width,
+                    height = output.height,
+                    bytes = output.bytes.len(),
+                    duration_ms,
+                    "rendered png"
+                ),
+                Err(err) => debug!(%err, duration_ms, "png render failed"),
+            }
+            result
+        }
+        .instrument(span)
+        .await
+    }
+
+    fn ensure_available(&self) -> Result<(), ApiError> {
+        if self.maintenance.load(Ordering::Relaxed) {
+            return Err(ApiError::Maintenance);
+        }
+        Ok(())
+    }
+
+    /// Validate `req`, expand its template, and build the render options shared by the
+    /// image endpoints.
+    fn prepare_render(&self, req: &RenderRequest) -> Result<(String, RenderOptions), ApiError> {
+        let opts = self.render_options(req)?;
+        let html = self.expand_html(req).map_err(ApiError::from)?;
+        Ok((html, opts))
+    }
+
+    /// Validate `req` and build its render options, resolving the requested fonts.
+    fn render_options(&self, req: &RenderRequest) -> Result<RenderOptions, ApiError> {
+        validate_request(req, &self.limits)?;
+
+        let font_paths =
+            resolve_requested_fonts(&self.state, &self.limits, req.font_paths.as_deref())?;
+        Ok(
+            RenderOptions::new(req.width, req.fixed_height().unwrap_or(0))
+                .max_height(self.limits.max_dimension)
+                .scale(req.scale)
+                .animation_time(req.animation_time)
+                .default_font(self.state.default_font.clone())
+                .font_paths(font_paths)
+                .enable_emoji(req.enable_emoji)
+                .debug_boxes(req.debug_boxes)
+                .collect_warnings(req.collect_warnings),
+        )
+    }
+
+    /// Expand `req`'s template under the server's template settings and limits.
+    fn expand_html(&self, req: &RenderRequest) -> Result<String, RenderError> {
+        let html = expand_template(req, self.template, self.limits.max_rendered_html)?;
+        if self.limits.reject_empty_html {
+            ensure_html_not_empty(&html)?;
+        }
+        Ok(html)
+    }
+
+    fn authorize_admin(&self, auth: &AdminAuth) -> Result<(), ApiError> {
+        match &self.admin_token {
+            Some(token) if constant_time_eq(token.as_bytes(), auth.0.token.as_bytes()) => Ok(()),
+            _ => Err(ApiError::Unauthorized),
+        }
     }
 }
 
+/// Bearer token guarding the `/admin` endpoints.
+#[derive(SecurityScheme)]
+#[oai(ty = "bearer")]
+struct AdminAuth(Bearer);
+
 #[OpenApi]
 impl RenderApi {
     /// Render HTML (as a `MiniJinja` template) to PNG bytes.
+    ///
+    /// With `?encoding=base64`, or an `Accept` that prefers `application/json`, the PNG is
+    /// returned base64-encoded in a JSON object together with its dimensions. With
+    /// `height: 0` or `auto_height`, the height is measured from the content and sent back
+    /// in `X-Image-Height`.
+    ///
+    /// PNG bodies carry an `ETag` derived from the render's cache key; repeating the request
+    /// with that tag in `If-None-Match` answers `304 Not Modified` without rendering.
     #[oai(path = "/render/png", method = "post")]
-    async fn render_png(&self, req: OpenApiJson<RenderRequest>) -> ApiResult<RenderResponse> {
-        validate_request(&req.0, &self.limits)?;
+    async fn render_png(
+        &self,
+        encoding: Query<Option<ResponseEncoding>>,
+        #[oai(name = "Accept")] accept: Header<Option<String>>,
+        #[oai(name = "If-None-Match")] if_none_match: Header<Option<String>>,
+        req: OpenApiJson<RenderRequest>,
+    ) -> ApiResult<RenderResponse> {
+        self.ensure_available()?;
+        let (html, opts) = self.prepare_render(&req.0)?;
 
-        let font_paths = resolve_requested_fonts(&self.state, req.0.font_paths.as_deref())?;
-        let context = build_context(&req.0);
-        let html = render_template(&req.html, &context).map_err(ApiError::from)?;
+        let as_json = match encoding.0 {
+            Some(encoding) => encoding == ResponseEncoding::Base64,
+            None => prefers_json(accept.0.as_deref()),
+        };
+        if as_json {
+            let output = self.render_png_cached(html, opts).await?;
+            return Ok(RenderResponse::Json(OpenApiJson(EncodedRender::from(
+                output,
+            ))));
+        }
+        self.png_response(html, opts, if_none_match.0.as_deref())
+            .await
+    }
+
+    /// Render HTML passed in the query string to PNG, for pasting links into chat apps.
+    ///
+    /// Only served when `allow_get_render` is enabled. The URL-encoded `html` is held to the
+    /// same `max_body_size` as POST bodies, but clients and proxies usually cap URLs at a
+    /// few KiB first, so keep these templates small.
+    #[oai(path = "/render/png", method = "get")]
+    async fn render_png_get(
+        &self,
+        #[oai(name = "html")] template: Query<String>,
+        width: Query<u32>,
+        height: Query<u32>,
+        #[oai(default = "default_scale")] scale: Query<f64>,
+        #[oai(default = "default_animation_time")] animation_time: Query<f64>,
+        #[oai(name = "If-None-Match")] if_none_match: Header<Option<String>>,
+    ) -> ApiResult<RenderResponse> {
+        let req =
+            self.query_render_request(template.0, (width.0, height.0), scale.0, animation_time.0)?;
+        let (html, opts) = self.prepare_render(&req)?;
+        self.png_response(html, opts, if_none_match.0.as_deref())
+            .await
+    }
+
+    /// Render like `GET /render/png`, but answer with the PNG's metadata headers only.
+    ///
+    /// The response carries `Content-Length` (the PNG's size in bytes), `X-Image-Width`,
+    /// `X-Image-Height`, and the `ETag` the GET would send, with no body. The render goes
+    /// through the render cache, so a HEAD warms it for the matching GET. Only served when
+    /// `allow_get_render` is enabled.
+    #[oai(path = "/render/png", method = "head")]
+    async fn render_png_head(
+        &self,
+        #[oai(name = "html")] template: Query<String>,
+        width: Query<u32>,
+        height: Query<u32>,
+        #[oai(default = "default_scale")] scale: Query<f64>,
+        #[oai(default = "default_animation_time")] animation_time: Query<f64>,
+    ) -> ApiResult<RenderHeadResponse> {
+        let req =
+            self.query_render_request(template.0, (width.0, height.0), scale.0, animation_time.0)?;
+        let (html, opts) = self.prepare_render(&req)?;
+        let etag = RenderKey::new(&html, &opts).etag();
+        let output = self.render_png_cached(html, opts).await?;
+
+        Ok(RenderHeadResponse::Png(
+            output.bytes.len(),
+            output.width,
+            output.height,
+            etag,
+        ))
+    }
+
+    /// Render HTML (as a `MiniJinja` template) to PNG with fonts uploaded alongside it.
+    ///
+    /// The `request` part carries the `/render/png` JSON body; each `fonts` part is a TTF,
+    /// OTF, or TTC file registered for this render only.
+    #[oai(path = "/render/png/multipart", method = "post")]
+    async fn render_png_multipart(
+        &self,
+        form: RenderMultipartRequest,
+    ) -> ApiResult<RenderResponse> {
+        self.ensure_available()?;
+        let fonts = read_font_uploads(form.fonts, &self.limits).await?;
+        let (html, opts) = self.prepare_render(&form.request.0)?;
+        let png_bytes = self
+            .pool
+            .run((opts.width, opts.height), move || {
+                render_html_bytes_with_fonts(&html, &opts, &fonts)
+            })
+            .await?;
+
+        Ok(RenderResponse::Png(Binary(png_bytes), None, None, None))
+    }
 
-        let width = req.width;
-        let height = req.height;
-        let scale = req.scale;
-        let animation_time = req.animation_time;
+    /// Queue a PNG render and answer 202 with its job at once.
+    ///
+    /// When the render finishes, the job (with the base64 PNG on success) is sent to
+    /// `callback_url` as a JSON POST; `GET /render/async/{id}` reports progress meanwhile.
+    #[oai(path = "/render/async", method = "post")]
+    async fn render_async(
+        &self,
+        req: OpenApiJson<AsyncRenderRequest>,
+    ) -> ApiResult<AsyncRenderResponse> {
+        self.ensure_available()?;
+        let callback =
+            resolve_callback_url(&req.callback_url, self.callback_allowed_hosts.as_ref()).await?;
+        let (html, opts) = self.prepare_render(&req.0.render)?;
 
-        let png_bytes = task::spawn_blocking(move || {
-            render_html_to_png_bytes(&html, width, height, scale, animation_time, &font_paths)
+        let job = self.jobs.insert(random_uuid());
+        let runner = JobRunner {
+            jobs: Arc::clone(&self.jobs),
+            pool: self.pool.clone(),
+        };
+        task::spawn(runner.run(job.id.clone(), html, opts, callback));
+
+        Ok(AsyncRenderResponse::Accepted(OpenApiJson(job)))
+    }
+
+    /// Report the status of a `/render/async` job.
+    #[oai(path = "/render/async/:id", method = "get")]
+    #[allow(
+        clippy::unused_async,
+        reason = "poem-openapi operations must be async fns."
+    )]
+    async fn render_async_status(
+        &self,
+        id: param::Path<String>,
+    ) -> ApiResult<OpenApiJson<RenderJob>> {
+        let job = self
+            .jobs
+            .get(&id.0)
+            .ok_or_else(|| ApiError::JobNotFound(id.0.clone()))?;
+        Ok(OpenApiJson(job))
+    }
+
+    /// Render HTML (as a `MiniJinja` template) in the image format picked from `Accept`.
+    ///
+    /// Supports `image/png`, `image/jpeg` (using `quality`), and `image/webp`; `*/*`,
+    /// `image/*`, or no header yields PNG. Anything else is answered with 406.
+    #[oai(path = "/render", method = "post")]
+    async fn render_negotiated(
+        &self,
+        #[oai(name = "Accept")] accept: Header<Option<String>>,
+        req: OpenApiJson<RenderRequest>,
+    ) -> ApiResult<RenderResponse> {
+        self.ensure_available()?;
+        let format = negotiate_format(accept.0.as_deref(), req.quality)?;
+        if let OutputFormat::Jpeg { quality } = format {
+            validate_jpeg_quality(quality, &self.limits)?;
+        }
+        let (html, opts) = self.prepare_render(&req.0)?;
+        let bytes = self
+            .pool
+            .run((opts.width, opts.height), move || {
+                render_html(&html, &opts, format)
+            })
+            .await?;
+
+        Ok(match format {
+            OutputFormat::Jpeg { .. } => RenderResponse::Jpeg(Binary(bytes)),
+            OutputFormat::Webp { .. } => RenderResponse::Webp(Binary(bytes)),
+            _ => RenderResponse::Png(Binary(bytes), None, None, None),
         })
-        .await
-        .map_err(|err| {
-            error!(%err, "render task join error");
-            ApiError::internal("render task failed")
-        })?
-        .map_err(ApiError::from)?;
+    }
+
+    /// Render HTML (as a `MiniJinja` template) to JPEG bytes.
+    ///
+    /// JPEG has no alpha channel: give the page an opaque background, or transparent areas
+    /// come out black. `quality` (1..=100, or the server's configured range) defaults to 85.
+    #[oai(path = "/render/jpeg", method = "post")]
+    async fn render_jpeg(&self, req: OpenApiJson<RenderRequest>) -> ApiResult<RenderResponse> {
+        self.ensure_available()?;
+        validate_jpeg_quality(req.quality, &self.limits)?;
+        let (html, opts) = self.prepare_render(&req.0)?;
+        let format = OutputFormat::Jpeg {
+            quality: req.quality,
+        };
+        let jpeg_bytes = self
+            .pool
+            .run((opts.width, opts.height), move || {
+                render_html(&html, &opts, format)
+            })
+            .await?;
+
+        Ok(RenderResponse::Jpeg(Binary(jpeg_bytes)))
+    }
+
+    /// Expand the `MiniJinja` template and return the HTML that would be painted.
+    ///
+    /// Uses the same context as `/render/png`, which makes template bugs easy to inspect.
+    #[oai(path = "/render/html", method = "post")]
+    async fn render_html(&self, req: OpenApiJson<RenderRequest>) -> ApiResult<Html<String>> {
+        validate_request(&req.0, &self.limits)?;
+
+        let (template, max_rendered_html) = (self.template, self.limits.max_rendered_html);
+        let html =
+            task::spawn_blocking(move || expand_template(&req.0, template, max_rendered_html))
+                .await
+                .map_err(|err| {
+                    error!(%err, "template task join error");
+                    ApiError::internal("template render failed")
+                })?
+                .map_err(ApiError::from)?;
+        Ok(Html(html))
+    }
+
+    /// Check a template and its data without rendering them.
+    ///
+    /// Takes the `/render/png` body and expands the template; with `?layout=true` the page
+    /// is also parsed and laid out to report resources it failed to load, still without
+    /// painting or encoding. Template errors answer 200 with `valid: false` and their
+    /// location; an invalid request (dimensions, fonts, oversized HTML) is rejected as it
+    /// would be by `/render/png`.
+    #[oai(path = "/render/validate", method = "post")]
+    async fn validate_render(
+        &self,
+        #[oai(default)] layout: Query<bool>,
+        req: OpenApiJson<RenderRequest>,
+    ) -> ApiResult<OpenApiJson<ValidateResponse>> {
+        self.ensure_available()?;
+        let opts = self.render_options(&req.0)?;
+        let html = match self.expand_html(&req.0) {
+            Ok(html) => html,
+            Err(err) => {
+                let template_error =
+                    TemplateError::from_render_error(&err).ok_or_else(|| ApiError::from(err))?;
+                return Ok(OpenApiJson(ValidateResponse {
+                    valid: false,
+                    template_error: Some(template_error),
+                    warnings: Vec::new(),
+                }));
+            }
+        };
+
+        let warnings = if layout.0 {
+            let opts = opts.collect_warnings(true);
+            self.pool
+                .run((opts.width, opts.height), move || layout_html(&html, &opts))
+                .await?
+        } else {
+            Vec::new()
+        };
+        Ok(OpenApiJson(ValidateResponse {
+            valid: true,
+            template_error: None,
+            warnings,
+        }))
+    }
+
+    /// Render the built-in demo card to PNG bytes.
+    ///
+    /// Icon and message are picked at random unless a `seed` is given.
+    #[oai(path = "/render/card", method = "post")]
+    async fn render_card(&self, req: OpenApiJson<CardRequest>) -> ApiResult<RenderResponse> {
+        self.ensure_available()?;
+        validate_render_params(
+            req.width,
+            Some(req.height),
+            req.scale,
+            req.animation_time,
+            &self.limits,
+        )?;
+
+        let font_paths =
+            resolve_requested_fonts(&self.state, &self.limits, req.0.font_paths.as_deref())?;
+        let mut card = generate_card(req.seed, &req.name);
+        card.width = req.width;
+        card.height = req.height;
+        let html = render_template(CARD_TEMPLATE, &card).map_err(ApiError::from)?;
+
+        let opts = RenderOptions::new(req.width, req.height)
+            .scale(req.scale)
+            .animation_time(req.animation_time)
+            .default_font(self.state.default_font.clone())
+            .font_paths(font_paths);
+        let png_bytes = self.pool.render_png(html, opts).await?;
+
+        Ok(RenderResponse::Png(Binary(png_bytes), None, None, None))
+    }
+
+    /// Render once and return a `multipart/mixed` body with `thumbnail.png` and `full.png`.
+    ///
+    /// The thumbnail is downscaled from the same render (`thumbnail_width`, aspect ratio kept).
+    #[oai(path = "/render/png/thumbnail", method = "post")]
+    async fn render_png_with_thumbnail(
+        &self,
+        req: OpenApiJson<RenderRequest>,
+    ) -> ApiResult<ThumbnailResponse> {
+        self.ensure_available()?;
+        let (html, opts) = self.prepare_render(&req)?;
+        let thumbnail_width = req.thumbnail_width.unwrap_or(DEFAULT_THUMBNAIL_WIDTH);
+        if thumbnail_width == 0 {
+            return Err(ApiError::validation("thumbnail_width must be at least 1").into());
+        }
+        let thumbnail_width = thumbnail_width.min(req.width);
+
+        let rendered = self
+            .pool
+            .run((opts.width, opts.height), move || {
+                render_html_to_png_with_thumbnail(&html, &opts, thumbnail_width)
+            })
+            .await?;
+
+        let body = multipart_png_body(&[
+            ("thumbnail.png", &rendered.thumbnail),
+            ("full.png", &rendered.full),
+        ])?;
+        Ok(ThumbnailResponse::Multipart(Binary(body)))
+    }
+
+    /// Render up to `max_batch` PNGs in one request, returned base64-encoded in item order.
+    ///
+    /// Each item is validated and rendered independently, so one bad item only fails its own
+    /// entry. Items share the server's render pool with every other request: a batch keeps
+    /// at most `max_concurrent_renders` of its items in the pool at once, so even a batch
+    /// larger than the queue fits on an idle server, and an item that finds the queue full
+    /// of other requests fails with the overloaded error.
+    #[oai(path = "/render/batch", method = "post")]
+    async fn render_batch(
+        &self,
+        req: OpenApiJson<BatchRequest>,
+    ) -> ApiResult<OpenApiJson<BatchResponse>> {
+        self.ensure_available()?;
+        let count = req.items.len();
+        if count == 0 || count > self.limits.max_batch {
+            return Err(ApiError::validation(format!(
+                "items must contain between 1 and {} requests",
+                self.limits.max_batch
+            ))
+            .into());
+        }
+
+        let in_flight = self.pool.max_concurrent();
+        let mut renders = JoinSet::new();
+        let mut results = Vec::with_capacity(count);
+        for (index, item) in req.0.items.into_iter().enumerate() {
+            let (html, opts) = match self.prepare_render(&item) {
+                Ok(prepared) => prepared,
+                Err(err) => {
+                    results.push(BatchItemResult::failed(index, &err));
+                    continue;
+                }
+            };
+            if renders.len() >= in_flight
+                && let Some(joined) = renders.join_next().await
+            {
+                results.push(batch_item_result(joined)?);
+            }
+            let pool = self.pool.clone();
+            renders.spawn(async move { (index, pool.render_png(html, opts).await) });
+        }
+
+        while let Some(joined) = renders.join_next().await {
+            results.push(batch_item_result(joined)?);
+        }
+        results.sort_by_key(|result| result.index);
+
+        Ok(OpenApiJson(BatchResponse { results }))
+    }
+
+    /// List font files available in the configured fonts directory, or the manifest's
+    /// aliases when one is configured.
+    #[oai(path = "/fonts", method = "get")]
+    async fn list_fonts(&self) -> ApiResult<OpenApiJson<FontScan>> {
+        if let Some(manifest) = &self.state.font_manifest {
+            return Ok(OpenApiJson(FontScan {
+                fonts: manifest.keys().cloned().collect(),
+                truncated: false,
+            }));
+        }
+        let Some(catalog) = self.font_catalog.clone() else {
+            return Ok(OpenApiJson(FontScan::default()));
+        };
+        if catalog.get().is_none() {
+            return Err(ApiError::FontsLoading.into());
+        }
+
+        // A due refresh walks the directory, so keep it off the async runtime.
+        let scan =
+            task::spawn_blocking(move || catalog.get().map(FontCatalog::fonts).unwrap_or_default())
+                .await
+                .map_err(|err| {
+                    error!(%err, "font scan task join error");
+                    ApiError::internal("font scan failed")
+                })?;
+        Ok(OpenApiJson(scan))
+    }
 
-        Ok(RenderResponse::Png(Binary(png_bytes)))
+    /// Toggle maintenance mode: renders are rejected with 503 and `/readyz` turns red,
+    /// while `/healthz` stays green so orchestrators drain rather than restart.
+    #[oai(path = "/admin/maintenance", method = "post")]
+    #[allow(
+        clippy::unused_async,
+        reason = "poem-openapi operations must be async fns."
+    )]
+    async fn set_maintenance(
+        &self,
+        auth: AdminAuth,
+        req: OpenApiJson<MaintenanceMode>,
+    ) -> ApiResult<OpenApiJson<MaintenanceMode>> {
+        self.authorize_admin(&auth)?;
+        self.maintenance.store(req.enabled, Ordering::Relaxed);
+        info!(enabled = req.enabled, "maintenance mode updated");
+        Ok(OpenApiJson(MaintenanceMode {
+            enabled: req.enabled,
+        }))
     }
 }
 
+/// Prometheus text exposition of the render cache counters (all zero when disabled).
+fn render_metrics(cache: Option<&RenderCache>) -> String {
+    let (hits, misses, entries) = cache.map_or((0, 0, 0), |cache| {
+        (cache.hits(), cache.misses(), cache.len())
+    });
+    format!(
+        "# TYPE html_to_image_render_cache_hits_total counter\n\
+         html_to_image_render_cache_hits_total {hits}\n\
+         # TYPE html_to_image_render_cache_misses_total counter\n\
+         html_to_image_render_cache_misses_total {misses}\n\
+         # TYPE html_to_image_render_cache_entries gauge\n\
+         html_to_image_render_cache_entries {entries}\n"
+    )
+}
+
+/// Compare secrets without short-circuiting on the first mismatching byte.
+fn constant_time_eq(expected: &[u8], actual: &[u8]) -> bool {
+    expected.len() == actual.len()
+        && expected
+            .iter()
+            .zip(actual)
+            .fold(0_u8, |diff, (left, right)| diff | (left ^ right))
+            == 0
+}
+
+/// Assemble a `multipart/mixed` body from `(filename, png bytes)` parts.
+fn multipart_png_body(parts: &[(&str, &[u8])]) -> Result<Vec<u8>, ApiError> {
+    let delimiter = format!("--{MULTIPART_BOUNDARY}");
+    let mut body = Vec::new();
+    for (filename, bytes) in parts {
+        if bytes
+            .windows(delimiter.len())
+            .any(|window| window == delimiter.as_bytes())
+        {
+            return Err(ApiError::internal(
+                "multipart boundary collides with image data",
+            ));
+        }
+        body.extend_from_slice(delimiter.as_bytes());
+        body.extend_from_slice(
+            format!(
+                "\r\nContent-Type: image/png\r\nContent-Disposition: attachment; filename=\"{filename}\"\r\nContent-Length: {}\r\n\r\n",
+                bytes.len()
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(bytes);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("{delimiter}--\r\n").as_bytes());
+    Ok(body)
+}
+
+/// Body of `/render/async`: a `/render/png` request plus where to deliver the result.
+#[derive(Object, Debug)]
+pub struct AsyncRenderRequest {
+    #[oai(flatten)]
+    pub render: RenderRequest,
+    /// `http`/`https` URL the finished job is posted to.
+    pub callback_url: String,
+}
+
+#[derive(ApiResponse)]
+pub enum AsyncRenderResponse {
+    /// The job was queued.
+    #[oai(status = 202)]
+    Accepted(OpenApiJson<RenderJob>),
+}
+
+/// Multipart body of `/render/png/multipart`.
+#[derive(Multipart)]
+pub struct RenderMultipartRequest {
+    /// The `/render/png` JSON body.
+    pub request: JsonField<RenderRequest>,
+    /// Font files (TTF, OTF, or TTC) to register for this render.
+    pub fonts: Vec<Upload>,
+}
+
 #[derive(Object, Debug, Deserialize)]
+#[allow(
+    clippy::struct_excessive_bools,
+    reason = "Each bool is an independent, optional request field"
+)]
 pub struct RenderRequest {
     /// HTML content that may contain `MiniJinja` placeholders.
     pub html: String,
     /// Output width in pixels (1..=4096 by default).
     pub width: u32,
-    /// Output height in pixels (1..=4096 by default).
+    /// Output height in pixels (1..=4096 by default); `0` measures it from the content.
     pub height: u32,
+    /// Measure the height from the content and ignore `height`.
+    #[oai(default)]
+    pub auto_height: bool,
     /// Scale factor applied during painting.
     #[oai(default = "default_scale")]
     pub scale: f64,
     /// Virtual animation time passed into the renderer.
     #[oai(default = "default_animation_time")]
     pub animation_time: f64,
-    /// Optional font file names resolved against the configured fonts directory.
+    /// Optional font files (e.g. `brand/Inter.ttf`) relative to the configured fonts directory,
+    /// or font aliases when the server has a font manifest.
     #[oai(default)]
     pub font_paths: Option<Vec<String>>,
+    /// Fall back to the bundled (monochrome) Noto Emoji font for emoji the requested fonts
+    /// lack; a server built without its default `emoji` feature answers 400.
+    #[oai(default)]
+    pub enable_emoji: bool,
     /// Arbitrary template variables (free-form JSON).
     #[oai(default)]
     pub data: Option<Any<Value>>,
+    /// Overlay each element's layout box and tag name for template debugging.
+    #[oai(default)]
+    pub debug_boxes: bool,
+    /// Thumbnail width for `/render/png/thumbnail` (defaults to 160, clamped to `width`).
+    #[oai(default)]
+    pub thumbnail_width: Option<u32>,
+    /// JPEG quality for `/render/jpeg` (1..=100 unless the server narrows it).
+    #[oai(default = "default_jpeg_quality")]
+    pub quality: u8,
+    /// Fail with 400 when the template uses a variable that is not defined, instead of
+    /// rendering it as an empty string.
+    #[oai(default)]
+    pub strict_variables: bool,
+    /// Report resources the page referenced but that were not loaded, in the
+    /// `X-Render-Warnings` header (or `warnings` of a JSON response).
+    #[oai(default)]
+    pub collect_warnings: bool,
 }
 
-#[derive(ApiResponse)]
+impl RenderRequest {
+    /// The requested height, or `None` when it is measured from the content.
+    fn fixed_height(&self) -> Option<u32> {
+        (!self.auto_height && self.height != 0).then_some(self.height)
+    }
+}
+
+#[derive(Object, Debug, Deserialize)]
+pub struct BatchRequest {
+    /// Render requests, each shaped like a `/render/png` body.
+    pub items: Vec<RenderRequest>,
+}
+
+#[derive(Object, Debug, Serialize)]
+pub struct BatchResponse {
+    /// One entry per request item, in the same order.
+    pub results: Vec<BatchItemResult>,
+}
+
+#[derive(Object, Debug, Serialize)]
+pub struct BatchItemResult {
+    /// Position of the item in the request.
+    pub index: usize,
+    /// HTTP status the item would have received on its own.
+    pub status: u16,
+    /// Base64-encoded PNG, present on success.
+    pub png_base64: Option<String>,
+    /// Error message, present on failure.
+    pub error: Option<String>,
+}
+
+impl BatchItemResult {
+    fn succeeded(index: usize, png: &[u8]) -> Self {
+        Self {
+            index,
+            status: StatusCode::OK.as_u16(),
+            png_base64: Some(BASE64.encode(png)),
+            error: None,
+        }
+    }
+
+    fn failed(index: usize, error: &ApiError) -> Self {
+        Self {
+            index,
+            status: error.status().as_u16(),
+            png_base64: None,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+#[derive(Object, Debug, Serialize)]
+pub struct ValidateResponse {
+    /// `false` when the template failed to parse or render.
+    pub valid: bool,
+    /// What went wrong in the template, present when `valid` is false.
+    pub template_error: Option<TemplateError>,
+    /// Resources the page failed to load; only filled with `?layout=true`.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Object, Debug, Serialize)]
+pub struct TemplateError {
+    /// Full error message, including the location.
+    pub message: String,
+    /// `MiniJinja`'s description of the failure, e.g. `undefined value`.
+    pub reason: String,
+    /// 1-based line of the failing template code, when known.
+    pub line: Option<usize>,
+    /// Name of the template the line refers to, when known.
+    pub template_name: Option<String>,
+}
+
+impl TemplateError {
+    /// The template failure behind `error`, or `None` for other render errors.
+    fn from_render_error(error: &RenderError) -> Option<Self> {
+        let (RenderError::RegisterTemplate {
+            source,
+            line,
+            template_name,
+        }
+        | RenderError::RenderTemplate {
+            source,
+            line,
+            template_name,
+        }) = error
+        else {
+            return None;
+        };
+        let reason = match source.detail() {
+            Some(detail) => format!("{}: {detail}", source.kind()),
+            None => source.kind().to_string(),
+        };
+        Some(Self {
+            message: error.to_string(),
+            reason,
+            line: *line,
+            template_name: template_name.clone(),
+        })
+    }
+}
+
+#[derive(Object, Debug, Deserialize)]
+pub struct CardRequest {
+    /// Name rendered into the greeting.
+    #[oai(default = "default_card_name")]
+    pub name: String,
+    /// Seed for deterministic icon/message selection.
+    #[oai(default)]
+    pub seed: Option<u64>,
+    /// Output width in pixels.
+    #[oai(default = "default_card_width")]
+    pub width: u32,
+    /// Output height in pixels.
+    #[oai(default = "default_card_height")]
+    pub height: u32,
+    /// Scale factor applied during painting.
+    #[oai(default = "default_scale")]
+    pub scale: f64,
+    /// Virtual animation time passed into the renderer.
+    #[oai(default = "default_animation_time")]
+    pub animation_time: f64,
+    /// Optional font files (e.g. `brand/Inter.ttf`) relative to the configured fonts directory,
+    /// or font aliases when the server has a font manifest.
+    #[oai(default)]
+    pub font_paths: Option<Vec<String>>,
+}
+
+#[derive(Object, Debug, Deserialize, Serialize)]
+pub struct MaintenanceMode {
+    /// `true` rejects new renders with 503 until switched off again.
+    pub enabled: bool,
+}
+
+/// An image, or its base64 JSON envelope, from one of the render endpoints.
 pub enum RenderResponse {
+    /// PNG bytes, its height, any render warnings, and its `ETag`.
+    Png(Binary<Vec<u8>>, Option<u32>, Option<String>, Option<String>),
+    Jpeg(Binary<Vec<u8>>),
+    Webp(Binary<Vec<u8>>),
+    Json(OpenApiJson<EncodedRender>),
+    /// The client's copy, named by this `ETag`, is current.
+    NotModified(String),
+}
+
+/// [`RenderResponse`] as poem-openapi derives it. Each variant becomes its own `200`
+/// entry, which serializes as duplicate keys that generated clients collapse to the last
+/// one (the JSON envelope), so [`RenderResponse`] merges them into a single response.
+#[derive(ApiResponse)]
+enum RenderVariants {
     #[oai(status = 200, content_type = "image/png")]
-    Png(Binary<Vec<u8>>),
+    Png(
+        Binary<Vec<u8>>,
+        /// Height of the PNG in pixels, useful when it was measured from the content.
+        #[oai(header = "X-Image-Height")]
+        Option<u32>,
+        /// `; `-separated render diagnostics, sent when `collect_warnings` found any.
+        #[oai(header = "X-Render-Warnings")]
+        Option<String>,
+        /// Tag of this render; send it back in `If-None-Match` to skip unchanged renders.
+        #[oai(header = "ETag")]
+        Option<String>,
+    ),
+    #[oai(status = 200, content_type = "image/jpeg")]
+    Jpeg(Binary<Vec<u8>>),
+    #[oai(status = 200, content_type = "image/webp")]
+    Webp(Binary<Vec<u8>>),
+    #[oai(status = 200)]
+    Json(OpenApiJson<EncodedRender>),
+    /// The PNG named in `If-None-Match` is still current.
+    #[oai(status = 304)]
+    NotModified(#[oai(header = "ETag")] String),
+}
+
+impl IntoResponse for RenderResponse {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Png(body, height, warnings, etag) => {
+                RenderVariants::Png(body, height, warnings, etag)
+            }
+            Self::Jpeg(body) => RenderVariants::Jpeg(body),
+            Self::Webp(body) => RenderVariants::Webp(body),
+            Self::Json(body) => RenderVariants::Json(body),
+            Self::NotModified(etag) => RenderVariants::NotModified(etag),
+        }
+        .into_response()
+    }
+}
+
+impl ApiResponse for RenderResponse {
+    /// One `200` response listing every content type, with binary image bodies
+    /// (`type: string, format: binary`) next to the JSON envelope.
+    fn meta() -> MetaResponses {
+        let mut responses: Vec<MetaResponse> = Vec::new();
+        for response in RenderVariants::meta().responses {
+            match responses
+                .iter_mut()
+                .find(|merged| merged.status == response.status)
+            {
+                Some(merged) => {
+                    merged.content.extend(response.content);
+                    for header in response.headers {
+                        if !merged.headers.iter().any(|known| known.name == header.name) {
+                            merged.headers.push(header);
+                        }
+                    }
+                }
+                None => responses.push(response),
+            }
+        }
+        MetaResponses { responses }
+    }
+
+    fn register(registry: &mut Registry) {
+        RenderVariants::register(registry);
+    }
+}
+
+/// How `POST /render/png` returns the image.
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+#[oai(rename_all = "lowercase")]
+pub enum ResponseEncoding {
+    /// Raw `image/png` body.
+    Binary,
+    /// JSON object with the base64-encoded PNG and its dimensions.
+    Base64,
+}
+
+/// A render returned as JSON for clients that cannot handle binary bodies.
+#[derive(Object, Debug, Serialize)]
+pub struct EncodedRender {
+    /// Base64-encoded image.
+    pub image: String,
+    /// Rendered width in pixels.
+    pub width: u32,
+    /// Rendered height in pixels.
+    pub height: u32,
+    /// Size of the decoded image in bytes.
+    pub bytes: usize,
+    /// Render diagnostics, present when `collect_warnings` found any.
+    #[oai(skip_serializing_if_is_empty)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl From<RenderOutput> for EncodedRender {
+    fn from(output: RenderOutput) -> Self {
+        Self {
+            image: BASE64.encode(&output.bytes),
+            width: output.width,
+            height: output.height,
+            bytes: output.bytes.len(),
+            warnings: output.warnings,
+        }
+    }
+}
+
+/// Join render warnings into one header value, escaping anything that is not printable
+/// ASCII; `None` when there are none.
+fn warnings_header(warnings: &[String]) -> Option<String> {
+    (!warnings.is_empty()).then(|| {
+        warnings
+            .iter()
+            .map(|warning| warning.escape_default().to_string())
+            .collect::<Vec<_>>()
+            .join("; ")
+    })
+}
+
+/// Whether an `If-None-Match` list is `*` or names `etag`, ignoring `W/` prefixes as the
+/// header's weak comparison requires.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag)
+}
+
+#[derive(ApiResponse)]
+pub enum RenderHeadResponse {
+    /// The headers `GET /render/png` would send, without the PNG.
+    #[oai(status = 200)]
+    Png(
+        /// Size of the PNG in bytes.
+        #[oai(header = "Content-Length")]
+        usize,
+        /// Width of the PNG in pixels.
+        #[oai(header = "X-Image-Width")]
+        u32,
+        /// Height of the PNG in pixels.
+        #[oai(header = "X-Image-Height")]
+        u32,
+        /// Tag of this render, as sent with the PNG.
+        #[oai(header = "ETag")]
+        String,
+    ),
+}
+
+#[derive(ApiResponse)]
+pub enum ThumbnailResponse {
+    /// `thumbnail.png` followed by `full.png`.
+    #[oai(
+        status = 200,
+        content_type = "multipart/mixed; boundary=html-to-image-part-4f1c9a7e2d"
+    )]
+    Multipart(Binary<Vec<u8>>),
 }
 
 fn default_scale() -> f64 {
@@ -191,28 +1489,62 @@ fn default_animation_time() -> f64 {
     DEFAULT_ANIMATION_TIME
 }
 
+fn default_jpeg_quality() -> u8 {
+    DEFAULT_JPEG_QUALITY
+}
+
+fn default_card_name() -> String {
+    "User".to_owned()
+}
+
+fn default_card_width() -> u32 {
+    DEFAULT_CARD_WIDTH
+}
+
+fn default_card_height() -> u32 {
+    DEFAULT_CARD_HEIGHT
+}
+
 fn validate_request(req: &RenderRequest, limits: &AppLimits) -> Result<(), ApiError> {
-    if req.width == 0 || req.width > limits.max_dimension {
+    validate_render_params(
+        req.width,
+        req.fixed_height(),
+        req.scale,
+        req.animation_time,
+        limits,
+    )
+}
+
+/// `height: None` is measured from the content; the render itself bounds it, via
+/// [`RenderOptions::max_height`].
+fn validate_render_params(
+    width: u32,
+    height: Option<u32>,
+    scale: f64,
+    animation_time: f64,
+    limits: &AppLimits,
+) -> Result<(), ApiError> {
+    if width == 0 || width > limits.max_dimension {
         return Err(ApiError::validation(format!(
             "width must be between 1 and {}",
             limits.max_dimension
         )));
     }
-    if req.height == 0 || req.height > limits.max_dimension {
+    if height.is_some_and(|height| height == 0 || height > limits.max_dimension) {
         return Err(ApiError::validation(format!(
             "height must be between 1 and {}",
             limits.max_dimension
         )));
     }
-    if !(req.scale.is_finite() && req.scale > 0.0 && req.scale <= limits.max_scale) {
+    if !(scale.is_finite() && scale > 0.0 && scale <= limits.max_scale) {
         return Err(ApiError::validation(format!(
             "scale must be within (0, {}]",
             limits.max_scale
         )));
     }
-    if !(req.animation_time.is_finite()
-        && req.animation_time >= 0.0
-        && req.animation_time <= limits.max_animation_time)
+    if !(animation_time.is_finite()
+        && animation_time >= 0.0
+        && animation_time <= limits.max_animation_time)
     {
         return Err(ApiError::validation(format!(
             "animation_time must be between 0 and {} seconds",
@@ -223,37 +1555,249 @@ fn validate_request(req: &RenderRequest, limits: &AppLimits) -> Result<(), ApiEr
     Ok(())
 }
 
+/// The entry for one finished `/render/batch` item.
+fn batch_item_result(
+    joined: Result<(usize, Result<Vec<u8>, ApiError>), task::JoinError>,
+) -> Result<BatchItemResult, ApiError> {
+    let (index, rendered) = joined.map_err(|err| {
+        error!(%err, "batch render task join error");
+        ApiError::internal("render task failed")
+    })?;
+    Ok(match rendered {
+        Ok(png) => BatchItemResult::succeeded(index, &png),
+        Err(err) => BatchItemResult::failed(index, &err),
+    })
+}
+
+/// Formats `/render` can produce, in the order wildcards prefer them.
+const NEGOTIABLE_TYPES: [&str; 3] = ["image/png", "image/jpeg", "image/webp"];
+
+/// The entries of an `Accept`-style header (`Accept`, `Accept-Encoding`) with their `q`
+/// weights, in header order.
+///
+/// Entries are trimmed and lowercased, and empty ones are dropped. A missing or unparsable
+/// `q` counts as `1`, so callers treat `q=0` (refused) the same way everywhere.
+pub(crate) fn parse_quality_list(header: &str) -> Vec<(String, f32)> {
+    header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let item = parts.next()?.trim().to_ascii_lowercase();
+            let quality = parts
+                .filter_map(|param| {
+                    let (name, value) = param.split_once('=')?;
+                    name.trim().eq_ignore_ascii_case("q").then_some(value)
+                })
+                .find_map(|value| value.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (!item.is_empty()).then_some((item, quality))
+        })
+        .collect()
+}
+
+/// Whether `accept` lists `application/json` ahead of (or instead of) `image/png`.
+fn prefers_json(accept: Option<&str>) -> bool {
+    let Some(accept) = accept else {
+        return false;
+    };
+    let ranges = parse_quality_list(accept);
+    let quality_of = |media_type: &str| {
+        ranges
+            .iter()
+            .filter(|(range, _)| range == media_type)
+            .map(|&(_, quality)| quality)
+            .reduce(f32::max)
+    };
+    match (quality_of("application/json"), quality_of("image/png")) {
+        (Some(json), Some(png)) => json > 0.0 && json > png,
+        (Some(json), None) => json > 0.0,
+        (None, _) => false,
+    }
+}
+
+/// Pick the output format for an `Accept` header value (RFC 9110 §12.5.1).
+///
+/// The highest-`q` media range that matches a supported type wins, with earlier entries
+/// breaking ties; wildcards resolve to the first supported type not refused with `q=0`.
+fn negotiate_format(accept: Option<&str>, jpeg_quality: u8) -> Result<OutputFormat, ApiError> {
+    let Some(accept) = accept.filter(|value| !value.trim().is_empty()) else {
+        return Ok(OutputFormat::default());
+    };
+
+    let ranges = parse_quality_list(accept);
+    let refused = |media_type: &str| {
+        ranges
+            .iter()
+            .any(|(range, quality)| range == media_type && *quality <= 0.0)
+    };
+
+    let mut best: Option<(&str, f32)> = None;
+    for (range, quality) in &ranges {
+        if *quality <= 0.0 {
+            continue;
+        }
+        let matched = match range.as_str() {
+            "*/*" | "image/*" => NEGOTIABLE_TYPES
+                .into_iter()
+                .find(|media_type| !refused(media_type)),
+            exact => NEGOTIABLE_TYPES
+                .into_iter()
+                .find(|media_type| *media_type == exact),
+        };
+        if let Some(media_type) = matched
+            && best.is_none_or(|(_, best_quality)| *quality > best_quality)
+        {
+            best = Some((media_type, *quality));
+        }
+    }
+
+    match best.map(|(media_type, _)| media_type) {
+        Some("image/jpeg") => Ok(OutputFormat::Jpeg {
+            quality: jpeg_quality,
+        }),
+        Some("image/webp") => Ok(OutputFormat::WEBP),
+        Some(_) => Ok(OutputFormat::default()),
+        None => Err(ApiError::NotAcceptable(accept.to_owned())),
+    }
+}
+
+/// Read uploaded font files, enforcing the upload limits and rejecting non-font files.
+async fn read_font_uploads(
+    uploads: Vec<Upload>,
+    limits: &AppLimits,
+) -> Result<Vec<Arc<Vec<u8>>>, ApiError> {
+    let total: usize = uploads.iter().map(Upload::size).sum();
+    if total > limits.max_font_uploads_size {
+        return Err(ApiError::validation(format!(
+            "font uploads must total at most {} bytes",
+            limits.max_font_uploads_size
+        )));
+    }
+
+    let mut fonts = Vec::with_capacity(uploads.len());
+    for (index, upload) in uploads.into_iter().enumerate() {
+        let name = upload.file_name().unwrap_or("unnamed").to_owned();
+        if upload.size() > limits.max_font_upload_size {
+            return Err(ApiError::validation(format!(
+                "font upload {index} ({name}) must be at most {} bytes",
+                limits.max_font_upload_size
+            )));
+        }
+        let data = upload.into_vec().await.map_err(|err| {
+            error!(%err, "failed to read font upload");
+            ApiError::internal("failed to read font upload")
+        })?;
+        if !is_font_file(&data) {
+            return Err(ApiError::validation(format!(
+                "font upload {index} ({name}) is not a TTF, OTF, or TTC font"
+            )));
+        }
+        fonts.push(Arc::new(data));
+    }
+    Ok(fonts)
+}
+
+/// Whether `data` starts with a TrueType, OpenType, or font collection signature.
+fn is_font_file(data: &[u8]) -> bool {
+    const SIGNATURES: [&[u8; 4]; 4] = [b"\x00\x01\x00\x00", b"OTTO", b"true", b"ttcf"];
+    SIGNATURES
+        .iter()
+        .any(|signature| data.starts_with(signature.as_slice()))
+}
+
+/// Reject content-measured heights beyond the configured maximum.
+fn validate_jpeg_quality(quality: u8, limits: &AppLimits) -> Result<(), ApiError> {
+    if !(limits.min_jpeg_quality..=limits.max_jpeg_quality).contains(&quality) {
+        return Err(ApiError::validation(format!(
+            "quality must be between {} and {}, got {quality}",
+            limits.min_jpeg_quality, limits.max_jpeg_quality
+        )));
+    }
+    Ok(())
+}
+
 fn resolve_requested_fonts(
     state: &AppState,
+    limits: &AppLimits,
     requested: Option<&[String]>,
 ) -> Result<Vec<PathBuf>, ApiError> {
     let Some(entries) = requested else {
         return Ok(Vec::new());
     };
+    if entries.len() > limits.max_fonts_per_request {
+        return Err(ApiError::validation(format!(
+            "font_paths may name at most {} fonts, got {}",
+            limits.max_fonts_per_request,
+            entries.len()
+        )));
+    }
 
     let fonts_dir = state.fonts_dir.as_ref().ok_or(ApiError::FontsNotAllowed)?;
 
-    resolve_font_paths(fonts_dir, entries)
+    resolve_font_paths(fonts_dir, entries, state.font_manifest.as_ref())
 }
 
-fn resolve_font_paths(fonts_dir: &Path, requested: &[String]) -> Result<Vec<PathBuf>, ApiError> {
+/// Resolve requested fonts, by alias when a manifest is configured and by file name otherwise.
+fn resolve_font_paths(
+    fonts_dir: &Path,
+    requested: &[String],
+    manifest: Option<&FontManifest>,
+) -> Result<Vec<PathBuf>, ApiError> {
     let mut resolved = Vec::with_capacity(requested.len());
+    let mut problems = Vec::new();
     for name in requested {
-        if name.contains('/') || name.contains('\\') {
-            return Err(ApiError::FontsNotAllowed);
+        let file = match manifest {
+            Some(manifest) => manifest.get(name).ok_or(ApiError::FontsNotAllowed)?,
+            None => name,
+        };
+        match resolve_font_path(fonts_dir, file) {
+            Ok(path) => resolved.push(path),
+            Err(reason) => problems.push(FontProblem {
+                font: name.clone(),
+                reason,
+            }),
         }
+    }
+    if problems.is_empty() {
+        Ok(resolved)
+    } else {
+        Err(ApiError::font_problems(problems))
+    }
+}
 
-        let candidate = fonts_dir.join(name);
-        let canonical = candidate
-            .canonicalize()
-            .map_err(|err| ApiError::validation(format!("font not found: {name} ({err})")))?;
+fn resolve_font_path(fonts_dir: &Path, name: &str) -> Result<PathBuf, FontProblemReason> {
+    let relative = Path::new(name);
+    let escapes = relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+    if escapes {
+        return Err(FontProblemReason::PathTraversal);
+    }
+    let canonical = fonts_dir
+        .join(name)
+        .canonicalize()
+        .map_err(|_err| FontProblemReason::NotFound)?;
+    if !canonical.starts_with(fonts_dir) {
+        return Err(FontProblemReason::PathTraversal);
+    }
+    Ok(canonical)
+}
 
-        if !canonical.starts_with(fonts_dir) {
-            return Err(ApiError::FontsNotAllowed);
+/// Expand `req.html` with its template context, up to `max_len` bytes, escaping values as
+/// `template` says and rejecting undefined variables when either `template` or the request
+/// (`strict_variables`) asks for it.
+fn expand_template(
+    req: &RenderRequest,
+    template: TemplateBehavior,
+    max_len: usize,
+) -> Result<String, RenderError> {
+    let context = build_context(req);
+    render_template_with_limit(&req.html, &context, max_len, |env| {
+        template.auto_escape.apply(env);
+        if template.undefined_strict || req.strict_variables {
+            env.set_undefined_behavior(UndefinedBehavior::Strict);
         }
-        resolved.push(canonical);
-    }
-    Ok(resolved)
+    })
 }
 
 fn build_context(req: &RenderRequest) -> Value {
@@ -278,27 +1822,108 @@ fn build_context(req: &RenderRequest) -> Value {
 }
 
 #[derive(Debug, Serialize)]
-struct ErrorBody {
+struct ErrorBody<'error> {
     error: String,
+    /// Correlation ID of the failed request, also sent as `X-Request-Id`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+    /// Every requested font that could not be used, when that caused the error.
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    font_problems: &'error [FontProblem],
+}
+
+/// A requested font name rejected by `font_paths` resolution.
+#[derive(Debug, Clone, Serialize)]
+pub struct FontProblem {
+    pub font: String,
+    pub reason: FontProblemReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FontProblemReason {
+    /// No such font in the fonts directory.
+    NotFound,
+    /// The name is absolute, contains `..`, or resolves outside the fonts directory.
+    PathTraversal,
 }
 
 #[derive(Debug, Error)]
 pub enum ApiError {
-    #[error("invalid request: {0}")]
-    Validation(String),
+    #[error("invalid request: {message}")]
+    Validation {
+        message: String,
+        /// Itemized font problems behind `message`, if any.
+        font_problems: Vec<FontProblem>,
+    },
     #[error("font usage is not allowed on this server")]
     FontsNotAllowed,
     #[error("rendering failed: {0}")]
     Render(String),
     #[error("render task failed: {0}")]
     Task(String),
+    #[error("server is in maintenance mode")]
+    Maintenance,
+    #[error("fonts are still loading, try again shortly")]
+    FontsLoading,
+    #[error("unauthorized")]
+    Unauthorized,
+    #[error("template expands to more than {0} bytes of html")]
+    HtmlTooLarge(usize),
+    #[error("request body exceeds the limit of {0} bytes")]
+    BodyTooLarge(usize),
+    #[error("request body needs a Content-Length header")]
+    LengthRequired,
+    #[error("rendering did not finish within {0:?}")]
+    Timeout(Duration),
+    #[error("too many renders in progress, try again later")]
+    Overloaded,
+    #[error("rate limit exceeded, retry in {0} s")]
+    RateLimited(u64),
+    #[error("render job not found: {0}")]
+    JobNotFound(String),
+    #[error("GET rendering is not enabled on this server")]
+    GetRenderDisabled,
+    #[error(
+        "none of the accepted types can be produced: {0} (supported: image/png, image/jpeg, image/webp)"
+    )]
+    NotAcceptable(String),
 }
 
 pub type ApiResult<T> = poem::Result<T>;
 
 impl ApiError {
     fn validation(message: impl Into<String>) -> Self {
-        Self::Validation(message.into())
+        Self::Validation {
+            message: message.into(),
+            font_problems: Vec::new(),
+        }
+    }
+
+    fn font_problems(font_problems: Vec<FontProblem>) -> Self {
+        let names = |reason| {
+            font_problems
+                .iter()
+                .filter(|problem| problem.reason == reason)
+                .map(|problem| problem.font.as_str())
+                .collect::<Vec<_>>()
+        };
+        let mut parts = Vec::new();
+        let missing = names(FontProblemReason::NotFound);
+        if !missing.is_empty() {
+            parts.push(format!("fonts not found: {}", missing.join(", ")));
+        }
+        let rejected = names(FontProblemReason::PathTraversal);
+        if !rejected.is_empty() {
+            parts.push(format!(
+                "fonts outside the fonts directory: {}",
+                rejected.join(", ")
+            ));
+        }
+        Self::Validation {
+            message: parts.join("; "),
+            font_problems,
+        }
     }
 
     fn internal(message: impl Into<String>) -> Self {
@@ -313,7 +1938,14 @@ impl From<RenderError> for ApiError {
             | RenderError::LoadTemplate { .. }
             | RenderError::RenderTemplate { .. }
             | RenderError::ReadFont { .. }
-            | RenderError::RegisterFont { .. } => ApiError::Validation(error.to_string()),
+            | RenderError::RegisterFont { .. }
+            | RenderError::RegisterFontBytes { .. }
+            | RenderError::EmptyHtml
+            | RenderError::EmojiNotBundled
+            | RenderError::ContentTooTall { .. }
+            | RenderError::DimensionsTooLarge { .. } => ApiError::validation(error.to_string()),
+            RenderError::TemplateTooLarge { limit, .. } => ApiError::HtmlTooLarge(limit),
+            RenderError::Timeout { timeout } => ApiError::Timeout(timeout),
             _ => ApiError::Render(error.to_string()),
         }
     }
@@ -322,17 +1954,37 @@ impl From<RenderError> for ApiError {
 impl ResponseError for ApiError {
     fn status(&self) -> StatusCode {
         match self {
-            ApiError::Validation(_) | ApiError::FontsNotAllowed => StatusCode::BAD_REQUEST,
+            ApiError::Validation { .. } | ApiError::FontsNotAllowed => StatusCode::BAD_REQUEST,
             ApiError::Render(_) | ApiError::Task(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::Maintenance | ApiError::FontsLoading | ApiError::Overloaded => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::HtmlTooLarge(_) | ApiError::BodyTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::LengthRequired => StatusCode::LENGTH_REQUIRED,
+            ApiError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            ApiError::GetRenderDisabled | ApiError::JobNotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::NotAcceptable(_) => StatusCode::NOT_ACCEPTABLE,
+            ApiError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
         }
     }
 
     fn as_response(&self) -> Response {
         let payload = PoemJson(ErrorBody {
             error: self.to_string(),
+            request_id: current_request_id(),
+            font_problems: match self {
+                ApiError::Validation { font_problems, .. } => font_problems,
+                _ => &[],
+            },
         });
         let mut response = payload.into_response();
         response.set_status(self.status());
+        if let ApiError::RateLimited(retry_after_secs) = self {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(*retry_after_secs));
+        }
         response
     }
 }
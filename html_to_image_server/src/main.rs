@@ -1,12 +1,20 @@
 use std::{
+    collections::HashSet,
     env, fs,
     net::SocketAddr,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use anyhow::{Context, Result};
 use dotenvy::dotenv;
-use html_to_image_server::{AppConfig, AppLimits, AppState, DEFAULT_MAX_BODY_SIZE, create_app};
+use html_to_image::AutoEscapeMode;
+use html_to_image_server::{
+    AppConfig, AppLimits, AppState, DEFAULT_MAX_BODY_SIZE, DEFAULT_MAX_FONTS_PER_REQUEST,
+    DEFAULT_MAX_FONTS_SCANNED, DEFAULT_MAX_QUEUED_RENDERS, DEFAULT_MAX_RENDERED_HTML,
+    DEFAULT_RENDER_TIMEOUT, FontManifest, FontScanConfig, create_app,
+    default_max_concurrent_renders,
+};
 use poem::{Server, listener::TcpListener};
 use tokio::signal;
 use tracing::{error, info};
@@ -23,16 +31,51 @@ async fn main() -> Result<()> {
 
     let addr = read_addr()?;
     let fonts_dir = read_fonts_dir()?;
+    let font_manifest = read_font_manifest(&fonts_dir)?;
+    let default_font = read_default_font()?;
     let max_body_size = read_max_body_size();
+    let font_scan = read_font_scan_config();
 
     let state = AppState {
         fonts_dir: Some(fonts_dir),
+        font_manifest,
+        default_font,
     };
     let config = AppConfig {
         state,
-        limits: AppLimits::default(),
+        limits: AppLimits {
+            render_timeout: read_render_timeout(),
+            max_fonts_per_request: read_usize(
+                "HTML_TO_IMAGE_MAX_FONTS_PER_REQUEST",
+                DEFAULT_MAX_FONTS_PER_REQUEST,
+            ),
+            max_rendered_html: read_usize(
+                "HTML_TO_IMAGE_MAX_RENDERED_HTML",
+                DEFAULT_MAX_RENDERED_HTML,
+            ),
+            ..AppLimits::default()
+        },
         max_body_size,
         server_base_url: Some(format!("http://{addr}")),
+        font_scan,
+        admin_token: read_admin_token(),
+        render_cache_entries: read_render_cache_entries(),
+        max_concurrent_renders: read_usize(
+            "HTML_TO_IMAGE_MAX_CONCURRENT_RENDERS",
+            default_max_concurrent_renders(),
+        ),
+        max_queued_renders: read_usize(
+            "HTML_TO_IMAGE_MAX_QUEUED_RENDERS",
+            DEFAULT_MAX_QUEUED_RENDERS,
+        ),
+        allow_get_render: read_allow_get_render(),
+        api_keys: read_api_keys(),
+        callback_allowed_hosts: read_callback_hosts(),
+        requests_per_minute: read_requests_per_minute(),
+        cors_allowed_origins: read_cors_allowed_origins(),
+        enable_compression: read_enable_compression(),
+        template_auto_escape: read_template_auto_escape(),
+        template_undefined_strict: read_template_strict(),
     };
 
     let listener = TcpListener::bind(addr);
@@ -57,6 +100,43 @@ fn read_fonts_dir() -> Result<PathBuf> {
     validate_fonts_dir(Path::new(&raw))
 }
 
+/// Read `HTML_TO_IMAGE_DEFAULT_FONT`, a font file registered for every render, checking
+/// that it can be read so a typo fails at startup rather than on each request.
+fn read_default_font() -> Result<Option<PathBuf>> {
+    let Ok(raw) = env::var("HTML_TO_IMAGE_DEFAULT_FONT") else {
+        return Ok(None);
+    };
+    let path = Path::new(raw.trim())
+        .canonicalize()
+        .with_context(|| format!("cannot read default font {}", raw.trim()))?;
+    info!(path = %path.display(), "using default font");
+    Ok(Some(path))
+}
+
+/// Load `HTML_TO_IMAGE_FONT_MANIFEST`, a JSON object of alias to font file, checking that
+/// every file exists inside `fonts_dir`.
+fn read_font_manifest(fonts_dir: &Path) -> Result<Option<FontManifest>> {
+    let Ok(raw) = env::var("HTML_TO_IMAGE_FONT_MANIFEST") else {
+        return Ok(None);
+    };
+    let path = Path::new(raw.trim());
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read font manifest {}", path.display()))?;
+    let manifest: FontManifest = serde_json::from_str(&contents)
+        .with_context(|| format!("invalid font manifest {}", path.display()))?;
+    for (alias, file) in &manifest {
+        let canonical = fonts_dir
+            .join(file)
+            .canonicalize()
+            .with_context(|| format!("font manifest alias {alias}: cannot read {file}"))?;
+        if !canonical.starts_with(fonts_dir) {
+            anyhow::bail!("font manifest alias {alias}: {file} is outside the fonts dir");
+        }
+    }
+    info!(aliases = manifest.len(), "loaded font manifest");
+    Ok(Some(manifest))
+}
+
 fn read_max_body_size() -> usize {
     match env::var("HTML_TO_IMAGE_MAX_BODY") {
         Ok(value) => match value.trim().parse::<usize>() {
@@ -73,6 +153,148 @@ fn read_max_body_size() -> usize {
     }
 }
 
+fn read_font_scan_config() -> FontScanConfig {
+    let max_fonts_scanned = match env::var("HTML_TO_IMAGE_MAX_FONTS_SCANNED") {
+        Ok(value) => value.trim().parse::<usize>().unwrap_or_else(|err| {
+            tracing::warn!(%value, %err, "failed to parse HTML_TO_IMAGE_MAX_FONTS_SCANNED, using default");
+            DEFAULT_MAX_FONTS_SCANNED
+        }),
+        Err(_) => DEFAULT_MAX_FONTS_SCANNED,
+    };
+    let refresh_interval = match env::var("HTML_TO_IMAGE_FONT_SCAN_REFRESH_SECS") {
+        Ok(value) => match value.trim().parse::<u64>() {
+            Ok(0) => None,
+            Ok(secs) => Some(Duration::from_secs(secs)),
+            Err(err) => {
+                tracing::warn!(%value, %err, "failed to parse HTML_TO_IMAGE_FONT_SCAN_REFRESH_SECS, disabling refresh");
+                None
+            }
+        },
+        Err(_) => None,
+    };
+
+    FontScanConfig {
+        max_fonts_scanned,
+        refresh_interval,
+    }
+}
+
+fn read_render_timeout() -> Duration {
+    match env::var("HTML_TO_IMAGE_RENDER_TIMEOUT_SECS") {
+        Ok(value) => match value.trim().parse::<u64>() {
+            Ok(secs) if secs > 0 => Duration::from_secs(secs),
+            Ok(_) => {
+                tracing::warn!(%value, "HTML_TO_IMAGE_RENDER_TIMEOUT_SECS must be positive, using default");
+                DEFAULT_RENDER_TIMEOUT
+            }
+            Err(err) => {
+                tracing::warn!(%value, %err, "failed to parse HTML_TO_IMAGE_RENDER_TIMEOUT_SECS, using default");
+                DEFAULT_RENDER_TIMEOUT
+            }
+        },
+        Err(_) => DEFAULT_RENDER_TIMEOUT,
+    }
+}
+
+fn read_render_cache_entries() -> usize {
+    match env::var("HTML_TO_IMAGE_RENDER_CACHE_ENTRIES") {
+        Ok(value) => value.trim().parse::<usize>().unwrap_or_else(|err| {
+            tracing::warn!(%value, %err, "failed to parse HTML_TO_IMAGE_RENDER_CACHE_ENTRIES, disabling the cache");
+            0
+        }),
+        Err(_) => 0,
+    }
+}
+
+fn read_requests_per_minute() -> u32 {
+    match env::var("HTML_TO_IMAGE_REQUESTS_PER_MINUTE") {
+        Ok(value) => value.trim().parse::<u32>().unwrap_or_else(|err| {
+            tracing::warn!(%value, %err, "failed to parse HTML_TO_IMAGE_REQUESTS_PER_MINUTE, disabling rate limiting");
+            0
+        }),
+        Err(_) => 0,
+    }
+}
+
+fn read_usize(name: &str, default: usize) -> usize {
+    match env::var(name) {
+        Ok(value) => value.trim().parse::<usize>().unwrap_or_else(|err| {
+            tracing::warn!(%value, %err, "failed to parse {name}, using default");
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+fn read_allow_get_render() -> bool {
+    env::var("HTML_TO_IMAGE_ALLOW_GET_RENDER")
+        .is_ok_and(|value| matches!(value.trim(), "1" | "true" | "yes"))
+}
+
+fn read_api_keys() -> Option<HashSet<String>> {
+    let keys: HashSet<String> = env::var("HTML_TO_IMAGE_API_KEYS")
+        .ok()?
+        .split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(str::to_owned)
+        .collect();
+    (!keys.is_empty()).then_some(keys)
+}
+
+fn read_callback_hosts() -> Option<HashSet<String>> {
+    let hosts: HashSet<String> = env::var("HTML_TO_IMAGE_CALLBACK_HOSTS")
+        .ok()?
+        .split(',')
+        .map(str::trim)
+        .filter(|host| !host.is_empty())
+        .map(str::to_owned)
+        .collect();
+    (!hosts.is_empty()).then_some(hosts)
+}
+
+fn read_cors_allowed_origins() -> Option<Vec<String>> {
+    let origins: Vec<String> = env::var("HTML_TO_IMAGE_CORS_ORIGINS")
+        .ok()?
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .map(str::to_owned)
+        .collect();
+    (!origins.is_empty()).then_some(origins)
+}
+
+fn read_enable_compression() -> bool {
+    env::var("HTML_TO_IMAGE_COMPRESSION")
+        .map_or(true, |value| !matches!(value.trim(), "0" | "false" | "no"))
+}
+
+fn read_template_auto_escape() -> AutoEscapeMode {
+    match env::var("HTML_TO_IMAGE_TEMPLATE_AUTO_ESCAPE") {
+        Ok(value) => match value.trim() {
+            "html" => AutoEscapeMode::Html,
+            "none" => AutoEscapeMode::None,
+            _ => {
+                tracing::warn!(%value, "HTML_TO_IMAGE_TEMPLATE_AUTO_ESCAPE must be html or none, using html");
+                AutoEscapeMode::Html
+            }
+        },
+        Err(_) => AutoEscapeMode::Html,
+    }
+}
+
+fn read_template_strict() -> bool {
+    env::var("HTML_TO_IMAGE_TEMPLATE_STRICT")
+        .is_ok_and(|value| matches!(value.trim(), "1" | "true" | "yes"))
+}
+
+fn read_admin_token() -> Option<String> {
+    env::var("HTML_TO_IMAGE_ADMIN_TOKEN")
+        .ok()
+        .map(|token| token.trim().to_owned())
+        .filter(|token| !token.is_empty())
+}
+
 fn init_tracing() {
     let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
 
@@ -0,0 +1,172 @@
+use std::{
+    any::Any,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use html_to_image::{
+    RenderError, RenderOptions, RenderOutput, render_html_bytes, render_html_output,
+};
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore, TryAcquireError},
+    task, time,
+};
+use tracing::{error, warn};
+
+use crate::ApiError;
+
+/// Runs CPU-bound renders on the blocking pool, at most `max_concurrent` at a time.
+///
+/// Up to `max_queued` further renders wait for a slot; beyond that, requests are turned
+/// away with [`ApiError::Overloaded`] instead of piling up blocking threads.
+#[derive(Debug, Clone)]
+pub(crate) struct RenderPool {
+    permits: Arc<Semaphore>,
+    max_concurrent: usize,
+    queued: Arc<AtomicUsize>,
+    max_queued: usize,
+    timeout: Duration,
+}
+
+impl RenderPool {
+    pub(crate) fn new(max_concurrent: usize, max_queued: usize, timeout: Duration) -> Self {
+        let max_concurrent = max_concurrent.max(1);
+        Self {
+            permits: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent,
+            queued: Arc::default(),
+            max_queued,
+            timeout,
+        }
+    }
+
+    /// Renders that can run at once.
+    pub(crate) fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+
+    pub(crate) async fn render_png(
+        &self,
+        html: String,
+        opts: RenderOptions,
+    ) -> Result<Vec<u8>, ApiError> {
+        self.run((opts.width, opts.height), move || {
+            render_html_bytes(&html, &opts)
+        })
+        .await
+    }
+
+    /// Like [`Self::render_png`], also reporting the rendered dimensions.
+    pub(crate) async fn render_png_output(
+        &self,
+        html: String,
+        opts: RenderOptions,
+    ) -> Result<RenderOutput, ApiError> {
+        self.run((opts.width, opts.height), move || {
+            render_html_output(&html, &opts)
+        })
+        .await
+    }
+
+    /// Run `render` on the blocking pool so the async runtime stays responsive.
+    ///
+    /// One deadline covers waiting for a slot and rendering: once the configured timeout
+    /// passes, this gives up with [`RenderError::Timeout`] as
+    /// [`html_to_image::render_html_bytes_timeout`] does, answered as [`ApiError::Timeout`].
+    /// Blitz cannot be interrupted, so an abandoned render still runs to completion in the
+    /// background and keeps holding its permit until then; its result is dropped. A
+    /// panicking render becomes an internal error naming the panic message and the
+    /// requested `(width, height)`.
+    pub(crate) async fn run<T, F>(
+        &self,
+        (width, height): (u32, u32),
+        render: F,
+    ) -> Result<T, ApiError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> html_to_image::Result<T> + Send + 'static,
+    {
+        let timeout = self.timeout;
+        let rendered = time::timeout(timeout, async {
+            let permit = self.acquire().await?;
+            task::spawn_blocking(move || {
+                let _permit = permit;
+                render()
+            })
+            .await
+            .map_err(|err| Self::join_error(err, (width, height)))
+        })
+        .await
+        .map_err(|_elapsed| {
+            error!(?timeout, "render timed out");
+            ApiError::from(RenderError::Timeout { timeout })
+        })??;
+        rendered.map_err(ApiError::from)
+    }
+
+    /// The internal error for a render task that panicked or failed to join.
+    fn join_error(err: task::JoinError, (width, height): (u32, u32)) -> ApiError {
+        match err.try_into_panic() {
+            Ok(payload) => {
+                let message = panic_message(payload.as_ref());
+                error!(width, height, panic = message, "render panicked");
+                ApiError::internal(format!("render of {width}x{height} panicked: {message}"))
+            }
+            Err(err) => {
+                error!(%err, "render task join error");
+                ApiError::internal("render task failed")
+            }
+        }
+    }
+
+    async fn acquire(&self) -> Result<OwnedSemaphorePermit, ApiError> {
+        match Arc::clone(&self.permits).try_acquire_owned() {
+            Ok(permit) => return Ok(permit),
+            Err(TryAcquireError::NoPermits) => {}
+            Err(TryAcquireError::Closed) => return Err(ApiError::internal("render pool closed")),
+        }
+
+        let waiting = QueueSlot::claim(&self.queued, self.max_queued).ok_or_else(|| {
+            warn!(max_queued = self.max_queued, "render queue full");
+            ApiError::Overloaded
+        })?;
+        let permit = Arc::clone(&self.permits)
+            .acquire_owned()
+            .await
+            .map_err(|_closed| ApiError::internal("render pool closed"));
+        drop(waiting);
+        permit
+    }
+}
+
+/// The text a panic was raised with, for `panic!("...")` and `panic!("{..}", ..)` payloads.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload")
+}
+
+/// A place in the wait queue, released on drop so cancelled requests give it back.
+struct QueueSlot<'pool>(&'pool AtomicUsize);
+
+impl<'pool> QueueSlot<'pool> {
+    fn claim(queued: &'pool AtomicUsize, max_queued: usize) -> Option<Self> {
+        queued
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |waiting| {
+                (waiting < max_queued).then_some(waiting + 1)
+            })
+            .ok()
+            .map(|_| Self(queued))
+    }
+}
+
+impl Drop for QueueSlot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
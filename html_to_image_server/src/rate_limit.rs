@@ -0,0 +1,127 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use poem::{Endpoint, Middleware, Request, Result, http::header::AUTHORIZATION};
+
+use crate::ApiError;
+
+/// Buckets kept before idle (full) ones are dropped, so one-off clients do not pile up.
+const PRUNE_THRESHOLD: usize = 4096;
+
+/// Token-bucket rate limiting per API key, or per client IP for anonymous requests.
+///
+/// Each client may burst up to `requests_per_minute` requests, refilled evenly over a
+/// minute. Requests over the limit get [`ApiError::RateLimited`] (429 with `Retry-After`).
+///
+/// Requests are only told apart by their bearer token when `key_by_api_key` is set, i.e.
+/// when `ApiKeyAuth` has already checked it; otherwise any client could dodge the limit
+/// by sending a fresh made-up token with every request.
+pub(crate) struct RateLimit {
+    limiter: Arc<Limiter>,
+}
+
+impl RateLimit {
+    pub(crate) fn new(requests_per_minute: u32, key_by_api_key: bool) -> Self {
+        Self {
+            limiter: Arc::new(Limiter {
+                capacity: f64::from(requests_per_minute),
+                per_second: f64::from(requests_per_minute) / 60.0,
+                key_by_api_key,
+                buckets: Mutex::default(),
+            }),
+        }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for RateLimit {
+    type Output = RateLimitEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        RateLimitEndpoint {
+            inner: ep,
+            limiter: Arc::clone(&self.limiter),
+        }
+    }
+}
+
+pub(crate) struct RateLimitEndpoint<E> {
+    inner: E,
+    limiter: Arc<Limiter>,
+}
+
+impl<E: Endpoint> Endpoint for RateLimitEndpoint<E> {
+    type Output = E::Output;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        let client = client_key(&req, self.limiter.key_by_api_key);
+        self.limiter.take(client, Instant::now())?;
+        self.inner.call(req).await
+    }
+}
+
+struct Limiter {
+    capacity: f64,
+    per_second: f64,
+    key_by_api_key: bool,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Limiter {
+    /// Spend one token from `client`'s bucket, or say how long until one is available.
+    fn take(&self, client: String, now: Instant) -> Result<(), ApiError> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        if buckets.len() >= PRUNE_THRESHOLD {
+            buckets.retain(|_, bucket| self.refilled(bucket, now) < self.capacity);
+        }
+
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            tokens: self.capacity,
+            updated: now,
+        });
+        bucket.tokens = self.refilled(bucket, now);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+
+        let wait = Duration::from_secs_f64((1.0 - bucket.tokens) / self.per_second);
+        Err(ApiError::RateLimited(
+            wait.as_secs() + u64::from(wait.subsec_nanos() > 0),
+        ))
+    }
+
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        elapsed
+            .mul_add(self.per_second, bucket.tokens)
+            .min(self.capacity)
+    }
+}
+
+/// The API key the request presents (with `key_by_api_key`), or else the client's IP
+/// address.
+fn client_key(req: &Request, key_by_api_key: bool) -> String {
+    let api_key = req
+        .headers()
+        .get(AUTHORIZATION)
+        .filter(|_| key_by_api_key)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim);
+    if let Some(api_key) = api_key {
+        return format!("key:{api_key}");
+    }
+    match req.remote_addr().as_socket_addr() {
+        Some(addr) => format!("ip:{}", addr.ip()),
+        None => "anonymous".to_owned(),
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt::Write as _;
+
+use poem::{
+    Endpoint, IntoResponse, Middleware, Request, Response, Result,
+    http::{HeaderName, HeaderValue},
+};
+use rand::{RngCore as _, SeedableRng as _, rngs::StdRng};
+use tracing::{Instrument as _, info_span};
+
+pub(crate) const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client-supplied request ID that is kept; longer ones are replaced.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    /// ID of the request being handled, for error bodies built outside the middleware.
+    static CURRENT_REQUEST_ID: String;
+}
+
+/// The ID of the request being handled, if any.
+pub(crate) fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Tags each request with a correlation ID: the client's `X-Request-Id` when it is a
+/// short printable string, otherwise a fresh UUID.
+///
+/// The ID is recorded on a tracing span around the request and echoed in the
+/// `X-Request-Id` response header and in error bodies.
+pub(crate) struct RequestId;
+
+impl<E: Endpoint> Middleware<E> for RequestId {
+    type Output = RequestIdEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        RequestIdEndpoint { inner: ep }
+    }
+}
+
+pub(crate) struct RequestIdEndpoint<E> {
+    inner: E,
+}
+
+impl<E: Endpoint> Endpoint for RequestIdEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        let request_id = req
+            .headers()
+            .get(&REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| is_valid_request_id(value))
+            .map_or_else(random_uuid, str::to_owned);
+        let span = info_span!("request", request_id = %request_id);
+
+        let mut response = CURRENT_REQUEST_ID
+            .scope(request_id.clone(), self.inner.get_response(req))
+            .instrument(span)
+            .await
+            .into_response();
+        if let Ok(value) = HeaderValue::from_str(&request_id) {
+            response.headers_mut().insert(REQUEST_ID_HEADER, value);
+        }
+        Ok(response)
+    }
+}
+
+fn is_valid_request_id(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LEN
+        && value.bytes().all(|byte| byte.is_ascii_graphic())
+}
+
+/// A random (version 4) UUID in its hyphenated form.
+pub(crate) fn random_uuid() -> String {
+    let mut bytes = [0_u8; 16];
+    StdRng::from_os_rng().fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0F) | 0x40;
+    bytes[8] = (bytes[8] & 0x3F) | 0x80;
+
+    let mut uuid = String::with_capacity(36);
+    for (index, byte) in bytes.iter().enumerate() {
+        if matches!(index, 4 | 6 | 8 | 10) {
+            uuid.push('-');
+        }
+        write!(uuid, "{byte:02x}").ok();
+    }
+    uuid
+}
@@ -0,0 +1,98 @@
+#![allow(
+    unused_crate_dependencies,
+    reason = "Integration test does not exercise all package-level dependencies."
+)]
+#![allow(
+    clippy::tests_outside_test_module,
+    reason = "Integration test crate is the test module."
+)]
+
+use std::collections::HashSet;
+
+use html_to_image_server::{AppConfig, create_app};
+use poem::{http::StatusCode, test::TestClient};
+
+const API_KEY: &str = "test-api-key";
+
+#[tokio::test]
+async fn api_keys_guard_every_route_but_probes() {
+    let config = AppConfig {
+        api_keys: Some(HashSet::from([API_KEY.to_owned(), "other-key".to_owned()])),
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+
+    client.get("/healthz").send().await.assert_status_is_ok();
+    client.get("/readyz").send().await.assert_status_is_ok();
+    client
+        .get("/metrics")
+        .send()
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    client
+        .get("/metrics")
+        .header("authorization", "Bearer wrong-key")
+        .send()
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    client
+        .get("/metrics")
+        .header("authorization", format!("Bearer {API_KEY}"))
+        .send()
+        .await
+        .assert_status_is_ok();
+}
+
+#[tokio::test]
+async fn server_is_open_without_api_keys() {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+
+    client.get("/metrics").send().await.assert_status_is_ok();
+}
+
+#[tokio::test]
+async fn rate_limit_answers_429_per_api_key() {
+    let config = AppConfig {
+        api_keys: Some(HashSet::from([API_KEY.to_owned(), "other-key".to_owned()])),
+        requests_per_minute: 2,
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+    let metrics = |key: &str| {
+        client
+            .get("/metrics")
+            .header("authorization", format!("Bearer {key}"))
+            .send()
+    };
+
+    metrics(API_KEY).await.assert_status_is_ok();
+    metrics(API_KEY).await.assert_status_is_ok();
+    let limited = metrics(API_KEY).await;
+    limited.assert_status(StatusCode::TOO_MANY_REQUESTS);
+    limited.assert_header("retry-after", "30");
+
+    metrics("other-key").await.assert_status_is_ok();
+    client.get("/healthz").send().await.assert_status_is_ok();
+    client.get("/readyz").send().await.assert_status_is_ok();
+}
+
+#[tokio::test]
+async fn rotating_bearer_tokens_do_not_dodge_the_rate_limit() {
+    let config = AppConfig {
+        requests_per_minute: 2,
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+    let metrics = |token: &str| {
+        client
+            .get("/metrics")
+            .header("authorization", format!("Bearer {token}"))
+            .send()
+    };
+
+    metrics("made-up-1").await.assert_status_is_ok();
+    metrics("made-up-2").await.assert_status_is_ok();
+    metrics("made-up-3")
+        .await
+        .assert_status(StatusCode::TOO_MANY_REQUESTS);
+}
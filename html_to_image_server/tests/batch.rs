@@ -0,0 +1,166 @@
+#![allow(
+    unused_crate_dependencies,
+    reason = "Integration test does not exercise all package-level dependencies."
+)]
+#![allow(
+    clippy::tests_outside_test_module,
+    reason = "Integration test crate is the test module."
+)]
+
+use html_to_image_server::{AppConfig, AppLimits, create_app};
+use poem::{http::StatusCode, test::TestClient};
+use serde_json::{Value, json};
+
+#[tokio::test]
+async fn batch_reports_each_item_in_order() -> poem::Result<()> {
+    let app = create_app(&AppConfig::default());
+    let client = TestClient::new(app);
+
+    let body = json!({
+        "items": [
+            { "html": "<div>one</div>", "width": 32, "height": 16 },
+            { "html": "<div>too wide</div>", "width": 0, "height": 16 },
+            { "html": "<div>three</div>", "width": 24, "height": 24 }
+        ]
+    })
+    .to_string();
+
+    let response = client
+        .post("/render/batch")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await;
+
+    response.assert_status(StatusCode::OK);
+    let bytes = response.0.into_body().into_vec().await?;
+    let json: Value = serde_json::from_slice(&bytes)
+        .map_err(|err| test_error(&format!("invalid JSON: {err}")))?;
+    let results = json
+        .get("results")
+        .and_then(Value::as_array)
+        .ok_or_else(|| test_error("missing results"))?;
+
+    let summary: Vec<(Option<u64>, Option<u64>, bool)> = results
+        .iter()
+        .map(|result| {
+            (
+                result.get("index").and_then(Value::as_u64),
+                result.get("status").and_then(Value::as_u64),
+                result.get("png_base64").is_some_and(Value::is_string),
+            )
+        })
+        .collect();
+    let expected = [
+        (Some(0), Some(200), true),
+        (Some(1), Some(400), false),
+        (Some(2), Some(200), true),
+    ];
+    if summary == expected {
+        Ok(())
+    } else {
+        Err(test_error(&format!("unexpected results: {summary:?}")))
+    }
+}
+
+#[tokio::test]
+async fn batch_rejects_more_items_than_max_batch() {
+    let config = AppConfig {
+        limits: AppLimits {
+            max_batch: 1,
+            ..AppLimits::default()
+        },
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+
+    let item = json!({ "html": "<div>x</div>", "width": 16, "height": 16 });
+    let body = json!({ "items": [item.clone(), item] }).to_string();
+
+    let response = client
+        .post("/render/batch")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await;
+
+    response.assert_status(StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn batch_items_respect_max_dimension_for_measured_heights() -> poem::Result<()> {
+    let config = AppConfig {
+        limits: AppLimits {
+            max_dimension: 64,
+            ..AppLimits::default()
+        },
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+
+    let tall = "<html><body style=\"margin:0\"><div style=\"height:200px\"></div></body></html>";
+    let body = json!({ "items": [{ "html": tall, "width": 32, "height": 0 }] }).to_string();
+
+    let response = client
+        .post("/render/batch")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await;
+
+    response.assert_status(StatusCode::OK);
+    let bytes = response.0.into_body().into_vec().await?;
+    let json: Value = serde_json::from_slice(&bytes)
+        .map_err(|err| test_error(&format!("invalid JSON: {err}")))?;
+    let status = json.pointer("/results/0/status").and_then(Value::as_u64);
+    if status == Some(400) {
+        Ok(())
+    } else {
+        Err(test_error(&format!("unexpected results: {json}")))
+    }
+}
+
+#[tokio::test]
+async fn batch_larger_than_the_render_queue_fits_an_idle_server() -> poem::Result<()> {
+    let config = AppConfig {
+        max_concurrent_renders: 1,
+        max_queued_renders: 1,
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+
+    let item = json!({ "html": "<div>x</div>", "width": 16, "height": 16 });
+    let body = json!({ "items": vec![item; 6] }).to_string();
+
+    let response = client
+        .post("/render/batch")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await;
+
+    response.assert_status(StatusCode::OK);
+    let bytes = response.0.into_body().into_vec().await?;
+    let json: Value = serde_json::from_slice(&bytes)
+        .map_err(|err| test_error(&format!("invalid JSON: {err}")))?;
+    let statuses: Vec<Option<u64>> = json
+        .get("results")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|result| result.get("status").and_then(Value::as_u64))
+        .collect();
+    if statuses == [Some(200); 6] {
+        Ok(())
+    } else {
+        Err(test_error(&format!("unexpected statuses: {statuses:?}")))
+    }
+}
+
+fn test_error(message: &str) -> poem::Error {
+    poem::Error::from_string(message.to_owned(), StatusCode::INTERNAL_SERVER_ERROR)
+}
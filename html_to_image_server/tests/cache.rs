@@ -0,0 +1,96 @@
+#![allow(
+    unused_crate_dependencies,
+    reason = "Integration test does not exercise all package-level dependencies."
+)]
+#![allow(
+    clippy::tests_outside_test_module,
+    reason = "Integration test crate is the test module."
+)]
+
+use html_to_image_server::{AppConfig, create_app};
+use poem::{Endpoint, Response, http::StatusCode, test::TestClient};
+use serde_json::json;
+
+#[tokio::test]
+async fn repeated_render_is_served_from_cache() -> poem::Result<()> {
+    let config = AppConfig {
+        render_cache_entries: 8,
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+
+    let mut bodies = Vec::new();
+    for _ in 0..2 {
+        bodies.push(render_png(&client, "Cached").await?);
+    }
+    if bodies.first() != bodies.last() {
+        return Err(test_error("cached render should return identical bytes"));
+    }
+
+    let metrics = metrics(&client).await?;
+    for line in [
+        "html_to_image_render_cache_hits_total 1",
+        "html_to_image_render_cache_misses_total 1",
+        "html_to_image_render_cache_entries 1",
+    ] {
+        if !metrics.lines().any(|metric| metric == line) {
+            return Err(test_error(&format!("missing `{line}` in:\n{metrics}")));
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn cache_is_disabled_by_default() -> poem::Result<()> {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+
+    render_png(&client, "Uncached").await?;
+
+    let metrics = metrics(&client).await?;
+    if metrics
+        .lines()
+        .any(|line| line == "html_to_image_render_cache_misses_total 0")
+    {
+        Ok(())
+    } else {
+        Err(test_error(&format!(
+            "cache should not be consulted:\n{metrics}"
+        )))
+    }
+}
+
+async fn render_png<E>(client: &TestClient<E>, name: &str) -> poem::Result<Vec<u8>>
+where
+    E: Endpoint<Output = Response>,
+{
+    let body = json!({
+        "html": "<div>{{ name }}</div>",
+        "width": 32,
+        "height": 16,
+        "data": { "name": name }
+    })
+    .to_string();
+
+    let response = client
+        .post("/render/png")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await;
+    response.assert_status(StatusCode::OK);
+    Ok(response.0.into_body().into_vec().await?)
+}
+
+async fn metrics<E>(client: &TestClient<E>) -> poem::Result<String>
+where
+    E: Endpoint<Output = Response>,
+{
+    let response = client.get("/metrics").send().await;
+    response.assert_status(StatusCode::OK);
+    Ok(response.0.into_body().into_string().await?)
+}
+
+fn test_error(message: &str) -> poem::Error {
+    poem::Error::from_string(message.to_owned(), StatusCode::INTERNAL_SERVER_ERROR)
+}
@@ -0,0 +1,143 @@
+#![allow(
+    unused_crate_dependencies,
+    reason = "Integration test does not exercise all package-level dependencies."
+)]
+#![allow(
+    clippy::tests_outside_test_module,
+    reason = "Integration test crate is the test module."
+)]
+
+use html_to_image_server::{AppConfig, create_app};
+use poem::{
+    http::{Method, StatusCode},
+    test::TestClient,
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn spec_is_gzipped_when_requested() {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+
+    let compressed = client
+        .get("/spec")
+        .header("accept-encoding", "deflate;q=0.5, gzip")
+        .send()
+        .await;
+    compressed.assert_status_is_ok();
+    compressed.assert_header("content-encoding", "gzip");
+
+    let plain = client.get("/spec").send().await;
+    plain.assert_status_is_ok();
+    plain.assert_header_is_not_exist("content-encoding");
+}
+
+#[tokio::test]
+async fn refused_codings_are_skipped() {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+
+    let deflated = client
+        .get("/spec")
+        .header("accept-encoding", " GZIP ; Q=0 , deflate")
+        .send()
+        .await;
+    deflated.assert_status_is_ok();
+    deflated.assert_header("content-encoding", "deflate");
+
+    let refused = client
+        .get("/spec")
+        .header("accept-encoding", "gzip;q=0, deflate; q=0")
+        .send()
+        .await;
+    refused.assert_status_is_ok();
+    refused.assert_header_is_not_exist("content-encoding");
+}
+
+#[tokio::test]
+async fn images_are_sent_uncompressed() {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+    let body = json!({ "html": "<div>x</div>", "width": 16, "height": 16 }).to_string();
+
+    let response = client
+        .post("/render/png")
+        .header("accept-encoding", "gzip")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await;
+
+    response.assert_status_is_ok();
+    response.assert_header_is_not_exist("content-encoding");
+}
+
+#[tokio::test]
+async fn head_responses_keep_their_content_length() {
+    let config = AppConfig {
+        allow_get_render: true,
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+
+    let response = client
+        .request(Method::HEAD, "/render/png")
+        .query("html", &"<div>x</div>")
+        .query("width", &16)
+        .query("height", &16)
+        .header("accept-encoding", "gzip")
+        .send()
+        .await;
+
+    response.assert_status_is_ok();
+    response.assert_header_is_not_exist("content-encoding");
+    response.assert_header_exist("content-length");
+}
+
+#[tokio::test]
+async fn not_modified_responses_are_not_compressed() {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+    let body = json!({ "html": "<div>x</div>", "width": 16, "height": 16 }).to_string();
+    let render = |etag: Option<&str>| {
+        let request = client
+            .post("/render/png")
+            .header("accept-encoding", "gzip")
+            .header("content-length", body.len())
+            .header("content-type", "application/json");
+        let request = match etag {
+            Some(etag) => request.header("if-none-match", etag),
+            None => request,
+        };
+        request.body(body.clone()).send()
+    };
+
+    let first = render(None).await;
+    first.assert_status_is_ok();
+    let etag = first
+        .0
+        .headers()
+        .get("etag")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_owned();
+
+    let repeat = render(Some(&etag)).await;
+    repeat.assert_status(StatusCode::NOT_MODIFIED);
+    repeat.assert_header_is_not_exist("content-encoding");
+}
+
+#[tokio::test]
+async fn compression_can_be_disabled() {
+    let config = AppConfig {
+        enable_compression: false,
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+
+    let response = client
+        .get("/spec")
+        .header("accept-encoding", "gzip")
+        .send()
+        .await;
+
+    response.assert_status_is_ok();
+    response.assert_header_is_not_exist("content-encoding");
+}
@@ -0,0 +1,37 @@
+#![allow(
+    unused_crate_dependencies,
+    reason = "Integration test does not exercise all package-level dependencies."
+)]
+#![allow(
+    clippy::tests_outside_test_module,
+    reason = "Integration test crate is the test module."
+)]
+
+use html_to_image_server::{AppConfig, create_app};
+use poem::{http::StatusCode, test::TestClient};
+use serde_json::json;
+
+#[tokio::test]
+async fn saturated_renders_beyond_the_queue_get_503() {
+    let config = AppConfig {
+        max_concurrent_renders: 1,
+        max_queued_renders: 0,
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+    // Large enough that the first render is still running when the second arrives.
+    let body = json!({ "html": "<div>busy</div>", "width": 1024, "height": 1024 }).to_string();
+
+    let request = || {
+        client
+            .post("/render/png")
+            .header("content-length", body.len())
+            .header("content-type", "application/json")
+            .body(body.clone())
+            .send()
+    };
+    let (first, second) = tokio::join!(request(), request());
+
+    first.assert_status(StatusCode::OK);
+    second.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+}
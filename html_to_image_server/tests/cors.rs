@@ -0,0 +1,69 @@
+#![allow(
+    unused_crate_dependencies,
+    reason = "Integration test does not exercise all package-level dependencies."
+)]
+#![allow(
+    clippy::tests_outside_test_module,
+    reason = "Integration test crate is the test module."
+)]
+
+use std::collections::HashSet;
+
+use html_to_image_server::{AppConfig, create_app};
+use poem::{http::Method, test::TestClient};
+
+const ORIGIN: &str = "https://app.example.com";
+
+#[tokio::test]
+async fn preflight_is_answered_before_authentication() {
+    let config = AppConfig {
+        api_keys: Some(HashSet::from(["test-api-key".to_owned()])),
+        cors_allowed_origins: Some(vec![ORIGIN.to_owned()]),
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+
+    let response = client
+        .request(Method::OPTIONS, "/render/png")
+        .header("origin", ORIGIN)
+        .header("access-control-request-method", "POST")
+        .header(
+            "access-control-request-headers",
+            "authorization, content-type",
+        )
+        .send()
+        .await;
+
+    response.assert_status_is_ok();
+    response.assert_header("access-control-allow-origin", ORIGIN);
+}
+
+#[tokio::test]
+async fn only_configured_origins_get_cors_headers() {
+    let config = AppConfig {
+        cors_allowed_origins: Some(vec![ORIGIN.to_owned()]),
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+
+    let allowed = client.get("/readyz").header("origin", ORIGIN).send().await;
+    allowed.assert_status_is_ok();
+    allowed.assert_header("access-control-allow-origin", ORIGIN);
+
+    let other = client
+        .get("/readyz")
+        .header("origin", "https://elsewhere.example.com")
+        .send()
+        .await;
+    other.assert_header_is_not_exist("access-control-allow-origin");
+}
+
+#[tokio::test]
+async fn no_cors_headers_without_configured_origins() {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+
+    let response = client.get("/readyz").header("origin", ORIGIN).send().await;
+
+    response.assert_status_is_ok();
+    response.assert_header_is_not_exist("access-control-allow-origin");
+}
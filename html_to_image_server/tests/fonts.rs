@@ -0,0 +1,290 @@
+#![allow(
+    unused_crate_dependencies,
+    reason = "Integration test does not exercise all package-level dependencies."
+)]
+#![allow(
+    clippy::tests_outside_test_module,
+    reason = "Integration test crate is the test module."
+)]
+
+use std::{error::Error as StdError, fs, path::Path, result::Result as StdResult};
+
+use html_to_image_server::{
+    AppConfig, AppLimits, AppState, FontCatalog, FontManifest, FontScanConfig, create_app,
+    scan_fonts_dir,
+};
+use poem::{http::StatusCode, test::TestClient};
+use serde_json::{Value, json};
+use tempfile::tempdir;
+
+type TestResult<T = ()> = StdResult<T, Box<dyn StdError>>;
+
+const FIRA_SANS: &[u8] = include_bytes!("../../assets/fonts/FiraSans-Regular.ttf");
+const FIRA_SANS_BOLD: &[u8] = include_bytes!("../../assets/fonts/FiraSans-Bold.ttf");
+
+fn font_config(fonts_dir: &Path, font_manifest: Option<FontManifest>) -> TestResult<AppConfig> {
+    Ok(AppConfig {
+        state: AppState {
+            fonts_dir: Some(fonts_dir.canonicalize()?),
+            font_manifest,
+            ..AppState::default()
+        },
+        ..AppConfig::default()
+    })
+}
+
+/// POST a `/render/png` body naming `font_paths`, served from `fonts_dir`.
+async fn render_with_fonts(
+    fonts_dir: &Path,
+    font_paths: &[&str],
+) -> TestResult<(StatusCode, Value)> {
+    render_with_config(&font_config(fonts_dir, None)?, font_paths).await
+}
+
+async fn render_with_config(
+    config: &AppConfig,
+    font_paths: &[&str],
+) -> TestResult<(StatusCode, Value)> {
+    let client = TestClient::new(create_app(config));
+    let body = json!({
+        "html": "<div>fonts</div>",
+        "width": 16,
+        "height": 16,
+        "font_paths": font_paths
+    })
+    .to_string();
+
+    let response = client
+        .post("/render/png")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await;
+    let status = response.0.status();
+    let bytes = response.0.into_body().into_vec().await?;
+    let error = if status.is_success() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&bytes)?
+    };
+    Ok((status, error))
+}
+
+/// `(font, reason)` pairs from an error body's `font_problems`.
+fn font_problems(error: &Value) -> Vec<(&str, &str)> {
+    error
+        .get("font_problems")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|problem| {
+            Some((
+                problem.get("font")?.as_str()?,
+                problem.get("reason")?.as_str()?,
+            ))
+        })
+        .collect()
+}
+
+#[test]
+fn scan_stops_at_max_fonts_scanned() -> TestResult {
+    let dir = tempdir()?;
+    fs::create_dir_all(dir.path().join("brand"))?;
+    for name in ["a.ttf", "b.otf", "notes.txt", "brand/c.ttf"] {
+        fs::write(dir.path().join(name), b"font")?;
+    }
+
+    let scan = scan_fonts_dir(dir.path(), 2);
+
+    if !scan.truncated {
+        return Err("scan should report truncation".into());
+    }
+    if scan.fonts != ["a.ttf", "b.otf"] {
+        return Err(format!("unexpected fonts: {:?}", scan.fonts).into());
+    }
+    Ok(())
+}
+
+#[test]
+fn catalog_caches_scan_without_refresh_interval() -> TestResult {
+    let dir = tempdir()?;
+    fs::write(dir.path().join("a.ttf"), b"font")?;
+
+    let catalog = FontCatalog::new(dir.path().to_path_buf(), FontScanConfig::default());
+    fs::write(dir.path().join("b.ttf"), b"font")?;
+
+    let scan = catalog.fonts();
+    if scan.fonts != ["a.ttf"] || scan.truncated {
+        return Err(format!("catalog should serve the startup scan: {scan:?}").into());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn unusable_fonts_are_itemized_in_one_error() -> TestResult {
+    let dir = tempdir()?;
+    fs::write(dir.path().join("brand.ttf"), FIRA_SANS)?;
+
+    let (status, error) = render_with_fonts(
+        dir.path(),
+        &[
+            "brand.ttf",
+            "missing.ttf",
+            "../etc/passwd",
+            "..",
+            "gone.otf",
+        ],
+    )
+    .await?;
+
+    let expected = [
+        ("missing.ttf", "not_found"),
+        ("../etc/passwd", "path_traversal"),
+        ("..", "path_traversal"),
+        ("gone.otf", "not_found"),
+    ];
+    if status != StatusCode::BAD_REQUEST || font_problems(&error) != expected {
+        return Err(format!("unexpected response: {status} {error}").into());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn fonts_resolve_from_subdirectories() -> TestResult {
+    let dir = tempdir()?;
+    fs::create_dir_all(dir.path().join("brand"))?;
+    fs::write(dir.path().join("brand/Inter.ttf"), FIRA_SANS)?;
+
+    let (status, error) = render_with_fonts(dir.path(), &["brand/Inter.ttf"]).await?;
+
+    if status != StatusCode::OK {
+        return Err(format!("unexpected response: {status} {error}").into());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn subdirectory_fonts_cannot_escape_fonts_dir() -> TestResult {
+    let root = tempdir()?;
+    let fonts_dir = root.path().join("fonts");
+    fs::create_dir_all(fonts_dir.join("brand"))?;
+    fs::write(root.path().join("secret.ttf"), FIRA_SANS)?;
+    let outside = root.path().join("secret.ttf");
+    let outside = outside.to_str().ok_or("temp path is not UTF-8")?;
+
+    let (status, error) =
+        render_with_fonts(&fonts_dir, &["brand/../../secret.ttf", outside]).await?;
+
+    let expected = [
+        ("brand/../../secret.ttf", "path_traversal"),
+        (outside, "path_traversal"),
+    ];
+    if status != StatusCode::BAD_REQUEST || font_problems(&error) != expected {
+        return Err(format!("unexpected response: {status} {error}").into());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn font_manifest_exposes_aliases_only() -> TestResult {
+    let dir = tempdir()?;
+    fs::create_dir_all(dir.path().join("brand"))?;
+    fs::write(dir.path().join("brand/Inter-Bold.ttf"), FIRA_SANS)?;
+    let manifest = FontManifest::from([
+        ("inter-bold".to_owned(), "brand/Inter-Bold.ttf".to_owned()),
+        ("ghost".to_owned(), "Ghost.ttf".to_owned()),
+    ]);
+    let config = font_config(dir.path(), Some(manifest))?;
+
+    let listed = TestClient::new(create_app(&config))
+        .get("/fonts")
+        .send()
+        .await;
+    listed.assert_status_is_ok();
+    listed
+        .assert_json(json!({ "fonts": ["ghost", "inter-bold"], "truncated": false }))
+        .await;
+
+    let (missing_status, missing) = render_with_config(&config, &["ghost"]).await?;
+    if missing_status != StatusCode::BAD_REQUEST
+        || font_problems(&missing) != [("ghost", "not_found")]
+    {
+        return Err(format!("unexpected response: {missing_status} {missing}").into());
+    }
+    for unknown in ["brand/Inter-Bold.ttf", "inter-regular"] {
+        let (status, error) = render_with_config(&config, &[unknown]).await?;
+        let message = error.get("error").and_then(Value::as_str);
+        if status != StatusCode::BAD_REQUEST
+            || message != Some("font usage is not allowed on this server")
+        {
+            return Err(format!("{unknown} should be rejected: {status} {error}").into());
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn too_many_font_paths_are_rejected_before_resolving() -> TestResult {
+    let dir = tempdir()?;
+    let config = AppConfig {
+        limits: AppLimits {
+            max_fonts_per_request: 2,
+            ..AppLimits::default()
+        },
+        ..font_config(dir.path(), None)?
+    };
+
+    let (_, at_limit) = render_with_config(&config, &["missing.ttf"; 2]).await?;
+    let (status, error) = render_with_config(&config, &["missing.ttf"; 3]).await?;
+
+    if font_problems(&at_limit).len() != 2 {
+        return Err(format!("fonts at the limit should be resolved: {at_limit}").into());
+    }
+    let message = error.get("error").and_then(Value::as_str);
+    if status != StatusCode::BAD_REQUEST
+        || message != Some("invalid request: font_paths may name at most 2 fonts, got 3")
+        || !font_problems(&error).is_empty()
+    {
+        return Err(format!("unexpected response: {status} {error}").into());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn replacing_a_font_file_changes_the_etag() -> TestResult {
+    let dir = tempdir()?;
+    fs::write(dir.path().join("brand.ttf"), FIRA_SANS)?;
+    let client = TestClient::new(create_app(&font_config(dir.path(), None)?));
+    let body = json!({
+        "html": "<div>fonts</div>",
+        "width": 16,
+        "height": 16,
+        "font_paths": ["brand.ttf"]
+    })
+    .to_string();
+    let render = |if_none_match: &str| {
+        client
+            .post("/render/png")
+            .header("content-length", body.len())
+            .header("content-type", "application/json")
+            .header("if-none-match", if_none_match)
+            .body(body.clone())
+            .send()
+    };
+
+    let first = render("\"none\"").await;
+    first.assert_status_is_ok();
+    let etag = first
+        .0
+        .headers()
+        .get("etag")
+        .and_then(|value| value.to_str().ok())
+        .ok_or("the PNG has no ETag")?
+        .to_owned();
+    render(&etag).await.assert_status(StatusCode::NOT_MODIFIED);
+
+    fs::write(dir.path().join("brand.ttf"), FIRA_SANS_BOLD)?;
+    render(&etag).await.assert_status_is_ok();
+    Ok(())
+}
@@ -0,0 +1,173 @@
+#![allow(
+    unused_crate_dependencies,
+    reason = "Integration test does not exercise all package-level dependencies."
+)]
+#![allow(
+    clippy::tests_outside_test_module,
+    reason = "Integration test crate is the test module."
+)]
+
+use std::{
+    collections::HashSet, error::Error as StdError, result::Result as StdResult, time::Duration,
+};
+
+use html_to_image_server::{AppConfig, create_app};
+use poem::{
+    Request, Server,
+    endpoint::make,
+    http::StatusCode,
+    listener::{Acceptor, Listener, TcpListener},
+    test::TestClient,
+};
+use serde_json::{Value, json};
+use tokio::{sync::mpsc, task, time};
+
+type TestResult<T = ()> = StdResult<T, Box<dyn StdError>>;
+
+/// Serve a callback receiver on a free local port, returning its URL and received bodies.
+async fn callback_receiver() -> TestResult<(String, mpsc::Receiver<Vec<u8>>)> {
+    let acceptor = TcpListener::bind("127.0.0.1:0").into_acceptor().await?;
+    let addr = acceptor
+        .local_addr()
+        .first()
+        .and_then(|addr| addr.as_socket_addr().copied())
+        .ok_or("receiver has no socket address")?;
+    let (sender, received) = mpsc::channel(1);
+    let endpoint = make(move |req: Request| {
+        let sender = sender.clone();
+        async move {
+            let body = req.into_body().into_vec().await.unwrap_or_default();
+            sender.send(body).await.ok();
+            StatusCode::OK
+        }
+    });
+    task::spawn(Server::new_with_acceptor(acceptor).run(endpoint));
+    Ok((format!("http://{addr}/done"), received))
+}
+
+#[tokio::test]
+async fn async_render_posts_result_to_callback() -> TestResult {
+    let (callback_url, mut received) = callback_receiver().await?;
+    let client = TestClient::new(create_app(&AppConfig {
+        callback_allowed_hosts: Some(HashSet::from(["127.0.0.1".to_owned()])),
+        ..AppConfig::default()
+    }));
+    let body = json!({
+        "html": "<div>async</div>",
+        "width": 24,
+        "height": 12,
+        "callback_url": callback_url
+    })
+    .to_string();
+
+    let response = client
+        .post("/render/async")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await;
+    response.assert_status(StatusCode::ACCEPTED);
+    let job: Value = serde_json::from_slice(&response.0.into_body().into_vec().await?)?;
+    let id = job.get("id").and_then(Value::as_str).ok_or("missing id")?;
+
+    let delivered = time::timeout(Duration::from_secs(10), received.recv())
+        .await?
+        .ok_or("receiver closed")?;
+    let callback: Value = serde_json::from_slice(&delivered)?;
+    let summary = (
+        callback.get("id").and_then(Value::as_str),
+        callback.get("status").and_then(Value::as_str),
+        callback.get("width").and_then(Value::as_u64),
+        callback.get("image").is_some_and(Value::is_string),
+    );
+    if summary != (Some(id), Some("succeeded"), Some(24), true) {
+        return Err(format!("unexpected callback: {summary:?}").into());
+    }
+
+    let status = client.get(format!("/render/async/{id}")).send().await;
+    status.assert_status_is_ok();
+    let status: Value = serde_json::from_slice(&status.0.into_body().into_vec().await?)?;
+    match status.get("status").and_then(Value::as_str) {
+        Some("succeeded") => Ok(()),
+        other => Err(format!("unexpected job status: {other:?}").into()),
+    }
+}
+
+#[tokio::test]
+async fn async_render_rejects_non_http_callbacks() {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+    let body = json!({
+        "html": "<div>async</div>",
+        "width": 24,
+        "height": 12,
+        "callback_url": "file:///etc/passwd"
+    })
+    .to_string();
+
+    client
+        .post("/render/async")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    client
+        .get("/render/async/unknown-job")
+        .send()
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn async_render_rejects_internal_callbacks() {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+    for callback_url in [
+        "http://127.0.0.1:9/hook",
+        "http://169.254.169.254/latest/meta-data",
+        "http://10.0.0.1/hook",
+        "http://[::1]/hook",
+    ] {
+        let body = json!({
+            "html": "<div>async</div>",
+            "width": 24,
+            "height": 12,
+            "callback_url": callback_url
+        })
+        .to_string();
+
+        client
+            .post("/render/async")
+            .header("content-length", body.len())
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+}
+
+#[tokio::test]
+async fn async_render_rejects_hosts_outside_the_allow_list() {
+    let client = TestClient::new(create_app(&AppConfig {
+        callback_allowed_hosts: Some(HashSet::from(["hooks.example.com".to_owned()])),
+        ..AppConfig::default()
+    }));
+    let body = json!({
+        "html": "<div>async</div>",
+        "width": 24,
+        "height": 12,
+        "callback_url": "http://127.0.0.1:9/hook"
+    })
+    .to_string();
+
+    client
+        .post("/render/async")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+}
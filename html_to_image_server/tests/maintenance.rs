@@ -0,0 +1,122 @@
+#![allow(
+    unused_crate_dependencies,
+    reason = "Integration test does not exercise all package-level dependencies."
+)]
+#![allow(
+    clippy::tests_outside_test_module,
+    reason = "Integration test crate is the test module."
+)]
+
+use std::{error::Error as StdError, fs, result::Result as StdResult, time::Duration};
+
+use html_to_image_server::{AppConfig, AppState, FontScanConfig, create_app};
+use poem::{http::StatusCode, test::TestClient};
+use serde_json::json;
+use tempfile::tempdir;
+use tokio::time;
+
+type TestResult<T = ()> = StdResult<T, Box<dyn StdError>>;
+
+const ADMIN_TOKEN: &str = "test-admin-token";
+
+#[tokio::test]
+async fn maintenance_mode_rejects_renders_until_disabled() {
+    let app = create_app(&AppConfig {
+        admin_token: Some(ADMIN_TOKEN.to_owned()),
+        ..AppConfig::default()
+    });
+    let client = TestClient::new(app);
+
+    let toggle = |enabled: bool, token: &'static str| {
+        let body = json!({ "enabled": enabled }).to_string();
+        client
+            .post("/admin/maintenance")
+            .header("authorization", format!("Bearer {token}"))
+            .header("content-length", body.len())
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+    };
+
+    toggle(true, "wrong-token")
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    toggle(true, ADMIN_TOKEN).await.assert_status_is_ok();
+
+    let render_body = json!({ "html": "<div>hi</div>", "width": 16, "height": 16 }).to_string();
+    client
+        .post("/render/png")
+        .header("content-length", render_body.len())
+        .header("content-type", "application/json")
+        .body(render_body)
+        .send()
+        .await
+        .assert_status(StatusCode::SERVICE_UNAVAILABLE);
+    client.get("/healthz").send().await.assert_status_is_ok();
+    client
+        .get("/readyz")
+        .send()
+        .await
+        .assert_status(StatusCode::SERVICE_UNAVAILABLE);
+
+    toggle(false, ADMIN_TOKEN).await.assert_status_is_ok();
+    client.get("/readyz").send().await.assert_status_is_ok();
+}
+
+#[tokio::test]
+async fn readyz_turns_green_once_fonts_are_scanned() -> TestResult {
+    let dir = tempdir()?;
+    fs::write(dir.path().join("brand.ttf"), b"font")?;
+    let app = create_app(&AppConfig {
+        state: AppState {
+            fonts_dir: Some(dir.path().to_path_buf()),
+            ..AppState::default()
+        },
+        ..AppConfig::default()
+    });
+    let client = TestClient::new(app);
+
+    client.get("/healthz").send().await.assert_status_is_ok();
+    for _ in 0..100 {
+        if client.get("/readyz").send().await.0.status() == StatusCode::OK {
+            client.get("/fonts").send().await.assert_status_is_ok();
+            return Ok(());
+        }
+        time::sleep(Duration::from_millis(20)).await;
+    }
+    Err("/readyz never reported ready".into())
+}
+
+#[tokio::test]
+async fn readyz_reports_loading_fonts_while_the_scan_runs() -> TestResult {
+    let dir = tempdir()?;
+    for index in 0..20_000 {
+        fs::write(dir.path().join(format!("font-{index:05}.ttf")), b"")?;
+    }
+    let config = AppConfig {
+        state: AppState {
+            fonts_dir: Some(dir.path().to_path_buf()),
+            ..AppState::default()
+        },
+        font_scan: FontScanConfig {
+            max_fonts_scanned: usize::MAX,
+            ..FontScanConfig::default()
+        },
+        ..AppConfig::default()
+    };
+
+    // Each app starts its own scan; a fast machine may finish one before the first probe,
+    // so try a few until a probe lands mid-scan.
+    for _ in 0..20 {
+        let client = TestClient::new(create_app(&config));
+        let probe = client.get("/readyz").send().await;
+        if probe.0.status() == StatusCode::OK {
+            continue;
+        }
+        probe.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        probe.assert_text("loading fonts").await;
+        client.get("/healthz").send().await.assert_status_is_ok();
+        return Ok(());
+    }
+    Err("/readyz never reported a scan in progress".into())
+}
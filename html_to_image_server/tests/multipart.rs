@@ -0,0 +1,99 @@
+#![allow(
+    unused_crate_dependencies,
+    reason = "Integration test does not exercise all package-level dependencies."
+)]
+#![allow(
+    clippy::tests_outside_test_module,
+    reason = "Integration test crate is the test module."
+)]
+
+use html_to_image_server::{AppConfig, AppLimits, create_app};
+use poem::{
+    http::StatusCode,
+    test::{TestClient, TestResponse},
+};
+use serde_json::json;
+
+const BOUNDARY: &str = "html-to-image-test-boundary";
+const FIRA_SANS: &[u8] = include_bytes!("../../assets/fonts/FiraSans-Regular.ttf");
+
+/// A `multipart/form-data` body with the render request followed by `fonts` parts.
+fn multipart_body(fonts: &[(&str, &[u8])]) -> Vec<u8> {
+    let request = json!({
+        "html": "<div style=\"font-family: 'Fira Sans'\">uploaded</div>",
+        "width": 64,
+        "height": 32
+    });
+    let mut body = format!(
+        "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"request\"\r\n\
+         Content-Type: application/json\r\n\r\n{request}\r\n"
+    )
+    .into_bytes();
+    for (file_name, data) in fonts {
+        body.extend_from_slice(
+            format!(
+                "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"fonts\"; \
+                 filename=\"{file_name}\"\r\nContent-Type: font/ttf\r\n\r\n"
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(data);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{BOUNDARY}--\r\n").as_bytes());
+    body
+}
+
+async fn post_multipart(config: &AppConfig, body: Vec<u8>) -> TestResponse {
+    TestClient::new(create_app(config))
+        .post("/render/png/multipart")
+        .header("content-length", body.len())
+        .header(
+            "content-type",
+            format!("multipart/form-data; boundary={BOUNDARY}"),
+        )
+        .body(body)
+        .send()
+        .await
+}
+
+#[tokio::test]
+async fn multipart_renders_with_uploaded_font() -> poem::Result<()> {
+    let body = multipart_body(&[("FiraSans-Regular.ttf", FIRA_SANS)]);
+    let response = post_multipart(&AppConfig::default(), body).await;
+
+    response.assert_status(StatusCode::OK);
+    response.assert_content_type("image/png");
+    let bytes = response.0.into_body().into_vec().await?;
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Ok(())
+    } else {
+        Err(poem::Error::from_string(
+            "response should be a PNG",
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ))
+    }
+}
+
+#[tokio::test]
+async fn multipart_rejects_non_font_uploads() {
+    let body = multipart_body(&[("notes.ttf", b"definitely not a font")]);
+    let response = post_multipart(&AppConfig::default(), body).await;
+
+    response.assert_status(StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn multipart_enforces_font_size_limits() {
+    let config = AppConfig {
+        limits: AppLimits {
+            max_font_upload_size: 1024,
+            ..AppLimits::default()
+        },
+        ..AppConfig::default()
+    };
+    let body = multipart_body(&[("FiraSans-Regular.ttf", FIRA_SANS)]);
+    let response = post_multipart(&config, body).await;
+
+    response.assert_status(StatusCode::BAD_REQUEST);
+}
@@ -7,17 +7,24 @@
     reason = "Integration test crate is the test module."
 )]
 
+use std::time::Duration;
+
+use html_to_image::AutoEscapeMode;
 use html_to_image_server::{AppConfig, AppLimits, AppState, DEFAULT_MAX_BODY_SIZE, create_app};
-use poem::{http::StatusCode, test::TestClient};
-use serde_json::json;
+use poem::{
+    http::{Method, StatusCode},
+    test::TestClient,
+};
+use serde_json::{Value, json};
 
 #[tokio::test]
 async fn render_png_endpoint_returns_png() -> poem::Result<()> {
     let app_config = AppConfig {
-        state: AppState { fonts_dir: None },
+        state: AppState::default(),
         limits: AppLimits::default(),
         max_body_size: DEFAULT_MAX_BODY_SIZE,
         server_base_url: None,
+        ..AppConfig::default()
     };
     let app = create_app(&app_config);
     let client = TestClient::new(app);
@@ -50,3 +57,833 @@ async fn render_png_endpoint_returns_png() -> poem::Result<()> {
         ))
     }
 }
+
+#[tokio::test]
+async fn render_png_returns_base64_json_when_requested() -> poem::Result<()> {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+    let body = json!({ "html": "<div>json</div>", "width": 40, "height": 24 }).to_string();
+
+    for (query, accept) in [("base64", "image/png"), ("", "application/json")] {
+        let mut request = client
+            .post("/render/png")
+            .header("content-length", body.len())
+            .header("content-type", "application/json")
+            .header("accept", accept);
+        if !query.is_empty() {
+            request = request.query("encoding", &query);
+        }
+        let response = request.body(body.clone()).send().await;
+
+        response.assert_status(StatusCode::OK);
+        response.assert_content_type("application/json; charset=utf-8");
+        let bytes = response.0.into_body().into_vec().await?;
+        let json: Value = serde_json::from_slice(&bytes)
+            .map_err(|err| test_error(&format!("invalid JSON: {err}")))?;
+        let summary = (
+            json.get("width").and_then(Value::as_u64),
+            json.get("height").and_then(Value::as_u64),
+            json.get("image").is_some_and(Value::is_string),
+            json.get("bytes")
+                .and_then(Value::as_u64)
+                .is_some_and(|len| len > 0),
+        );
+        if summary != (Some(40), Some(24), true, true) {
+            return Err(test_error(&format!(
+                "unexpected JSON render for {accept}: {summary:?}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn render_png_reads_accept_weights_like_negotiation() {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+    let body = json!({ "html": "<div>json</div>", "width": 40, "height": 24 }).to_string();
+
+    for (accept, content_type) in [
+        ("application/json ; q=0, image/png", "image/png"),
+        (
+            " Application/JSON; Q=0.9 , image/png;q=0.5",
+            "application/json; charset=utf-8",
+        ),
+    ] {
+        let response = client
+            .post("/render/png")
+            .header("content-length", body.len())
+            .header("content-type", "application/json")
+            .header("accept", accept)
+            .body(body.clone())
+            .send()
+            .await;
+
+        response.assert_status(StatusCode::OK);
+        response.assert_content_type(content_type);
+    }
+}
+
+#[tokio::test]
+async fn render_png_measures_auto_height() {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+    let html = r#"<body style="margin:0"><div style="height:90px">notice</div></body>"#;
+
+    for body in [
+        json!({ "html": html, "width": 40, "height": 0 }),
+        json!({ "html": html, "width": 40, "height": 500, "auto_height": true }),
+    ] {
+        let body = body.to_string();
+        let response = client
+            .post("/render/png")
+            .header("content-length", body.len())
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+            .await;
+
+        response.assert_status_is_ok();
+        response.assert_header("x-image-height", "90");
+    }
+}
+
+#[tokio::test]
+async fn render_png_reports_warnings_only_when_asked() -> poem::Result<()> {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+    let html = r#"<body><img src="https://example.com/logo.png"></body>"#;
+
+    let mut headers = Vec::new();
+    for collect_warnings in [false, true] {
+        let body = json!({
+            "html": html,
+            "width": 40,
+            "height": 20,
+            "collect_warnings": collect_warnings
+        })
+        .to_string();
+        let response = client
+            .post("/render/png")
+            .header("content-length", body.len())
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+            .await;
+
+        response.assert_status_is_ok();
+        headers.push(
+            response
+                .0
+                .headers()
+                .get("x-render-warnings")
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned),
+        );
+    }
+    match headers.as_slice() {
+        [None, Some(warnings)] if warnings.contains("https://example.com/logo.png") => Ok(()),
+        other => Err(test_error(&format!("unexpected warnings: {other:?}"))),
+    }
+}
+
+#[tokio::test]
+async fn render_jpeg_endpoint_returns_jpeg() -> poem::Result<()> {
+    let app = create_app(&AppConfig::default());
+    let client = TestClient::new(app);
+
+    let body = json!({
+        "html": "<html><body style=\"background:white\"><div>{{ name }}</div></body></html>",
+        "width": 64,
+        "height": 48,
+        "quality": 70,
+        "data": { "name": "Test User" }
+    })
+    .to_string();
+
+    let response = client
+        .post("/render/jpeg")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await;
+
+    response.assert_status(StatusCode::OK);
+    response.assert_content_type("image/jpeg");
+
+    let bytes = response.0.into_body().into_vec().await?;
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Ok(())
+    } else {
+        Err(test_error("response should be a JPEG"))
+    }
+}
+
+#[tokio::test]
+async fn render_jpeg_endpoint_rejects_out_of_range_quality() {
+    let app = create_app(&AppConfig::default());
+    let client = TestClient::new(app);
+
+    let body =
+        json!({ "html": "<div>q</div>", "width": 16, "height": 16, "quality": 0 }).to_string();
+
+    let response = client
+        .post("/render/jpeg")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await;
+
+    response.assert_status(StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn jpeg_quality_outside_the_configured_range_is_rejected() -> poem::Result<()> {
+    let config = AppConfig {
+        limits: AppLimits {
+            min_jpeg_quality: 40,
+            max_jpeg_quality: 90,
+            ..AppLimits::default()
+        },
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+
+    for (quality, status) in [
+        (39, StatusCode::BAD_REQUEST),
+        (91, StatusCode::BAD_REQUEST),
+        (40, StatusCode::OK),
+        (90, StatusCode::OK),
+    ] {
+        let body = json!({ "html": "<div>q</div>", "width": 16, "height": 16, "quality": quality })
+            .to_string();
+        let response = client
+            .post("/render/jpeg")
+            .header("content-length", body.len())
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+            .await;
+
+        response.assert_status(status);
+        if status == StatusCode::BAD_REQUEST {
+            let error: Value = serde_json::from_slice(&response.0.into_body().into_vec().await?)
+                .map_err(|err| test_error(&format!("invalid JSON: {err}")))?;
+            let message = error
+                .get("error")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            if !message.contains("between 40 and 90") {
+                return Err(test_error(&format!("unclear message: {message}")));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn render_endpoint_negotiates_format_from_accept() -> poem::Result<()> {
+    let app = create_app(&AppConfig::default());
+    let client = TestClient::new(app);
+    let body = json!({ "html": "<div>accept</div>", "width": 32, "height": 16 }).to_string();
+
+    let cases: [(&str, &str, &[u8]); 4] = [
+        ("image/jpeg", "image/jpeg", &[0xFF, 0xD8, 0xFF]),
+        ("image/webp, image/png;q=0.5", "image/webp", b"RIFF"),
+        ("*/*", "image/png", b"\x89PNG\r\n\x1a\n"),
+        (
+            "text/html, image/*;q=0.8",
+            "image/png",
+            b"\x89PNG\r\n\x1a\n",
+        ),
+    ];
+    for (accept, content_type, magic) in cases {
+        let response = client
+            .post("/render")
+            .header("accept", accept)
+            .header("content-length", body.len())
+            .header("content-type", "application/json")
+            .body(body.clone())
+            .send()
+            .await;
+
+        response.assert_status(StatusCode::OK);
+        response.assert_content_type(content_type);
+        let bytes = response.0.into_body().into_vec().await?;
+        if !bytes.starts_with(magic) {
+            return Err(test_error(&format!(
+                "Accept: {accept} returned the wrong format"
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn render_endpoint_rejects_unsupported_accept() {
+    let app = create_app(&AppConfig::default());
+    let client = TestClient::new(app);
+    let body = json!({ "html": "<div>accept</div>", "width": 32, "height": 16 }).to_string();
+
+    let response = client
+        .post("/render")
+        .header("accept", "application/pdf")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await;
+
+    response.assert_status(StatusCode::NOT_ACCEPTABLE);
+}
+
+#[tokio::test]
+async fn render_exceeding_timeout_returns_gateway_timeout() {
+    let config = AppConfig {
+        limits: AppLimits {
+            render_timeout: Duration::ZERO,
+            ..AppLimits::default()
+        },
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+    // Large enough that painting and encoding cannot finish before the first poll.
+    let body = json!({ "html": "<div>slow</div>", "width": 1024, "height": 1024 }).to_string();
+
+    let response = client
+        .post("/render/png")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await;
+
+    response.assert_status(StatusCode::GATEWAY_TIMEOUT);
+}
+
+#[tokio::test]
+async fn template_expanding_past_the_html_limit_is_rejected() {
+    let config = AppConfig {
+        limits: AppLimits {
+            max_rendered_html: 1024,
+            ..AppLimits::default()
+        },
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+    let body = json!({
+        "html": "{% for _ in range(10000) %}<p>row</p>{% endfor %}",
+        "width": 64,
+        "height": 48
+    })
+    .to_string();
+
+    for path in ["/render/png", "/render/html"] {
+        client
+            .post(path)
+            .header("content-length", body.len())
+            .header("content-type", "application/json")
+            .body(body.clone())
+            .send()
+            .await
+            .assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
+
+#[tokio::test]
+async fn oversized_body_gets_a_json_413() -> poem::Result<()> {
+    let config = AppConfig {
+        max_body_size: 64,
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+    let body = json!({ "html": "x".repeat(128), "width": 16, "height": 16 }).to_string();
+
+    let response = client
+        .post("/render/png")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await;
+
+    response.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+    response.assert_content_type("application/json; charset=utf-8");
+    let bytes = response.0.into_body().into_vec().await?;
+    let json: Value = serde_json::from_slice(&bytes)
+        .map_err(|err| test_error(&format!("invalid JSON: {err}")))?;
+    let error = json.get("error").and_then(Value::as_str);
+    if error != Some("request body exceeds the limit of 64 bytes") {
+        return Err(test_error(&format!("unexpected error body: {json}")));
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn get_render_maps_query_params_when_enabled() -> poem::Result<()> {
+    let config = AppConfig {
+        allow_get_render: true,
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+
+    let response = client
+        .get("/render/png")
+        .query("html", &"<div>linked</div>")
+        .query("width", &40)
+        .query("height", &20)
+        .send()
+        .await;
+
+    response.assert_status(StatusCode::OK);
+    let bytes = response.0.into_body().into_vec().await?;
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Ok(())
+    } else {
+        Err(test_error("response should be a PNG"))
+    }
+}
+
+#[tokio::test]
+async fn head_render_sends_the_png_metadata_without_a_body() -> poem::Result<()> {
+    let config = AppConfig {
+        allow_get_render: true,
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+    let request = |method| {
+        client
+            .request(method, "/render/png")
+            .query("html", &"<div>linked</div>")
+            .query("width", &40)
+            .query("height", &20)
+            .send()
+    };
+
+    let head = request(Method::HEAD).await;
+    head.assert_status(StatusCode::OK);
+    head.assert_header("x-image-width", "40");
+    head.assert_header("x-image-height", "20");
+    let header = |name| {
+        head.0
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned)
+    };
+    let (length, etag) = (header("content-length"), header("etag"));
+    if !head.0.into_body().into_vec().await?.is_empty() {
+        return Err(test_error("HEAD should not send the PNG"));
+    }
+
+    let get = request(Method::GET).await;
+    get.assert_status(StatusCode::OK);
+    let get_etag = get.0.headers().get("etag").cloned();
+    let png = get.0.into_body().into_vec().await?;
+    if length != Some(png.len().to_string())
+        || etag.is_none()
+        || get_etag.as_ref().and_then(|tag| tag.to_str().ok()) != etag.as_deref()
+    {
+        return Err(test_error(&format!(
+            "HEAD sent length {length:?} and ETag {etag:?} for a {}-byte PNG tagged {get_etag:?}",
+            png.len()
+        )));
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn get_render_is_disabled_by_default() {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+
+    let response = client
+        .get("/render/png")
+        .query("html", &"<div>linked</div>")
+        .query("width", &40)
+        .query("height", &20)
+        .send()
+        .await;
+
+    response.assert_status(StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn render_card_endpoint_returns_png() -> poem::Result<()> {
+    let app = create_app(&AppConfig::default());
+    let client = TestClient::new(app);
+
+    let body = json!({ "name": "Card User", "seed": 7 }).to_string();
+
+    let response = client
+        .post("/render/card")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await;
+
+    response.assert_status(StatusCode::OK);
+
+    let bytes = response.0.into_body().into_vec().await?;
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Ok(())
+    } else {
+        Err(poem::Error::from_string(
+            "response should be a PNG",
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ))
+    }
+}
+
+#[tokio::test]
+async fn render_thumbnail_endpoint_returns_both_parts() -> poem::Result<()> {
+    let app = create_app(&AppConfig::default());
+    let client = TestClient::new(app);
+
+    let body = json!({
+        "html": "<html><body><div>{{ name }}</div></body></html>",
+        "width": 200,
+        "height": 100,
+        "thumbnail_width": 50,
+        "data": { "name": "Thumb" }
+    })
+    .to_string();
+
+    let response = client
+        .post("/render/png/thumbnail")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await;
+
+    response.assert_status(StatusCode::OK);
+    let content_type = response
+        .0
+        .headers()
+        .get("content-type")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_owned();
+    let boundary = content_type
+        .split("boundary=")
+        .nth(1)
+        .ok_or_else(|| test_error("missing multipart boundary"))?
+        .to_owned();
+
+    let bytes = response.0.into_body().into_vec().await?;
+    let widths = multipart_png_widths(&bytes, &boundary);
+    match widths.as_slice() {
+        [thumbnail, full] if thumbnail < full && *full == 200 => Ok(()),
+        other => Err(test_error(&format!("unexpected part widths: {other:?}"))),
+    }
+}
+
+#[tokio::test]
+async fn thumbnail_width_is_clamped_to_the_image_width() -> poem::Result<()> {
+    let app = create_app(&AppConfig::default());
+    let client = TestClient::new(app);
+
+    // The default thumbnail width (160) is wider than the image.
+    let body = json!({
+        "html": "<html><body><div>narrow</div></body></html>",
+        "width": 100,
+        "height": 50
+    })
+    .to_string();
+
+    let response = client
+        .post("/render/png/thumbnail")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await;
+
+    response.assert_status(StatusCode::OK);
+    let content_type = response
+        .0
+        .headers()
+        .get("content-type")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_owned();
+    let boundary = content_type
+        .split("boundary=")
+        .nth(1)
+        .ok_or_else(|| test_error("missing multipart boundary"))?
+        .to_owned();
+
+    let bytes = response.0.into_body().into_vec().await?;
+    let widths = multipart_png_widths(&bytes, &boundary);
+    match widths.as_slice() {
+        [100, 100] => Ok(()),
+        other => Err(test_error(&format!("unexpected part widths: {other:?}"))),
+    }
+}
+
+#[tokio::test]
+async fn render_html_endpoint_returns_expanded_template() -> poem::Result<()> {
+    let app = create_app(&AppConfig::default());
+    let client = TestClient::new(app);
+
+    let body = json!({
+        "html": "<p>{{ name }} at {{ width }}px</p>",
+        "width": 64,
+        "height": 48,
+        "data": { "name": "<Dump>" }
+    })
+    .to_string();
+
+    let response = client
+        .post("/render/html")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await;
+
+    response.assert_status(StatusCode::OK);
+    response.assert_text("<p>&lt;Dump&gt; at 64px</p>").await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn strict_variables_rejects_undefined_template_variables() {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+    for (strict, status) in [(false, StatusCode::OK), (true, StatusCode::BAD_REQUEST)] {
+        let body = json!({
+            "html": "<p>{{ nmae }}</p>",
+            "width": 64,
+            "height": 48,
+            "data": { "name": "Ada" },
+            "strict_variables": strict
+        })
+        .to_string();
+
+        client
+            .post("/render/html")
+            .header("content-length", body.len())
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .assert_status(status);
+    }
+}
+
+#[tokio::test]
+async fn app_config_sets_template_escaping_and_strictness() {
+    let config = AppConfig {
+        template_auto_escape: AutoEscapeMode::None,
+        template_undefined_strict: true,
+        ..AppConfig::default()
+    };
+    let client = TestClient::new(create_app(&config));
+    for (html, status) in [
+        ("<p>{{ name }}</p>", StatusCode::OK),
+        ("<p>{{ nmae }}</p>", StatusCode::BAD_REQUEST),
+    ] {
+        let body = json!({
+            "html": html,
+            "width": 64,
+            "height": 48,
+            "data": { "name": "<b>Ada</b>" }
+        })
+        .to_string();
+
+        let response = client
+            .post("/render/html")
+            .header("content-length", body.len())
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+            .await;
+
+        response.assert_status(status);
+        if status == StatusCode::OK {
+            response.assert_text("<p><b>Ada</b></p>").await;
+        }
+    }
+}
+
+#[tokio::test]
+async fn validate_reports_template_errors_without_rendering() -> poem::Result<()> {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+    let validate = |html: &str| {
+        let body = json!({
+            "html": html,
+            "width": 64,
+            "height": 48,
+            "data": { "name": "Ada" }
+        })
+        .to_string();
+        client
+            .post("/render/validate")
+            .header("content-length", body.len())
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+    };
+
+    let valid = validate("<p>{{ name }}</p>").await;
+    valid.assert_status_is_ok();
+    valid
+        .assert_json(json!({ "valid": true, "template_error": null, "warnings": [] }))
+        .await;
+
+    let invalid = validate("<p>\n{% if %}</p>").await;
+    invalid.assert_status_is_ok();
+    let json: Value = invalid.0.into_body().into_json().await?;
+    if json.get("valid") != Some(&json!(false))
+        || json.pointer("/template_error/line") != Some(&json!(2))
+    {
+        return Err(test_error(&format!("unexpected validation: {json}")));
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn etag_round_trips_to_not_modified() -> poem::Result<()> {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+    let body =
+        json!({ "html": "<div>{{ n }}</div>", "width": 24, "height": 16, "data": { "n": 1 } })
+            .to_string();
+    let render = |if_none_match: Option<&str>, payload: &str| {
+        let mut request = client
+            .post("/render/png")
+            .header("content-length", payload.len())
+            .header("content-type", "application/json");
+        if let Some(tag) = if_none_match {
+            request = request.header("if-none-match", tag);
+        }
+        request.body(payload.to_owned()).send()
+    };
+
+    let first = render(None, &body).await;
+    first.assert_status(StatusCode::OK);
+    let etag = first
+        .0
+        .headers()
+        .get("etag")
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| test_error("the PNG has no ETag"))?
+        .to_owned();
+
+    let unchanged = render(Some(&format!("\"other\", W/{etag}")), &body).await;
+    unchanged.assert_status(StatusCode::NOT_MODIFIED);
+    unchanged.assert_header("etag", etag.as_str());
+    let bytes = unchanged.0.into_body().into_vec().await?;
+    if !bytes.is_empty() {
+        return Err(test_error("a 304 should have no body"));
+    }
+
+    let changed_body = body.replace("\"n\":1", "\"n\":2");
+    let changed = render(Some(&etag), &changed_body).await;
+    changed.assert_status(StatusCode::OK);
+    if changed
+        .0
+        .headers()
+        .get("etag")
+        .is_none_or(|tag| tag == etag.as_str())
+    {
+        return Err(test_error("changed data should get a new ETag"));
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn etag_ignores_collect_warnings() -> poem::Result<()> {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+    let render = |collect_warnings: bool, if_none_match: &str| {
+        let body = json!({
+            "html": "<div>warnings</div>",
+            "width": 24,
+            "height": 16,
+            "collect_warnings": collect_warnings
+        })
+        .to_string();
+        client
+            .post("/render/png")
+            .header("content-length", body.len())
+            .header("content-type", "application/json")
+            .header("if-none-match", if_none_match)
+            .body(body)
+            .send()
+    };
+
+    let plain = render(false, "\"none\"").await;
+    plain.assert_status(StatusCode::OK);
+    let etag = plain
+        .0
+        .headers()
+        .get("etag")
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| test_error("the PNG has no ETag"))?
+        .to_owned();
+
+    render(true, &etag)
+        .await
+        .assert_status(StatusCode::NOT_MODIFIED);
+    Ok(())
+}
+
+#[tokio::test]
+async fn spec_declares_render_images_as_binary_strings() -> poem::Result<()> {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+
+    let response = client.get("/spec").send().await;
+    response.assert_status(StatusCode::OK);
+    let bytes = response.0.into_body().into_vec().await?;
+    let spec: Value = serde_json::from_slice(&bytes)
+        .map_err(|err| test_error(&format!("invalid JSON: {err}")))?;
+
+    let content = spec
+        .pointer("/paths/~1render~1png/post/responses/200/content")
+        .ok_or_else(|| test_error("the render response has no 200 content"))?;
+    let binary = json!({ "type": "string", "format": "binary" });
+    for content_type in ["image/png", "image/jpeg", "image/webp"] {
+        let schema = content
+            .get(content_type)
+            .and_then(|media| media.get("schema"));
+        if schema != Some(&binary) {
+            return Err(test_error(&format!(
+                "{content_type} should be a binary string, got {schema:?}"
+            )));
+        }
+    }
+    if content.get("application/json; charset=utf-8").is_none() {
+        return Err(test_error(&format!("missing the JSON envelope: {content}")));
+    }
+    Ok(())
+}
+
+/// PNG widths (from each part's IHDR chunk) in multipart order.
+fn multipart_png_widths(body: &[u8], boundary: &str) -> Vec<u32> {
+    let delimiter = format!("--{boundary}");
+    let mut widths = Vec::new();
+    let mut rest = body;
+    while let Some(start) = find(rest, delimiter.as_bytes()) {
+        rest = rest.get(start + delimiter.len()..).unwrap_or_default();
+        let Some(header_end) = find(rest, b"\r\n\r\n") else {
+            break;
+        };
+        let part = rest.get(header_end + 4..).unwrap_or_default();
+        if part.starts_with(b"\x89PNG\r\n\x1a\n")
+            && let Some(width) = part
+                .get(16..20)
+                .and_then(|raw| <[u8; 4]>::try_from(raw).ok())
+        {
+            widths.push(u32::from_be_bytes(width));
+        }
+    }
+    widths
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn test_error(message: &str) -> poem::Error {
+    poem::Error::from_string(message, StatusCode::INTERNAL_SERVER_ERROR)
+}
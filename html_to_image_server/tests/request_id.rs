@@ -0,0 +1,62 @@
+#![allow(
+    unused_crate_dependencies,
+    reason = "Integration test does not exercise all package-level dependencies."
+)]
+#![allow(
+    clippy::tests_outside_test_module,
+    reason = "Integration test crate is the test module."
+)]
+
+use html_to_image_server::{AppConfig, create_app};
+use poem::{http::StatusCode, test::TestClient};
+use serde_json::{Value, json};
+
+#[tokio::test]
+async fn request_id_is_echoed_in_header_and_error_body() -> poem::Result<()> {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+    let body = json!({ "html": "<div>x</div>", "width": 0, "height": 16 }).to_string();
+
+    let response = client
+        .post("/render/png")
+        .header("x-request-id", "client-id-42")
+        .header("content-length", body.len())
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await;
+
+    response.assert_status(StatusCode::BAD_REQUEST);
+    response.assert_header("x-request-id", "client-id-42");
+    let bytes = response.0.into_body().into_vec().await?;
+    let json: Value = serde_json::from_slice(&bytes)
+        .map_err(|err| test_error(&format!("invalid JSON: {err}")))?;
+    match json.get("request_id").and_then(Value::as_str) {
+        Some("client-id-42") => Ok(()),
+        other => Err(test_error(&format!("unexpected request_id: {other:?}"))),
+    }
+}
+
+#[tokio::test]
+async fn request_id_is_generated_when_missing() -> poem::Result<()> {
+    let client = TestClient::new(create_app(&AppConfig::default()));
+
+    let response = client.get("/healthz").send().await;
+
+    response.assert_status_is_ok();
+    let request_id = response
+        .0
+        .headers()
+        .get("x-request-id")
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| test_error("missing x-request-id"))?;
+    let groups: Vec<usize> = request_id.split('-').map(str::len).collect();
+    if groups == [8, 4, 4, 4, 12] {
+        Ok(())
+    } else {
+        Err(test_error(&format!("not a UUID: {request_id}")))
+    }
+}
+
+fn test_error(message: &str) -> poem::Error {
+    poem::Error::from_string(message.to_owned(), StatusCode::INTERNAL_SERVER_ERROR)
+}
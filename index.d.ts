@@ -1,5 +1,18 @@
 /* auto-generated by NAPI-RS */
 /* eslint-disable */
+export interface RenderBufferRequest {
+  templatePath: string
+  width: number
+  height: number
+  /** Arbitrary JSON-like object from TS (Record<string, any>) */
+  data: any
+  /** Optional render tuning */
+  scale?: number
+  animationTime?: number
+  /** Optional extra fonts (paths on disk) */
+  fontPaths?: Array<string>
+}
+
 export interface RenderRequest {
   templatePath: string
   outPath: string
@@ -14,4 +27,92 @@ export interface RenderRequest {
   fontPaths?: Array<string>
 }
 
-export declare function renderTemplateToPng(req: RenderRequest): Promise<void>
+/** Options for [`render_html_to_png`]: final HTML is passed separately, no template. */
+export interface RenderHtmlRequest {
+  outPath: string
+  width: number
+  height: number
+  /** Optional render tuning */
+  scale?: number
+  animationTime?: number
+  /** Optional extra fonts (paths on disk) */
+  fontPaths?: Array<string>
+}
+
+/** Like [`RenderHtmlRequest`], without `out_path`: the PNG is returned instead of written. */
+export interface RenderHtmlBufferRequest {
+  width: number
+  height: number
+  /** Optional render tuning */
+  scale?: number
+  animationTime?: number
+  /** Optional extra fonts (paths on disk) */
+  fontPaths?: Array<string>
+}
+
+export interface BatchResult {
+  /** Position of the item in the request */
+  index: number
+  result?: RenderResult
+  /** Why the item failed */
+  error?: string
+}
+
+export interface RenderResult {
+  /** Image width in pixels */
+  width: number
+  /** Image height in pixels */
+  height: number
+  /** Size of the written PNG in bytes */
+  bytes: number
+  /** Wall time of the render and write, in milliseconds */
+  durationMs: number
+}
+
+/**
+ * Render a `MiniJinja` HTML template to a PNG on disk.
+ *
+ * Aborting `signal` rejects the promise with an `AbortError`; a render already painting
+ * still finishes in the background and its result is discarded.
+ */
+export declare function renderTemplateToPng(req: RenderRequest, signal?: AbortSignal | undefined | null): Promise<void>
+
+/**
+ * Render a `MiniJinja` HTML template to a PNG on disk and report what was rendered.
+ *
+ * Aborting `signal` rejects the promise with an `AbortError`; a render already painting
+ * still finishes in the background and its result is discarded.
+ */
+export declare function renderTemplateToPngResult(req: RenderRequest, signal?: AbortSignal | undefined | null): Promise<RenderResult>
+
+/**
+ * Render a `MiniJinja` HTML template to PNG bytes returned as a Node `Buffer`.
+ *
+ * Aborting `signal` rejects the promise with an `AbortError`; a render already painting
+ * still finishes in the background and its result is discarded.
+ */
+export declare function renderTemplateToPngBuffer(req: RenderBufferRequest, signal?: AbortSignal | undefined | null): Promise<Buffer>
+
+/**
+ * Render final HTML held in memory to a PNG on disk, without a template file.
+ *
+ * Aborting `signal` rejects the promise with an `AbortError`; a render already painting
+ * still finishes in the background and its result is discarded.
+ */
+export declare function renderHtmlToPng(html: string, opts: RenderHtmlRequest, signal?: AbortSignal | undefined | null): Promise<void>
+
+/**
+ * Render final HTML held in memory to PNG bytes returned as a Node `Buffer`.
+ *
+ * Aborting `signal` rejects the promise with an `AbortError`; a render already painting
+ * still finishes in the background and its result is discarded.
+ */
+export declare function renderHtmlToPngBuffer(html: string, opts: RenderHtmlBufferRequest, signal?: AbortSignal | undefined | null): Promise<Buffer>
+
+/**
+ * Render many `MiniJinja` templates to PNGs on disk in one call.
+ *
+ * Aborting `signal` rejects the promise with an `AbortError`; a render already painting
+ * still finishes in the background and its result is discarded.
+ */
+export declare function renderBatch(items: Array<RenderRequest>, signal?: AbortSignal | undefined | null): Promise<Array<BatchResult>>